@@ -0,0 +1,212 @@
+use crate::util::{self, PiHoleServer};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Number of alerts kept in the history, the oldest cleared alerts are dropped first
+const HISTORY_LENGTH: usize = 100;
+
+/// The `alerts` section of the config, every rule is off unless set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Raised while the share of blocked queries of a server is above this percentage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ads_percentage_above: Option<f64>,
+    /// Raised while a server has answered fewer queries today
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queries_below: Option<u64>,
+    /// Raised once a server could not be updated for this many minutes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unreachable_minutes: Option<u64>,
+    /// Raised while blocking of a server is not enabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub status_not_enabled: bool,
+    /// Raised once blocking of a server has been disabled for this many minutes, for a
+    /// disable which was meant to be short
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled_minutes: Option<u64>,
+    /// Raised while the core, web interface or FTL of a server can be updated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub update_available: bool,
+}
+
+impl AlertsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.ads_percentage_above.is_none()
+            && self.queries_below.is_none()
+            && self.unreachable_minutes.is_none()
+            && !self.status_not_enabled
+            && self.disabled_minutes.is_none()
+            && !self.update_available
+    }
+}
+
+/// Rule an alert was raised by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertKind {
+    AdsPercentage,
+    FewQueries,
+    Unreachable,
+    NotEnabled,
+    LeftDisabled,
+    UpdateAvailable,
+}
+
+pub struct Alert {
+    pub server: String,
+    pub kind: AlertKind,
+    /// What held when the alert was raised
+    pub message: String,
+    pub raised_at: DateTime<Local>,
+    /// When the rule stopped holding, `None` while the alert is active
+    pub cleared_at: Option<DateTime<Local>>,
+}
+
+/// Rules of the config with the alerts they raised, newest last
+pub struct Alerts {
+    pub config: AlertsConfig,
+    pub history: Vec<Alert>,
+}
+
+impl Alerts {
+    pub fn new(config: AlertsConfig) -> Self {
+        Alerts {
+            config,
+            history: Vec::new(),
+        }
+    }
+
+    /// Checks every rule against each server, raising an alert when a rule starts to hold and
+    /// clearing it once the rule no longer does. Returns the number of alerts raised, the last
+    /// of the history.
+    pub fn evaluate(&mut self, servers: &[PiHoleServer]) -> usize {
+        let now = Local::now();
+        let mut holding = Vec::new();
+        for server in servers {
+            for (kind, message) in self.check(server) {
+                holding.push((server.name.as_str(), kind, message));
+            }
+        }
+
+        for alert in self.history.iter_mut().filter(|alert| alert.is_active()) {
+            let still_holding = holding
+                .iter()
+                .any(|(server, kind, _)| *server == alert.server && *kind == alert.kind);
+            if !still_holding {
+                log::info!("Cleared alert for {}: {}", alert.server, alert.message);
+                alert.cleared_at = Some(now);
+            }
+        }
+        let mut raised = 0;
+        for (server, kind, message) in holding {
+            let active = self
+                .active()
+                .any(|alert| alert.server == server && alert.kind == kind);
+            if !active {
+                log::warn!("Alert for {}: {}", server, message);
+                self.history.push(Alert {
+                    server: server.to_string(),
+                    kind,
+                    message,
+                    raised_at: now,
+                    cleared_at: None,
+                });
+                raised += 1;
+            }
+        }
+        // The oldest cleared alerts make room, active ones are kept however many there are
+        while self.history.len() > HISTORY_LENGTH {
+            match self.history.iter().position(|alert| !alert.is_active()) {
+                Some(cleared) => {
+                    self.history.remove(cleared);
+                }
+                None => break,
+            }
+        }
+        raised
+    }
+
+    /// Alerts whose rule still holds, oldest first
+    pub fn active(&self) -> impl Iterator<Item = &Alert> {
+        self.history.iter().filter(|alert| alert.is_active())
+    }
+
+    /// The rules holding for `server` with a description of each
+    fn check(&self, server: &PiHoleServer) -> Vec<(AlertKind, String)> {
+        let config = &self.config;
+        let mut holding = Vec::new();
+        if let (Some(minutes), Some(since)) = (config.unreachable_minutes, server.unreachable_since)
+        {
+            let unreachable_for = since.elapsed();
+            if unreachable_for >= Duration::from_secs(minutes * 60) {
+                holding.push((
+                    AlertKind::Unreachable,
+                    format!("unreachable for {}", util::format_duration(unreachable_for)),
+                ));
+            }
+        }
+        if let (Some(minutes), Some(since)) = (config.disabled_minutes, server.disabled_since) {
+            let disabled_for = since.elapsed();
+            if disabled_for >= Duration::from_secs(minutes * 60) {
+                holding.push((
+                    AlertKind::LeftDisabled,
+                    format!(
+                        "blocking disabled for {}",
+                        util::format_duration(disabled_for)
+                    ),
+                ));
+            }
+        }
+        if config.update_available
+            && server
+                .last_data
+                .versions
+                .as_ref()
+                .is_some_and(util::update_available)
+        {
+            holding.push((AlertKind::UpdateAvailable, "update available".to_string()));
+        }
+        let summary = match &server.last_data.summary {
+            Some(summary) => summary,
+            None => return holding,
+        };
+        if let Some(threshold) = config.ads_percentage_above {
+            let percentage: f64 = summary
+                .ads_percentage_today
+                .trim()
+                .parse()
+                .unwrap_or_default();
+            if percentage > threshold {
+                holding.push((
+                    AlertKind::AdsPercentage,
+                    format!(
+                        "{:.1}% of queries blocked, above {}%",
+                        percentage, threshold
+                    ),
+                ));
+            }
+        }
+        if let Some(threshold) = config.queries_below {
+            let queries = util::parse_count(&summary.dns_queries_today);
+            if queries < threshold {
+                holding.push((
+                    AlertKind::FewQueries,
+                    format!("{} queries today, below {}", queries, threshold),
+                ));
+            }
+        }
+        if config.status_not_enabled && summary.status != "enabled" {
+            holding.push((
+                AlertKind::NotEnabled,
+                format!("blocking is {}", summary.status),
+            ));
+        }
+        holding
+    }
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.cleared_at.is_none()
+    }
+}
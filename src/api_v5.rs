@@ -0,0 +1,369 @@
+use crate::util::{ClientsOverTime, LocalRecord, LocalRecordType, NetworkDevice, QueryFilter};
+use pi_hole_api::{api_types::*, errors::APIError};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap;
+
+/// Pi-hole v5 PHP API configuration.
+/// Requests share a single HTTP client so connections are reused between updates.
+pub struct PiHoleAPIConfigV5 {
+    host: String,
+    api_key: Option<String>,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct CacheInfoResponse {
+    cacheinfo: CacheInfo,
+}
+
+#[derive(Deserialize)]
+struct ClientsOverTimeResponse {
+    /// Counts of each period in the order of the client names
+    over_time: HashMap<String, Vec<u64>>,
+}
+
+#[derive(Deserialize)]
+struct ClientNamesResponse {
+    clients: Vec<ClientName>,
+}
+
+#[derive(Deserialize)]
+struct ClientName {
+    name: String,
+    ip: String,
+}
+
+#[derive(Deserialize)]
+struct NetworkResponse {
+    network: Vec<NetworkEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkEntry {
+    hwaddr: String,
+    interface: String,
+    last_query: i64,
+    num_queries: u64,
+    /// Addresses with the host name of each in the same order, empty if there is none
+    ip: Vec<String>,
+    name: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LocalRecordsResponse {
+    /// Domain and IP address or target of each record
+    data: Vec<(String, String)>,
+}
+
+/// Parameter selecting the records of `record_type` and the name of the parameter of their
+/// target
+fn local_record_params(record_type: LocalRecordType) -> (&'static str, &'static str) {
+    match record_type {
+        LocalRecordType::Dns => ("customdns", "ip"),
+        LocalRecordType::Cname => ("customcname", "target"),
+    }
+}
+
+impl PiHoleAPIConfigV5 {
+    /// Creates a new Pi Hole v5 API instance making its requests with `client`.
+    /// `host` must begin with the protocol e.g. http:// or https://
+    pub fn new(host: String, api_key: Option<String>, client: Client) -> Self {
+        Self {
+            host,
+            api_key,
+            client,
+        }
+    }
+
+    pub fn has_api_key(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    async fn request<T>(&self, params: &[(&str, String)]) -> Result<T, APIError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self
+            .client
+            .get(format!("{}/admin/api.php", self.host))
+            .query(params)
+            .send()
+            .await?
+            .json()
+            .await?)
+    }
+
+    async fn authenticated_request<T>(&self, params: &[(&str, String)]) -> Result<T, APIError>
+    where
+        T: DeserializeOwned,
+    {
+        self.authenticated_script_request("api.php", params).await
+    }
+
+    /// Authenticated request to `script` in the admin directory, e.g. `api_db.php` for the
+    /// long term database
+    async fn authenticated_script_request<T>(
+        &self,
+        script: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, APIError>
+    where
+        T: DeserializeOwned,
+    {
+        let api_key = self.api_key.as_ref().ok_or(APIError::MissingAPIKey)?;
+        let response_text = self
+            .client
+            .get(format!("{}/admin/{}", self.host, script))
+            .query(params)
+            .query(&[("auth", api_key)])
+            .send()
+            .await?
+            .text()
+            .await?;
+        pi_hole_api::errors::detect_response_errors(&response_text)?;
+        // An invalid key is answered with an empty list rather than an error
+        if response_text.trim() == "[]" {
+            return Err(APIError::MissingAPIKey);
+        }
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    pub async fn get_summary_raw(&self) -> Result<SummaryRaw, APIError> {
+        self.request(&[("summaryRaw", String::new())]).await
+    }
+
+    pub async fn get_summary(&self) -> Result<Summary, APIError> {
+        self.request(&[("summary", String::new())]).await
+    }
+
+    pub async fn get_over_time_data_10_mins(&self) -> Result<OverTimeData, APIError> {
+        self.request(&[("overTimeData10mins", String::new())]).await
+    }
+
+    pub async fn get_top_items(&self, count: u32) -> Result<TopItems, APIError> {
+        self.authenticated_request(&[("topItems", count.to_string())])
+            .await
+    }
+
+    pub async fn get_top_clients(&self, count: u32) -> Result<TopClients, APIError> {
+        self.authenticated_request(&[("topClients", count.to_string())])
+            .await
+    }
+
+    /// Top clients by blocked queries, in the form of `TopClients`
+    pub async fn get_top_clients_blocked(&self, count: u32) -> Result<TopClients, APIError> {
+        let top: TopClientsBlocked = self
+            .authenticated_request(&[("topClientsBlocked", count.to_string())])
+            .await?;
+        Ok(TopClients {
+            top_sources: top.top_sources_blocked,
+        })
+    }
+
+    pub async fn get_query_types(&self) -> Result<QueryTypes, APIError> {
+        self.authenticated_request(&[("getQueryTypes", String::new())])
+            .await
+    }
+
+    pub async fn get_all_queries(&self, count: u32) -> Result<Vec<Query>, APIError> {
+        let mut raw_data: HashMap<String, Vec<Query>> = self
+            .authenticated_request(&[("getAllQueries", count.to_string())])
+            .await?;
+        Ok(raw_data.remove("data").unwrap_or_default())
+    }
+
+    pub async fn get_filtered_queries(
+        &self,
+        filter: &QueryFilter,
+        count: u32,
+    ) -> Result<Vec<Query>, APIError> {
+        let filter = match filter {
+            QueryFilter::Client(client) => ("client", client.clone()),
+            QueryFilter::Domain(domain) => ("domain", domain.clone()),
+        };
+        let mut raw_data: HashMap<String, Vec<Query>> = self
+            .authenticated_request(&[("getAllQueries", count.to_string()), filter])
+            .await?;
+        Ok(raw_data.remove("data").unwrap_or_default())
+    }
+
+    pub async fn get_versions(&self) -> Result<Versions, APIError> {
+        self.request(&[("versions", String::new())]).await
+    }
+
+    pub async fn get_cache_info(&self) -> Result<CacheInfo, APIError> {
+        let response: CacheInfoResponse = self
+            .authenticated_request(&[("getCacheInfo", String::new())])
+            .await?;
+        Ok(response.cacheinfo)
+    }
+
+    pub async fn get_clients_over_time(&self) -> Result<ClientsOverTime, APIError> {
+        let over_time_params = [("overTimeDataClients", String::new())];
+        let names_params = [("getClientNames", String::new())];
+        let (response, names): (ClientsOverTimeResponse, ClientNamesResponse) = tokio::try_join!(
+            self.authenticated_request(&over_time_params),
+            self.authenticated_request(&names_params),
+        )?;
+        let mut over_time: Vec<(i64, Vec<u64>)> = response
+            .over_time
+            .into_iter()
+            .filter_map(|(timestamp, counts)| Some((timestamp.parse().ok()?, counts)))
+            .collect();
+        over_time.sort_by_key(|(timestamp, _)| *timestamp);
+        Ok(ClientsOverTime {
+            clients: names
+                .clients
+                .into_iter()
+                .map(|client| match client.name.is_empty() {
+                    true => client.ip,
+                    false => client.name,
+                })
+                .collect(),
+            over_time,
+        })
+    }
+
+    pub async fn get_network_devices(&self) -> Result<Vec<NetworkDevice>, APIError> {
+        let response: NetworkResponse = self
+            .authenticated_script_request("api_network.php", &[("network", String::new())])
+            .await?;
+        Ok(response
+            .network
+            .into_iter()
+            .map(|entry| NetworkDevice {
+                hwaddr: entry.hwaddr,
+                interface: entry.interface,
+                ips: entry.ip,
+                names: entry
+                    .name
+                    .into_iter()
+                    .filter(|name| !name.is_empty())
+                    .collect(),
+                last_query: entry.last_query,
+                queries: entry.num_queries,
+            })
+            .collect())
+    }
+
+    /// Hourly query and blocked counts between the unix timestamps `from` and `until`
+    pub async fn get_database_history(
+        &self,
+        from: i64,
+        until: i64,
+    ) -> Result<Vec<(i64, u64, u64)>, APIError> {
+        let data: OverTimeData = self
+            .authenticated_script_request(
+                "api_db.php",
+                &[
+                    ("getGraphData", String::new()),
+                    ("from", from.to_string()),
+                    ("until", until.to_string()),
+                    ("interval", "3600".to_string()),
+                ],
+            )
+            .await?;
+        Ok(data
+            .domains_over_time
+            .iter()
+            .filter_map(|(timestamp, queries)| {
+                let blocked = data.ads_over_time.get(timestamp).copied();
+                Some((
+                    timestamp.parse().ok()?,
+                    *queries,
+                    blocked.unwrap_or_default(),
+                ))
+            })
+            .collect())
+    }
+
+    pub async fn get_list(&self, list: &str) -> Result<Vec<CustomListDomainDetails>, APIError> {
+        let mut raw_data: HashMap<String, Vec<CustomListDomainDetails>> = self
+            .authenticated_request(&[("list", list.to_string()), ("get", String::new())])
+            .await?;
+        let mut domains = raw_data.remove("data").unwrap_or_default();
+        // pi-hole-api reads the enabled flag inverted, 1 means enabled
+        for domain in &mut domains {
+            domain.enabled = !domain.enabled;
+        }
+        Ok(domains)
+    }
+
+    pub async fn list_add(
+        &self,
+        domain: &str,
+        list: &str,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.authenticated_request(&[("list", list.to_string()), ("add", domain.to_string())])
+            .await
+    }
+
+    pub async fn list_remove(
+        &self,
+        domain: &str,
+        list: &str,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.authenticated_request(&[("list", list.to_string()), ("sub", domain.to_string())])
+            .await
+    }
+
+    pub async fn get_local_records(
+        &self,
+        record_type: LocalRecordType,
+    ) -> Result<Vec<LocalRecord>, APIError> {
+        let (records, _) = local_record_params(record_type);
+        let response: LocalRecordsResponse = self
+            .authenticated_request(&[(records, String::new()), ("action", "get".to_string())])
+            .await?;
+        Ok(response
+            .data
+            .into_iter()
+            .map(|(domain, target)| LocalRecord { domain, target })
+            .collect())
+    }
+
+    async fn change_local_record(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+        action: &str,
+    ) -> Result<ListModificationResponse, APIError> {
+        let (records, target) = local_record_params(record_type);
+        self.authenticated_request(&[
+            (records, String::new()),
+            ("action", action.to_string()),
+            ("domain", record.domain.clone()),
+            (target, record.target.clone()),
+        ])
+        .await
+    }
+
+    pub async fn local_record_add(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.change_local_record(record_type, record, "add").await
+    }
+
+    pub async fn local_record_remove(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.change_local_record(record_type, record, "delete")
+            .await
+    }
+
+    pub async fn enable(&self) -> Result<Status, APIError> {
+        self.authenticated_request(&[("enable", String::new())])
+            .await
+    }
+
+    pub async fn disable(&self, seconds: u64) -> Result<Status, APIError> {
+        self.authenticated_request(&[("disable", seconds.to_string())])
+            .await
+    }
+}
@@ -0,0 +1,1270 @@
+use crate::util::{
+    format_count, Adlist, ClientsOverTime, DhcpLease, LocalRecord, LocalRecordType, NetworkDevice,
+    QueryFilter, ServerAction, StatusGroup,
+};
+use chrono::NaiveDateTime;
+use pi_hole_api::{
+    api_types::*,
+    errors::APIError,
+    ftl_types::{DNSSECStatus, QueryStatus, QueryType, ReplyType},
+};
+use reqwest::{Client, Method, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Header carrying the session ID on authenticated requests
+const SESSION_HEADER: &str = "X-FTL-SID";
+/// Gravity downloads every adlist, far longer than any other request takes
+const GRAVITY_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+struct Session {
+    sid: String,
+    validity: Duration,
+    expires: Instant,
+}
+
+impl Session {
+    fn new(sid: String, validity: Duration) -> Self {
+        Session {
+            sid,
+            validity,
+            expires: Instant::now() + validity,
+        }
+    }
+}
+
+/// Pi-hole v6 REST API configuration.
+/// Sessions are created on demand from the password and shared between requests.
+pub struct PiHoleAPIConfigV6 {
+    host: Url,
+    password: Option<String>,
+    client: Client,
+    session: Mutex<Option<Session>>,
+}
+
+#[derive(Deserialize)]
+struct AuthResponse {
+    session: AuthSession,
+}
+
+#[derive(Deserialize)]
+struct AuthSession {
+    valid: bool,
+    sid: Option<String>,
+    validity: i64,
+}
+
+impl PiHoleAPIConfigV6 {
+    /// Creates a new Pi Hole v6 API instance.
+    /// `host` must be an http:// or https:// URL.
+    /// `password` is the web interface or app password, if one is set.
+    /// Requests are made with `client`.
+    pub fn new(host: Url, password: Option<String>, client: Client) -> Self {
+        Self {
+            host,
+            password,
+            client,
+            session: Mutex::new(None),
+        }
+    }
+
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.host.clone();
+        // Only fails for URLs such as mailto: which cannot be hosts
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().push("api").extend(segments);
+        }
+        url
+    }
+
+    /// Log in with the password and return the new session.
+    async fn login(&self, password: &str) -> Result<Session, APIError> {
+        let response: AuthResponse = self
+            .client
+            .post(self.url(&["auth"]))
+            .json(&json!({ "password": password }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        match (response.session.valid, response.session.sid) {
+            (true, Some(sid)) => Ok(Session::new(
+                sid,
+                Duration::from_secs(response.session.validity.max(0) as u64),
+            )),
+            _ => Err(APIError::MissingAPIKey),
+        }
+    }
+
+    /// Return the current session ID, logging in again if the session has expired.
+    async fn session_id(&self) -> Result<Option<String>, APIError> {
+        let password = match &self.password {
+            Some(password) => password,
+            None => return Ok(None),
+        };
+        // Holding the lock while logging in stops concurrent requests creating extra sessions
+        let mut session = self.session.lock().await;
+        match &*session {
+            Some(current) if current.expires > Instant::now() => {}
+            _ => *session = Some(self.login(password).await?),
+        }
+        Ok(session.as_ref().map(|session| session.sid.clone()))
+    }
+
+    async fn send(
+        &self,
+        method: &Method,
+        url: &Url,
+        body: Option<&Value>,
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::Response, APIError> {
+        let mut request = self.client.request(method.clone(), url.clone());
+        if let Some(sid) = self.session_id().await? {
+            request = request.header(SESSION_HEADER, sid);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// Send a request, renewing the session once if the server rejects it, and return the
+    /// successful response before reading its body.
+    /// `timeout` replaces the timeout of the client.
+    async fn response(
+        &self,
+        method: Method,
+        segments: &[&str],
+        params: &[(&str, String)],
+        body: Option<Value>,
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::Response, APIError> {
+        let mut url = self.url(segments);
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        let mut response = self.send(&method, &url, body.as_ref(), timeout).await?;
+        if response.status() == StatusCode::UNAUTHORIZED && self.password.is_some() {
+            *self.session.lock().await = None;
+            response = self.send(&method, &url, body.as_ref(), timeout).await?;
+        }
+        let response = response.error_for_status()?;
+        self.extend_session().await;
+        Ok(response)
+    }
+
+    /// Send a request, renewing the session once if the server rejects it.
+    async fn request<T>(
+        &self,
+        method: Method,
+        segments: &[&str],
+        params: &[(&str, String)],
+        body: Option<Value>,
+    ) -> Result<T, APIError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.response(method, segments, params, body, None).await?;
+
+        // Successful deletions return no content
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(serde_json::from_value(Value::Null)?);
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn get<T>(&self, segments: &[&str], params: &[(&str, String)]) -> Result<T, APIError>
+    where
+        T: DeserializeOwned,
+    {
+        self.request(Method::GET, segments, params, None).await
+    }
+
+    /// Sessions remain valid for their validity period after their last use
+    async fn extend_session(&self) {
+        if let Some(session) = &mut *self.session.lock().await {
+            session.expires = Instant::now() + session.validity;
+        }
+    }
+
+    /// End the current session to free the session slot on the server
+    pub async fn logout(&self) {
+        if let Some(session) = self.session.lock().await.take() {
+            let _ = self
+                .client
+                .delete(self.url(&["auth"]))
+                .header(SESSION_HEADER, session.sid)
+                .send()
+                .await;
+        }
+    }
+}
+
+fn naive_datetime(timestamp: f64) -> NaiveDateTime {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+fn enum_from_name<T>(name: &str) -> Option<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_value(Value::String(name.to_string())).ok()
+}
+
+/// Convert a v5 list name into the v6 domain type and kind
+fn list_type_kind(list: &str) -> Result<(&'static str, &'static str), APIError> {
+    match list {
+        "white" => Ok(("allow", "exact")),
+        "black" => Ok(("deny", "exact")),
+        "white_regex" => Ok(("allow", "regex")),
+        "black_regex" => Ok(("deny", "regex")),
+        _ => Err(APIError::InvalidList),
+    }
+}
+
+fn status_from_name(name: &str) -> QueryStatus {
+    match name {
+        "GRAVITY" => QueryStatus::QueryGravity,
+        "FORWARDED" => QueryStatus::QueryForwarded,
+        "CACHE" | "CACHE_STALE" => QueryStatus::QueryCache,
+        "REGEX" => QueryStatus::QueryRegex,
+        "DENYLIST" | "SPECIAL_DOMAIN" => QueryStatus::QueryBlacklist,
+        "EXTERNAL_BLOCKED_IP" => QueryStatus::QueryExternalBlockedIp,
+        "EXTERNAL_BLOCKED_NULL" | "EXTERNAL_BLOCKED_EDE15" => QueryStatus::QueryExternalBlockedNull,
+        "EXTERNAL_BLOCKED_NXRA" => QueryStatus::QueryExternalBlockedNxra,
+        "GRAVITY_CNAME" => QueryStatus::QueryGravityCname,
+        "REGEX_CNAME" => QueryStatus::QueryRegexCname,
+        "DENYLIST_CNAME" => QueryStatus::QueryBlacklistCname,
+        "RETRIED" => QueryStatus::QueryRetried,
+        "RETRIED_DNSSEC" => QueryStatus::QueryRetriedDnssec,
+        "IN_PROGRESS" => QueryStatus::QueryInProgress,
+        "DBBUSY" => QueryStatus::QueryDbbusy,
+        _ => QueryStatus::QueryUnknown,
+    }
+}
+
+fn dnssec_from_name(name: &str) -> DNSSECStatus {
+    match name {
+        "SECURE" => DNSSECStatus::DNSSECSecure,
+        "INSECURE" => DNSSECStatus::DNSSECInsecure,
+        "BOGUS" => DNSSECStatus::DNSSECBogus,
+        "ABANDONED" => DNSSECStatus::DNSSECAbandoned,
+        _ => DNSSECStatus::DNSSECUnspecified,
+    }
+}
+
+fn reply_from_name(name: &str) -> ReplyType {
+    match name {
+        "NODATA" => ReplyType::ReplyNODATA,
+        "NXDOMAIN" => ReplyType::ReplyNXDOMAIN,
+        "CNAME" => ReplyType::ReplyCNAME,
+        "IP" => ReplyType::ReplyIP,
+        "DOMAIN" => ReplyType::ReplyDOMAIN,
+        "RRNAME" => ReplyType::ReplyRRNAME,
+        "SERVFAIL" => ReplyType::ReplySERVFAIL,
+        "REFUSED" => ReplyType::ReplyREFUSED,
+        "NOTIMP" => ReplyType::ReplyNOTIMP,
+        "OTHER" => ReplyType::ReplyOTHER,
+        "DNSSEC" => ReplyType::ReplyDNSSEC,
+        "NONE" => ReplyType::ReplyNONE,
+        "BLOB" => ReplyType::ReplyBLOB,
+        _ => ReplyType::ReplyUNKNOWN,
+    }
+}
+
+#[derive(Deserialize)]
+struct StatsSummary {
+    queries: StatsQueries,
+    clients: StatsClients,
+    gravity: StatsGravity,
+}
+
+#[derive(Deserialize)]
+struct StatsQueries {
+    total: u64,
+    blocked: u64,
+    percent_blocked: f64,
+    unique_domains: u64,
+    forwarded: u64,
+    cached: u64,
+    replies: HashMap<String, u64>,
+    #[serde(default)]
+    status: HashMap<String, u64>,
+}
+
+#[derive(Deserialize)]
+struct StatsClients {
+    active: u64,
+    total: u64,
+}
+
+#[derive(Deserialize)]
+struct StatsGravity {
+    domains_being_blocked: u64,
+}
+
+#[derive(Deserialize)]
+struct Blocking {
+    blocking: String,
+    /// Seconds until blocking is restored, if disabled with a timer
+    #[serde(default)]
+    timer: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct FtlInfo {
+    ftl: FtlInfoDetails,
+}
+
+#[derive(Deserialize)]
+struct FtlInfoDetails {
+    privacy_level: u64,
+}
+
+#[derive(Deserialize)]
+struct Metrics {
+    metrics: MetricsDetails,
+}
+
+#[derive(Deserialize)]
+struct MetricsDetails {
+    dns: MetricsDns,
+}
+
+#[derive(Deserialize)]
+struct MetricsDns {
+    cache: MetricsCache,
+}
+
+#[derive(Deserialize)]
+struct MetricsCache {
+    size: u64,
+    inserted: u64,
+    evicted: u64,
+}
+
+#[derive(Deserialize)]
+struct SystemInfo {
+    system: SystemDetails,
+}
+
+#[derive(Deserialize)]
+struct SystemDetails {
+    cpu: SystemCpu,
+}
+
+#[derive(Deserialize)]
+struct SystemCpu {
+    load: SystemLoad,
+}
+
+#[derive(Deserialize)]
+struct SystemLoad {
+    /// Load averages of the last 1, 5 and 15 minutes
+    raw: [f64; 3],
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    version: ComponentVersions,
+}
+
+#[derive(Deserialize)]
+struct ComponentVersions {
+    core: ComponentVersion,
+    web: ComponentVersion,
+    ftl: ComponentVersion,
+}
+
+#[derive(Deserialize)]
+struct ComponentVersion {
+    local: Option<LocalVersion>,
+    remote: Option<RemoteVersion>,
+}
+
+#[derive(Deserialize, Default)]
+struct LocalVersion {
+    branch: Option<String>,
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RemoteVersion {
+    version: Option<String>,
+}
+
+impl ComponentVersion {
+    /// Whether an update is available, the current and latest versions and the branch
+    fn into_parts(self) -> (bool, String, String, String) {
+        let local = self.local.unwrap_or_default();
+        let current = local.version.unwrap_or_default();
+        let latest = self
+            .remote
+            .and_then(|remote| remote.version)
+            .unwrap_or_else(|| current.clone());
+        (
+            current != latest,
+            current,
+            latest,
+            local.branch.unwrap_or_default(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct History {
+    history: Vec<HistoryEntry>,
+}
+
+#[derive(Deserialize)]
+struct HistoryEntry {
+    timestamp: f64,
+    total: u64,
+    blocked: u64,
+}
+
+#[derive(Deserialize)]
+struct NetworkDevices {
+    devices: Vec<NetworkDeviceV6>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkDeviceV6 {
+    hwaddr: String,
+    interface: String,
+    last_query: i64,
+    num_queries: u64,
+    ips: Vec<NetworkDeviceIp>,
+}
+
+#[derive(Deserialize)]
+struct NetworkDeviceIp {
+    ip: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DhcpLeases {
+    leases: Vec<DhcpLeaseV6>,
+}
+
+#[derive(Deserialize)]
+struct DhcpLeaseV6 {
+    expires: i64,
+    name: Option<String>,
+    hwaddr: String,
+    ip: String,
+}
+
+#[derive(Deserialize)]
+struct Lists {
+    lists: Vec<ListEntry>,
+}
+
+#[derive(Deserialize)]
+struct ListEntry {
+    address: String,
+    #[serde(rename = "type")]
+    list_type: String,
+    enabled: bool,
+    comment: Option<String>,
+    groups: Vec<u64>,
+    /// Domains found in the list by the last gravity update
+    number: u64,
+    date_updated: i64,
+}
+
+impl From<ListEntry> for Adlist {
+    fn from(entry: ListEntry) -> Self {
+        Adlist {
+            address: entry.address,
+            enabled: entry.enabled,
+            comment: entry.comment.unwrap_or_default(),
+            groups: entry.groups,
+            domains: entry.number,
+            date_updated: entry.date_updated,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DnsConfigResponse {
+    config: DnsConfig,
+}
+
+#[derive(Deserialize)]
+struct DnsConfig {
+    dns: DnsRecords,
+}
+
+/// Only the setting asked for is included
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DnsRecords {
+    /// Lines of a hosts file, e.g. `192.168.1.10 nas.lan`
+    #[serde(default)]
+    hosts: Vec<String>,
+    /// Alias and target separated by a comma, e.g. `media.lan,nas.lan`
+    #[serde(default)]
+    cname_records: Vec<String>,
+}
+
+/// Name of the setting holding the records of `record_type`
+fn local_record_setting(record_type: LocalRecordType) -> &'static str {
+    match record_type {
+        LocalRecordType::Dns => "hosts",
+        LocalRecordType::Cname => "cnameRecords",
+    }
+}
+
+/// Entry of the setting of `record_type` holding `record`
+fn local_record_entry(record_type: LocalRecordType, record: &LocalRecord) -> String {
+    match record_type {
+        LocalRecordType::Dns => format!("{} {}", record.target, record.domain),
+        LocalRecordType::Cname => format!("{},{}", record.domain, record.target),
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientHistory {
+    clients: HashMap<String, ClientHistoryClient>,
+    history: Vec<ClientHistoryEntry>,
+}
+
+#[derive(Deserialize)]
+struct ClientHistoryClient {
+    name: Option<String>,
+    total: u64,
+}
+
+#[derive(Deserialize)]
+struct ClientHistoryEntry {
+    timestamp: f64,
+    /// Count of each client by address
+    data: HashMap<String, u64>,
+}
+
+#[derive(Deserialize)]
+struct TopDomains {
+    domains: Vec<TopDomain>,
+}
+
+#[derive(Deserialize)]
+struct TopDomain {
+    domain: String,
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct TopClientsV6 {
+    clients: Vec<TopClient>,
+}
+
+#[derive(Deserialize)]
+struct TopClient {
+    ip: String,
+    name: Option<String>,
+    count: u64,
+}
+
+/// Client key in the v5 form of "hostname|IP" or "IP"
+fn client_key(ip: &str, name: &Option<String>) -> String {
+    match name {
+        Some(name) if !name.is_empty() => format!("{}|{}", name, ip),
+        _ => ip.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+struct QueryTypesV6 {
+    types: HashMap<String, u64>,
+}
+
+#[derive(Deserialize)]
+struct Queries {
+    queries: Vec<QueryV6>,
+}
+
+#[derive(Deserialize)]
+struct QueryV6 {
+    time: f64,
+    #[serde(rename = "type")]
+    query_type: String,
+    domain: String,
+    cname: Option<String>,
+    status: Option<String>,
+    client: QueryClient,
+    dnssec: Option<String>,
+    reply: QueryReply,
+    list_id: Option<i32>,
+    upstream: Option<String>,
+    ede: QueryEde,
+}
+
+#[derive(Deserialize)]
+struct QueryClient {
+    ip: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct QueryReply {
+    #[serde(rename = "type")]
+    reply_type: Option<String>,
+    time: f64,
+}
+
+#[derive(Deserialize)]
+struct QueryEde {
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DomainsResponse {
+    processed: Option<Processed>,
+}
+
+#[derive(Deserialize)]
+struct Processed {
+    errors: Vec<ProcessedError>,
+}
+
+#[derive(Deserialize)]
+struct ProcessedError {
+    item: String,
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct Domains {
+    domains: Vec<DomainEntry>,
+}
+
+#[derive(Deserialize)]
+struct DomainEntry {
+    id: u64,
+    #[serde(rename = "type")]
+    domain_type: String,
+    kind: String,
+    domain: String,
+    enabled: bool,
+    date_added: i64,
+    date_modified: i64,
+    comment: Option<String>,
+    groups: Vec<u64>,
+}
+
+/// Removes the colours and cursor movements of terminal output, e.g. `\x1b[K`
+fn strip_escape_sequences(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Control sequences end with a letter
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Success unless the server reports an error for the item, with the first error as message
+fn modification_response(response: DomainsResponse) -> ListModificationResponse {
+    let errors = response
+        .processed
+        .map(|processed| processed.errors)
+        .unwrap_or_default();
+    ListModificationResponse {
+        success: errors.is_empty(),
+        message: errors
+            .first()
+            .map(|error| format!("{}: {}", error.item, error.error)),
+    }
+}
+
+impl From<DomainEntry> for CustomListDomainDetails {
+    fn from(entry: DomainEntry) -> Self {
+        CustomListDomainDetails {
+            id: entry.id,
+            // Numbering used by the v5 API
+            domain_type: match (entry.domain_type.as_str(), entry.kind.as_str()) {
+                ("allow", "exact") => 0,
+                ("deny", "exact") => 1,
+                ("allow", _) => 2,
+                _ => 3,
+            },
+            domain: entry.domain,
+            enabled: entry.enabled,
+            date_added: naive_datetime(entry.date_added as f64),
+            date_modified: naive_datetime(entry.date_modified as f64),
+            comment: entry.comment.unwrap_or_default(),
+            groups: entry.groups,
+        }
+    }
+}
+
+impl From<QueryV6> for Query {
+    fn from(query: QueryV6) -> Self {
+        Query {
+            timestring: naive_datetime(query.time),
+            query_type: enum_from_name(&query.query_type).unwrap_or(QueryType::OTHER),
+            domain: query.domain,
+            client: client_key(&query.client.ip, &query.client.name),
+            status: status_from_name(query.status.as_deref().unwrap_or_default()),
+            dnssec_status: dnssec_from_name(query.dnssec.as_deref().unwrap_or_default()),
+            reply_type: reply_from_name(query.reply.reply_type.as_deref().unwrap_or_default()),
+            // Reply time is in milliseconds, negative if unknown
+            response_time: Duration::from_secs_f64(query.reply.time.max(0.0) / 1000.0),
+            cname_domain: query.cname.unwrap_or_default(),
+            regex_id: query.list_id.unwrap_or(-1),
+            upstream_destination: query.upstream.unwrap_or_default(),
+            ede: query.ede.text.unwrap_or_default(),
+        }
+    }
+}
+
+impl PiHoleAPIConfigV6 {
+    pub async fn get_summary_raw(&self) -> Result<SummaryRaw, APIError> {
+        let (summary, blocking, ftl_info) = tokio::try_join!(
+            self.get::<StatsSummary>(&["stats", "summary"], &[]),
+            self.get::<Blocking>(&["dns", "blocking"], &[]),
+            self.get::<FtlInfo>(&["info", "ftl"], &[]),
+        )?;
+        let reply = |name: &str| summary.queries.replies.get(name).copied().unwrap_or(0);
+        Ok(SummaryRaw {
+            domains_being_blocked: summary.gravity.domains_being_blocked,
+            dns_queries_today: summary.queries.total,
+            ads_blocked_today: summary.queries.blocked,
+            ads_percentage_today: summary.queries.percent_blocked,
+            unique_domains: summary.queries.unique_domains,
+            queries_forwarded: summary.queries.forwarded,
+            queries_cached: summary.queries.cached,
+            clients_ever_seen: summary.clients.total,
+            unique_clients: summary.clients.active,
+            dns_queries_all_types: summary.queries.total,
+            reply_nodata: reply("NODATA"),
+            reply_nxdomain: reply("NXDOMAIN"),
+            reply_cname: reply("CNAME"),
+            reply_ip: reply("IP"),
+            privacy_level: ftl_info.ftl.privacy_level,
+            status: blocking.blocking,
+        })
+    }
+
+    pub async fn get_summary(&self) -> Result<Summary, APIError> {
+        let summary = self.get_summary_raw().await?;
+        Ok(Summary {
+            domains_being_blocked: format_count(summary.domains_being_blocked),
+            dns_queries_today: format_count(summary.dns_queries_today),
+            ads_blocked_today: format_count(summary.ads_blocked_today),
+            ads_percentage_today: format!("{:.1}", summary.ads_percentage_today),
+            unique_domains: format_count(summary.unique_domains),
+            queries_forwarded: format_count(summary.queries_forwarded),
+            queries_cached: format_count(summary.queries_cached),
+            clients_ever_seen: format_count(summary.clients_ever_seen),
+            unique_clients: format_count(summary.unique_clients),
+            dns_queries_all_types: format_count(summary.dns_queries_all_types),
+            reply_nodata: format_count(summary.reply_nodata),
+            reply_nxdomain: format_count(summary.reply_nxdomain),
+            reply_cname: format_count(summary.reply_cname),
+            reply_ip: format_count(summary.reply_ip),
+            privacy_level: summary.privacy_level.to_string(),
+            status: summary.status,
+        })
+    }
+
+    pub async fn get_over_time_data_10_mins(&self) -> Result<OverTimeData, APIError> {
+        let history: History = self.get(&["history"], &[]).await?;
+        let timestamp = |entry: &HistoryEntry| (entry.timestamp as i64).to_string();
+        Ok(OverTimeData {
+            domains_over_time: history
+                .history
+                .iter()
+                .map(|entry| (timestamp(entry), entry.total))
+                .collect(),
+            ads_over_time: history
+                .history
+                .iter()
+                .map(|entry| (timestamp(entry), entry.blocked))
+                .collect(),
+        })
+    }
+
+    pub async fn get_network_devices(&self) -> Result<Vec<NetworkDevice>, APIError> {
+        let network: NetworkDevices = self.get(&["network", "devices"], &[]).await?;
+        Ok(network
+            .devices
+            .into_iter()
+            .map(|device| NetworkDevice {
+                hwaddr: device.hwaddr,
+                interface: device.interface,
+                names: device
+                    .ips
+                    .iter()
+                    .filter_map(|ip| ip.name.clone().filter(|name| !name.is_empty()))
+                    .collect(),
+                ips: device.ips.into_iter().map(|ip| ip.ip).collect(),
+                last_query: device.last_query,
+                queries: device.num_queries,
+            })
+            .collect())
+    }
+
+    pub async fn get_dhcp_leases(&self) -> Result<Vec<DhcpLease>, APIError> {
+        let leases: DhcpLeases = self.get(&["dhcp", "leases"], &[]).await?;
+        Ok(leases
+            .leases
+            .into_iter()
+            .map(|lease| DhcpLease {
+                // Clients without a host name are listed as "*"
+                name: lease.name.filter(|name| name != "*").unwrap_or_default(),
+                ip: lease.ip,
+                hwaddr: lease.hwaddr,
+                expires: lease.expires,
+            })
+            .collect())
+    }
+
+    pub async fn get_clients_over_time(&self) -> Result<ClientsOverTime, APIError> {
+        let history: ClientHistory = self.get(&["history", "clients"], &[]).await?;
+        // Busiest clients first like the v5 API
+        let mut clients: Vec<(String, ClientHistoryClient)> = history.clients.into_iter().collect();
+        clients.sort_by_key(|(_, client)| std::cmp::Reverse(client.total));
+        let over_time = history
+            .history
+            .iter()
+            .map(|entry| {
+                let counts = clients
+                    .iter()
+                    .map(|(ip, _)| entry.data.get(ip).copied().unwrap_or_default())
+                    .collect();
+                (entry.timestamp as i64, counts)
+            })
+            .collect();
+        Ok(ClientsOverTime {
+            clients: clients
+                .into_iter()
+                .map(|(ip, client)| client.name.filter(|name| !name.is_empty()).unwrap_or(ip))
+                .collect(),
+            over_time,
+        })
+    }
+
+    /// Query and blocked counts of each period between the unix timestamps `from` and `until`
+    pub async fn get_database_history(
+        &self,
+        from: i64,
+        until: i64,
+    ) -> Result<Vec<(i64, u64, u64)>, APIError> {
+        let history: History = self
+            .get(
+                &["history", "database"],
+                &[("from", from.to_string()), ("until", until.to_string())],
+            )
+            .await?;
+        Ok(history
+            .history
+            .iter()
+            .map(|entry| (entry.timestamp as i64, entry.total, entry.blocked))
+            .collect())
+    }
+
+    async fn get_top_domains(
+        &self,
+        count: u32,
+        blocked: bool,
+    ) -> Result<HashMap<String, u64>, APIError> {
+        let top: TopDomains = self
+            .get(
+                &["stats", "top_domains"],
+                &[
+                    ("count", count.to_string()),
+                    ("blocked", blocked.to_string()),
+                ],
+            )
+            .await?;
+        Ok(top
+            .domains
+            .into_iter()
+            .map(|domain| (domain.domain, domain.count))
+            .collect())
+    }
+
+    pub async fn get_top_items(&self, count: u32) -> Result<TopItems, APIError> {
+        let (top_queries, top_ads) = tokio::try_join!(
+            self.get_top_domains(count, false),
+            self.get_top_domains(count, true),
+        )?;
+        Ok(TopItems {
+            top_queries,
+            top_ads,
+        })
+    }
+
+    pub async fn get_top_clients(&self, count: u32, blocked: bool) -> Result<TopClients, APIError> {
+        let top: TopClientsV6 = self
+            .get(
+                &["stats", "top_clients"],
+                &[
+                    ("count", count.to_string()),
+                    ("blocked", blocked.to_string()),
+                ],
+            )
+            .await?;
+        Ok(TopClients {
+            top_sources: top
+                .clients
+                .iter()
+                .map(|client| (client_key(&client.ip, &client.name), client.count))
+                .collect(),
+        })
+    }
+
+    pub async fn get_query_types(&self) -> Result<QueryTypes, APIError> {
+        let query_types: QueryTypesV6 = self.get(&["stats", "query_types"], &[]).await?;
+        let total = query_types.types.values().sum::<u64>().max(1) as f64;
+        Ok(QueryTypes {
+            querytypes: query_types
+                .types
+                .into_iter()
+                .map(|(name, count)| (name, count as f64 * 100.0 / total))
+                .collect(),
+        })
+    }
+
+    pub async fn get_all_queries(&self, count: u32) -> Result<Vec<Query>, APIError> {
+        let queries: Queries = self
+            .get(&["queries"], &[("length", count.to_string())])
+            .await?;
+        // The v5 API returns the oldest query first
+        Ok(queries.queries.into_iter().rev().map(Query::from).collect())
+    }
+
+    pub async fn get_filtered_queries(
+        &self,
+        filter: &QueryFilter,
+        count: u32,
+    ) -> Result<Vec<Query>, APIError> {
+        let filter = match filter {
+            QueryFilter::Client(client) => ("client_ip", client.clone()),
+            QueryFilter::Domain(domain) => ("domain", domain.clone()),
+        };
+        let queries: Queries = self
+            .get(&["queries"], &[("length", count.to_string()), filter])
+            .await?;
+        Ok(queries.queries.into_iter().rev().map(Query::from).collect())
+    }
+
+    pub async fn get_disable_timer(&self) -> Result<Option<Duration>, APIError> {
+        let blocking: Blocking = self.get(&["dns", "blocking"], &[]).await?;
+        Ok(blocking
+            .timer
+            .filter(|timer| *timer > 0.0)
+            .map(Duration::from_secs_f64))
+    }
+
+    pub async fn get_versions(&self) -> Result<Versions, APIError> {
+        let versions: VersionInfo = self.get(&["info", "version"], &[]).await?;
+        let (core_update, core_current, core_latest, core_branch) =
+            versions.version.core.into_parts();
+        let (web_update, web_current, web_latest, web_branch) = versions.version.web.into_parts();
+        let (ftl_update, ftl_current, ftl_latest, ftl_branch) = versions.version.ftl.into_parts();
+        Ok(Versions {
+            core_update,
+            web_update,
+            ftl_update,
+            core_current,
+            web_current,
+            ftl_current,
+            core_latest,
+            web_latest,
+            ftl_latest,
+            core_branch,
+            web_branch,
+            ftl_branch,
+        })
+    }
+
+    /// Queries of the last 24 hours by status group
+    pub async fn get_status_counts(&self) -> Result<HashMap<StatusGroup, u64>, APIError> {
+        let summary: StatsSummary = self.get(&["stats", "summary"], &[]).await?;
+        let mut counts = HashMap::new();
+        for (name, count) in summary.queries.status {
+            *counts
+                .entry(StatusGroup::of(&status_from_name(&name)))
+                .or_default() += count;
+        }
+        Ok(counts)
+    }
+
+    pub async fn get_system_load(&self) -> Result<[f64; 3], APIError> {
+        let info: SystemInfo = self.get(&["info", "system"], &[]).await?;
+        Ok(info.system.cpu.load.raw)
+    }
+
+    pub async fn get_cache_info(&self) -> Result<CacheInfo, APIError> {
+        let metrics: Metrics = self.get(&["info", "metrics"], &[]).await?;
+        let cache = metrics.metrics.dns.cache;
+        Ok(CacheInfo {
+            cache_size: cache.size,
+            cache_live_freed: cache.evicted,
+            cache_inserted: cache.inserted,
+        })
+    }
+
+    pub async fn get_list(&self, list: &str) -> Result<Vec<CustomListDomainDetails>, APIError> {
+        let (domain_type, kind) = list_type_kind(list)?;
+        let domains: Domains = self.get(&["domains", domain_type, kind], &[]).await?;
+        Ok(domains
+            .domains
+            .into_iter()
+            .map(CustomListDomainDetails::from)
+            .collect())
+    }
+
+    pub async fn list_add(
+        &self,
+        domain: &str,
+        list: &str,
+    ) -> Result<ListModificationResponse, APIError> {
+        let (domain_type, kind) = list_type_kind(list)?;
+        let response: DomainsResponse = self
+            .request(
+                Method::POST,
+                &["domains", domain_type, kind],
+                &[],
+                Some(json!({ "domain": domain, "enabled": true })),
+            )
+            .await?;
+        Ok(modification_response(response))
+    }
+
+    pub async fn list_remove(
+        &self,
+        domain: &str,
+        list: &str,
+    ) -> Result<ListModificationResponse, APIError> {
+        let (domain_type, kind) = list_type_kind(list)?;
+        self.request::<Value>(
+            Method::DELETE,
+            &["domains", domain_type, kind, domain],
+            &[],
+            None,
+        )
+        .await?;
+        Ok(ListModificationResponse {
+            success: true,
+            message: None,
+        })
+    }
+
+    /// Lists of domains to block downloaded by gravity, leaving out allowlists
+    pub async fn get_adlists(&self) -> Result<Vec<Adlist>, APIError> {
+        let lists: Lists = self.get(&["lists"], &[]).await?;
+        Ok(lists
+            .lists
+            .into_iter()
+            .filter(|list| list.list_type == "block")
+            .map(Adlist::from)
+            .collect())
+    }
+
+    pub async fn adlist_add(&self, address: &str) -> Result<ListModificationResponse, APIError> {
+        let response: DomainsResponse = self
+            .request(
+                Method::POST,
+                &["lists"],
+                &[("type", "block".to_string())],
+                Some(json!({ "address": address, "enabled": true })),
+            )
+            .await?;
+        Ok(modification_response(response))
+    }
+
+    /// Replaces the whole list, so its comment and groups are sent back unchanged
+    pub async fn adlist_set_enabled(
+        &self,
+        adlist: &Adlist,
+        enabled: bool,
+    ) -> Result<ListModificationResponse, APIError> {
+        let response: DomainsResponse = self
+            .request(
+                Method::PUT,
+                &["lists", &adlist.address],
+                &[("type", "block".to_string())],
+                Some(json!({
+                    "type": "block",
+                    "comment": adlist.comment,
+                    "groups": adlist.groups,
+                    "enabled": enabled,
+                })),
+            )
+            .await?;
+        Ok(modification_response(response))
+    }
+
+    pub async fn get_local_records(
+        &self,
+        record_type: LocalRecordType,
+    ) -> Result<Vec<LocalRecord>, APIError> {
+        let setting = local_record_setting(record_type);
+        let response: DnsConfigResponse = self.get(&["config", "dns", setting], &[]).await?;
+        let records = match record_type {
+            LocalRecordType::Dns => response
+                .config
+                .dns
+                .hosts
+                .iter()
+                .filter_map(|entry| {
+                    let (address, domain) = entry.trim().split_once(char::is_whitespace)?;
+                    Some(LocalRecord {
+                        domain: domain.trim().to_string(),
+                        target: address.to_string(),
+                    })
+                })
+                .collect(),
+            // The target may be followed by a TTL, kept so the entry can be removed
+            LocalRecordType::Cname => response
+                .config
+                .dns
+                .cname_records
+                .iter()
+                .filter_map(|entry| {
+                    let (domain, target) = entry.split_once(',')?;
+                    Some(LocalRecord {
+                        domain: domain.to_string(),
+                        target: target.to_string(),
+                    })
+                })
+                .collect(),
+        };
+        Ok(records)
+    }
+
+    /// Adds or removes an entry of the setting, answered without content
+    async fn change_local_record(
+        &self,
+        method: Method,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        let entry = local_record_entry(record_type, record);
+        self.response(
+            method,
+            &["config", "dns", local_record_setting(record_type), &entry],
+            &[],
+            None,
+            None,
+        )
+        .await?;
+        Ok(ListModificationResponse {
+            success: true,
+            message: None,
+        })
+    }
+
+    pub async fn local_record_add(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.change_local_record(Method::PUT, record_type, record)
+            .await
+    }
+
+    pub async fn local_record_remove(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        self.change_local_record(Method::DELETE, record_type, record)
+            .await
+    }
+
+    /// Runs gravity, passing each line of its output to `on_line` as it arrives
+    pub async fn update_gravity(&self, mut on_line: impl FnMut(String)) -> Result<(), APIError> {
+        let mut response = self
+            .response(
+                Method::POST,
+                &["action", "gravity"],
+                &[],
+                None,
+                Some(GRAVITY_TIMEOUT),
+            )
+            .await?;
+        let mut output = String::new();
+        while let Some(chunk) = response.chunk().await? {
+            output.push_str(&String::from_utf8_lossy(&chunk));
+            // Progress is redrawn with carriage returns, each redraw is taken as a line
+            while let Some(end) = output.find(['\n', '\r']) {
+                let line = strip_escape_sequences(&output[..end]);
+                output.drain(..=end);
+                if !line.trim().is_empty() {
+                    on_line(line);
+                }
+            }
+        }
+        let line = strip_escape_sequences(&output);
+        if !line.trim().is_empty() {
+            on_line(line);
+        }
+        Ok(())
+    }
+
+    pub async fn run_action(&self, action: ServerAction) -> Result<(), APIError> {
+        let segments: &[&str] = match action {
+            ServerAction::FlushLogs => &["action", "flush", "logs"],
+            ServerAction::FlushNetworkTable => &["action", "flush", "arp"],
+            ServerAction::RestartDns => &["action", "restartdns"],
+        };
+        self.response(Method::POST, segments, &[], None, None)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn enable(&self) -> Result<Status, APIError> {
+        let blocking: Blocking = self
+            .request(
+                Method::POST,
+                &["dns", "blocking"],
+                &[],
+                Some(json!({ "blocking": true, "timer": null })),
+            )
+            .await?;
+        Ok(Status {
+            status: blocking.blocking,
+        })
+    }
+
+    pub async fn disable(&self, seconds: u64) -> Result<Status, APIError> {
+        // A timer of zero disables blocking indefinitely
+        let timer = if seconds > 0 { Some(seconds) } else { None };
+        let blocking: Blocking = self
+            .request(
+                Method::POST,
+                &["dns", "blocking"],
+                &[],
+                Some(json!({ "blocking": false, "timer": timer })),
+            )
+            .await?;
+        Ok(Status {
+            status: blocking.blocking,
+        })
+    }
+}
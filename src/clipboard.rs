@@ -0,0 +1,84 @@
+use base64::engine::{general_purpose, Engine};
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Puts `text` on the system clipboard with the clipboard command of the platform, or else
+/// with an OSC 52 escape sequence asking the terminal to do it. Over SSH the escape sequence
+/// is used directly as the commands would reach the clipboard of the remote machine.
+pub fn copy(text: &str) -> Result<(), String> {
+    let over_ssh = env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some();
+    if !over_ssh
+        && clipboard_commands()
+            .iter()
+            .any(|command| run(command, text))
+    {
+        return Ok(());
+    }
+    copy_osc52(text).map_err(|error| format!("Could not copy to the clipboard: {}", error))
+}
+
+/// Runs `command` with `text` as its input, `false` if it is missing or fails
+fn run(command: &[&str], text: &str) -> bool {
+    let child = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    // Dropping stdin closes it so the command sees the end of the text
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    match child.wait() {
+        Ok(status) if written && status.success() => true,
+        Ok(status) => {
+            log::warn!("{} failed with {}", command[0], status);
+            false
+        }
+        Err(error) => {
+            log::warn!("{} failed: {}", command[0], error);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn clipboard_commands() -> Vec<&'static [&'static str]> {
+    vec![&["pbcopy"]]
+}
+
+#[cfg(target_os = "windows")]
+fn clipboard_commands() -> Vec<&'static [&'static str]> {
+    vec![&["clip"]]
+}
+
+/// Wayland first when it is running, X11 otherwise
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn clipboard_commands() -> Vec<&'static [&'static str]> {
+    let mut commands: Vec<&'static [&'static str]> = Vec::new();
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        commands.push(&["wl-copy"]);
+    }
+    if env::var_os("DISPLAY").is_some() {
+        commands.push(&["xclip", "-selection", "clipboard"]);
+        commands.push(&["xsel", "--clipboard", "--input"]);
+    }
+    commands
+}
+
+/// Writes the OSC 52 sequence to the terminal, wrapped for tmux to pass it on
+fn copy_osc52(text: &str) -> io::Result<()> {
+    let mut sequence = format!("\x1b]52;c;{}\x07", general_purpose::STANDARD.encode(text));
+    if env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()
+}
@@ -0,0 +1,78 @@
+use crossterm::event::{self, Event};
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Everything the interface reacts to, each one may change what is drawn
+pub enum CustomEvent {
+    /// Key press, mouse event or resize of the terminal
+    Input(Event),
+    /// Reading the terminal failed, no more input follows
+    InputFailed(io::Error),
+    /// Due every `tick_rate`, keeps timers and scheduled updates going
+    Tick,
+    /// A server answered an update or another request
+    DataArrived,
+}
+
+pub struct Config {
+    pub tick_rate: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            tick_rate: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// Input is read and ticks are generated on their own threads, both are sent with the data
+/// arriving over the same channel so the interface waits on a single receiver
+pub struct CustomEvents {
+    receiver: Receiver<CustomEvent>,
+    sender: Sender<CustomEvent>,
+}
+
+impl CustomEvents {
+    pub fn with_config(config: Config) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let input_sender = sender.clone();
+        thread::spawn(move || loop {
+            let event = match event::read() {
+                Ok(event) => CustomEvent::Input(event),
+                Err(error) => {
+                    let _ = input_sender.send(CustomEvent::InputFailed(error));
+                    return;
+                }
+            };
+            if input_sender.send(event).is_err() {
+                return;
+            }
+        });
+        let tick_sender = sender.clone();
+        thread::spawn(move || loop {
+            thread::sleep(config.tick_rate);
+            if tick_sender.send(CustomEvent::Tick).is_err() {
+                return;
+            }
+        });
+        CustomEvents { receiver, sender }
+    }
+
+    /// Sender for `CustomEvent::DataArrived` once a request finishes
+    pub fn sender(&self) -> Sender<CustomEvent> {
+        self.sender.clone()
+    }
+
+    /// Waits for the next event. There always is one as a sender is kept here.
+    pub fn next(&self) -> Option<CustomEvent> {
+        self.receiver.recv().ok()
+    }
+
+    /// Takes an event already waiting, if any
+    pub fn try_next(&self) -> Option<CustomEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
@@ -0,0 +1,119 @@
+use crate::util::{ApiVersion, PiHoleServerConfig};
+use reqwest::Client;
+use serde_json::Value;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Ports the web interface is commonly served on, 8080 is popular for containers
+pub const DEFAULT_PORTS: [u16; 2] = [80, 8080];
+
+/// How long each address is given to answer
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Probes running at once, each holds a socket open and the open file limit can be as low as
+/// 256 on macOS
+const CONCURRENT_PROBES: usize = 64;
+
+/// Pi-hole answering on the network
+#[derive(Clone, Debug)]
+pub struct DiscoveredServer {
+    /// http:// URL of the server
+    pub host: String,
+    pub api_version: ApiVersion,
+}
+
+impl DiscoveredServer {
+    /// Name suggested when adding the server
+    pub fn default_name(&self) -> String {
+        format!("Pi-hole {}", self.host.trim_start_matches("http://"))
+    }
+}
+
+/// `servers` without those whose host is already in `configured`
+pub fn not_configured(
+    servers: Vec<DiscoveredServer>,
+    configured: &[PiHoleServerConfig],
+) -> Vec<DiscoveredServer> {
+    servers
+        .into_iter()
+        .filter(|server| {
+            !configured
+                .iter()
+                .any(|config| config.host.trim_end_matches('/') == server.host)
+        })
+        .collect()
+}
+
+/// The /24 network of the interface used to reach other hosts, `None` without a route
+pub fn local_network() -> Option<Ipv4Addr> {
+    // Connecting a UDP socket only picks the interface, nothing is sent
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(address) if !address.is_loopback() => {
+            let [a, b, c, _] = address.octets();
+            Some(Ipv4Addr::new(a, b, c, 0))
+        }
+        _ => None,
+    }
+}
+
+/// Probes every address of the /24 `network` on each of `ports` for a Pi-hole v5 or v6 API,
+/// returning those found in address order
+pub async fn discover(network: Ipv4Addr, ports: &[u16]) -> Vec<DiscoveredServer> {
+    let client = match Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return Vec::new(),
+    };
+    let [a, b, c, _] = network.octets();
+    let permits = Arc::new(Semaphore::new(CONCURRENT_PROBES));
+    let mut probes = Vec::new();
+    for last in 1..=254 {
+        for port in ports {
+            let host = match port {
+                80 => format!("http://{}", Ipv4Addr::new(a, b, c, last)),
+                port => format!("http://{}:{}", Ipv4Addr::new(a, b, c, last), port),
+            };
+            let client = client.clone();
+            let permits = Arc::clone(&permits);
+            probes.push(tokio::spawn(async move {
+                // The semaphore is never closed
+                let _permit = permits.acquire_owned().await.ok()?;
+                probe(client, host).await
+            }));
+        }
+    }
+    let mut servers = Vec::new();
+    for probe in probes {
+        if let Ok(Some(server)) = probe.await {
+            servers.push(server);
+        }
+    }
+    servers
+}
+
+/// Checks for the v6 API first, a v6 Pi-hole may still answer some v5 requests
+async fn probe(client: Client, host: String) -> Option<DiscoveredServer> {
+    // Answered with the session state whether or not a password is set
+    let v6 = json(&client, format!("{}/api/auth", host)).await;
+    if v6.is_some_and(|response| response.get("session").is_some()) {
+        return Some(DiscoveredServer {
+            host,
+            api_version: ApiVersion::V6,
+        });
+    }
+    let v5 = json(&client, format!("{}/admin/api.php?summaryRaw", host)).await;
+    if v5.is_some_and(|response| response.get("domains_being_blocked").is_some()) {
+        return Some(DiscoveredServer {
+            host,
+            api_version: ApiVersion::V5,
+        });
+    }
+    None
+}
+
+async fn json(client: &Client, url: String) -> Option<Value> {
+    client.get(url).send().await.ok()?.json().await.ok()
+}
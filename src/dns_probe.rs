@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::{self, UdpSocket};
+use tokio::time;
+
+/// How long the server is given to answer each query
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+const TYPE_A: u16 = 1;
+const CLASS_IN: u16 = 1;
+const RCODE_NXDOMAIN: u8 = 3;
+
+/// The `dns_probe` section of a server, the domains queried on its DNS port with each update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsProbeConfig {
+    /// Expected to resolve to a real address
+    #[serde(default = "default_allowed_domain")]
+    pub allowed_domain: String,
+    /// Expected to be blocked, it must be on one of the server's blocklists
+    #[serde(default = "default_blocked_domain")]
+    pub blocked_domain: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_allowed_domain() -> String {
+    "pi-hole.net".to_string()
+}
+
+fn default_blocked_domain() -> String {
+    "doubleclick.net".to_string()
+}
+
+fn default_port() -> u16 {
+    53
+}
+
+/// Whether the server answers DNS queries as a Pi-hole should, the API can be up while
+/// the resolver is not
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum DnsHealth {
+    Ok,
+    /// The allowed domain resolved but so did the blocked domain
+    NotBlocking,
+    /// No usable answer for the allowed domain, with the reason
+    Failed(String),
+}
+
+/// Answer to an A query
+#[derive(Debug, PartialEq, Eq)]
+enum Answer {
+    Addresses(Vec<Ipv4Addr>),
+    NxDomain,
+    /// Any other error response code
+    Error(u8),
+}
+
+impl Answer {
+    /// Pi-hole blocks with NXDOMAIN, no addresses or 0.0.0.0 depending on its blocking mode
+    fn is_blocked(&self) -> bool {
+        match self {
+            Answer::Addresses(addresses) => addresses.iter().all(Ipv4Addr::is_unspecified),
+            Answer::NxDomain => true,
+            Answer::Error(_) => false,
+        }
+    }
+}
+
+/// Queries the allowed and then the blocked domain of `config` on `host`
+pub async fn probe(host: &str, config: &DnsProbeConfig) -> DnsHealth {
+    match query(host, config.port, &config.allowed_domain).await {
+        Ok(Answer::Error(rcode)) => {
+            return DnsHealth::Failed(format!("response code {}", rcode));
+        }
+        Ok(answer) if answer.is_blocked() => {
+            return DnsHealth::Failed(format!("{} is blocked", config.allowed_domain));
+        }
+        Ok(_) => {}
+        Err(error) => return DnsHealth::Failed(error.to_string()),
+    }
+    match query(host, config.port, &config.blocked_domain).await {
+        Ok(Answer::Error(rcode)) => DnsHealth::Failed(format!("response code {}", rcode)),
+        Ok(answer) if answer.is_blocked() => DnsHealth::Ok,
+        Ok(_) => DnsHealth::NotBlocking,
+        Err(error) => DnsHealth::Failed(error.to_string()),
+    }
+}
+
+async fn query(host: &str, port: u16, domain: &str) -> io::Result<Answer> {
+    // IPv6 hosts of URLs are in brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let target = net::lookup_host((host, port))
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found"))?;
+    // Bound to the address family of the server, an IPv4 socket cannot reach IPv6 hosts
+    let local: SocketAddr = match target {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(target).await?;
+    // Only needs to differ between queries, not be unpredictable
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.subsec_nanos() as u16)
+        .unwrap_or_default();
+    socket.send(&encode_query(id, domain)?).await?;
+
+    let mut response = [0; 512];
+    let length = time::timeout(QUERY_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out"))??;
+    decode_answer(id, &response[..length])
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid response"))
+}
+
+/// A query for the A records of `domain` with recursion desired
+fn encode_query(id: u16, domain: &str) -> io::Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(domain.len() + 18);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Flags with only recursion desired, then one question and no other records
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in domain.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid domain {}", domain),
+            ));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_A.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+/// The addresses or error of the response to the query `id`, `None` if it is malformed
+fn decode_answer(id: u16, response: &[u8]) -> Option<Answer> {
+    let header = response.get(..12)?;
+    let is_response = header[2] & 0x80 != 0;
+    if u16::from_be_bytes([header[0], header[1]]) != id || !is_response {
+        return None;
+    }
+    match header[3] & 0x0f {
+        0 => {}
+        RCODE_NXDOMAIN => return Some(Answer::NxDomain),
+        rcode => return Some(Answer::Error(rcode)),
+    }
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+    let answers = u16::from_be_bytes([header[6], header[7]]);
+
+    let mut position = 12;
+    for _ in 0..questions {
+        position = skip_name(response, position)? + 4;
+    }
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        position = skip_name(response, position)?;
+        let record = response.get(position..position + 10)?;
+        let record_type = u16::from_be_bytes([record[0], record[1]]);
+        let length = u16::from_be_bytes([record[8], record[9]]) as usize;
+        position += 10;
+        let data = response.get(position..position + length)?;
+        // CNAMEs leading to the address are skipped
+        if record_type == TYPE_A && length == 4 {
+            addresses.push(Ipv4Addr::new(data[0], data[1], data[2], data[3]));
+        }
+        position += length;
+    }
+    Some(Answer::Addresses(addresses))
+}
+
+/// Position after the name starting at `position`, which may end in a compression pointer
+fn skip_name(packet: &[u8], mut position: usize) -> Option<usize> {
+    loop {
+        let length = *packet.get(position)?;
+        match length {
+            0 => return Some(position + 1),
+            length if length & 0xc0 == 0xc0 => return Some(position + 2),
+            length => position += 1 + length as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Response to `query` with `rcode`, its question and then `answers` as written
+    fn response(query: &[u8], rcode: u8, answers: &[&[u8]]) -> Vec<u8> {
+        let mut packet = query.to_vec();
+        packet[2] |= 0x80;
+        packet[3] |= rcode;
+        packet[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for answer in answers {
+            packet.extend_from_slice(answer);
+        }
+        packet
+    }
+
+    /// Resource record of `record_type` for the name at `pointer`, compressed
+    fn record(pointer: u8, record_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut record = vec![0xc0, pointer];
+        record.extend_from_slice(&record_type.to_be_bytes());
+        record.extend_from_slice(&CLASS_IN.to_be_bytes());
+        record.extend_from_slice(&300u32.to_be_bytes());
+        record.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        record.extend_from_slice(data);
+        record
+    }
+
+    #[test]
+    fn encodes_query() {
+        let query = encode_query(0x1234, "pi-hole.net.").unwrap();
+        let mut expected = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(b"\x07pi-hole\x03net\x00");
+        expected.extend_from_slice(&[0, 1, 0, 1]);
+        assert_eq!(query, expected);
+        assert_eq!(skip_name(&query, 12), Some(query.len() - 4));
+    }
+
+    #[test]
+    fn rejects_invalid_domains() {
+        assert!(encode_query(1, "pi-hole..net").is_err());
+        assert!(encode_query(1, &"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn round_trips_query() {
+        let query = encode_query(7, "pi-hole.net").unwrap();
+        assert_eq!(
+            decode_answer(7, &response(&query, 0, &[])),
+            Some(Answer::Addresses(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn decodes_cname_then_address() {
+        let query = encode_query(7, "www.pi-hole.net").unwrap();
+        // The CNAME target is compressed against the question, the A record against the
+        // CNAME target
+        let cname_target = [3, b'c', b'd', b'n', 0xc0, 16];
+        let cname = record(12, 5, &cname_target);
+        let target_at = (query.len() + 12) as u8;
+        let address = record(target_at, TYPE_A, &[192, 0, 2, 1]);
+        let response = response(&query, 0, &[&cname, &address]);
+        assert_eq!(
+            decode_answer(7, &response),
+            Some(Answer::Addresses(vec![Ipv4Addr::new(192, 0, 2, 1)]))
+        );
+    }
+
+    #[test]
+    fn decodes_blocked_answers() {
+        let query = encode_query(7, "doubleclick.net").unwrap();
+        let nxdomain = decode_answer(7, &response(&query, RCODE_NXDOMAIN, &[]));
+        assert_eq!(nxdomain, Some(Answer::NxDomain));
+        assert!(nxdomain.unwrap().is_blocked());
+        let unspecified = record(12, TYPE_A, &[0, 0, 0, 0]);
+        let answer = decode_answer(7, &response(&query, 0, &[&unspecified])).unwrap();
+        assert!(answer.is_blocked());
+        assert_eq!(
+            decode_answer(7, &response(&query, 2, &[])),
+            Some(Answer::Error(2))
+        );
+    }
+
+    #[test]
+    fn ignores_other_ids_and_queries() {
+        let query = encode_query(7, "pi-hole.net").unwrap();
+        assert_eq!(decode_answer(8, &response(&query, 0, &[])), None);
+        assert_eq!(decode_answer(7, &query), None);
+    }
+
+    #[test]
+    fn rejects_truncated_and_malformed_packets() {
+        let query = encode_query(7, "pi-hole.net").unwrap();
+        let address = record(12, TYPE_A, &[192, 0, 2, 1]);
+        let response = response(&query, 0, &[&address]);
+        for length in 0..response.len() {
+            assert_eq!(decode_answer(7, &response[..length]), None);
+        }
+
+        // More answers than there are
+        let mut counted = response.clone();
+        counted[7] = 2;
+        assert_eq!(decode_answer(7, &counted), None);
+        // Data longer than the packet
+        let mut long = response.clone();
+        let length_at = response.len() - 6;
+        long[length_at..length_at + 2].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert_eq!(decode_answer(7, &long), None);
+        // Label running past the end
+        let mut label = response[..12].to_vec();
+        label.push(63);
+        assert_eq!(decode_answer(7, &label), None);
+        // Names are only skipped, a pointer cut off is caught by the reads that follow
+        assert_eq!(skip_name(&[0xc0], 0), Some(2));
+        assert_eq!(skip_name(&[], 0), None);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::theme::Theme;
+use crate::ui::query_status_text;
+use crate::util::{self, PiHoleData, StatisticsTable, Timezone};
+use chrono::Local;
+use pi_hole_api::api_types::Query;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes Top Queries, Top Ads and Top Clients of `data` to one CSV file in `directory`,
+/// returning its path
+pub fn write_statistics(
+    directory: &Path,
+    server_name: &str,
+    data: &PiHoleData,
+) -> io::Result<PathBuf> {
+    let mut csv = csv_line(&["Table", "Name", "Count"]);
+    for table in StatisticsTable::ALL.iter() {
+        for row in table.rows(data) {
+            let mut fields = vec![table.title()];
+            fields.extend(row.iter().map(String::as_str));
+            csv.push_str(&csv_line(&fields));
+        }
+    }
+    write(directory, server_name, "top-lists", "csv", &csv)
+}
+
+/// Writes `queries` with the name of their server to a CSV file in `directory`, returning its path
+pub fn write_query_log(
+    directory: &Path,
+    server_name: &str,
+    queries: &[(&str, &Query)],
+    theme: &Theme,
+    timezone: Option<&Timezone>,
+) -> io::Result<PathBuf> {
+    let mut csv = csv_line(&["Time", "Type", "Domain", "Client", "Status", "Server"]);
+    for (server, query) in queries {
+        let time = util::format_timestamp(
+            query.timestring.and_utc().timestamp(),
+            "%Y-%m-%d %H:%M:%S",
+            timezone,
+        );
+        let query_type = format!("{:?}", query.query_type);
+        let (status, _) = query_status_text(theme, &query.status);
+        csv.push_str(&csv_line(&[
+            &time,
+            &query_type,
+            &query.domain,
+            &query.client,
+            status,
+            server,
+        ]));
+    }
+    write(directory, server_name, "query-log", "csv", &csv)
+}
+
+/// Writes everything last fetched from a server as pretty printed JSON to a file in `directory`,
+/// returning its path
+pub fn write_snapshot(
+    directory: &Path,
+    server_name: &str,
+    data: &PiHoleData,
+) -> io::Result<PathBuf> {
+    let json = serde_json::to_string_pretty(data)?;
+    write(directory, server_name, "snapshot", "json", &json)
+}
+
+/// Writes `text` to a new file named after the server, what it contains and the current time
+fn write(
+    directory: &Path,
+    server_name: &str,
+    contents: &str,
+    extension: &str,
+    text: &str,
+) -> io::Result<PathBuf> {
+    let path = directory.join(format!(
+        "pimon-{}-{}-{}.{}",
+        util::file_name(server_name),
+        contents,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        extension
+    ));
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// Joins `fields` into a CSV line, quoting those containing a separator, quote or line break
+fn csv_line(fields: &[&str]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
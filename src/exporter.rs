@@ -0,0 +1,189 @@
+use crate::util::{PiHoleConfigImplementation, PiHoleServer};
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use pi_hole_api::api_types::SummaryRaw;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// A Pi-Hole whose statistics are exposed by the exporter
+pub struct ExporterTarget {
+    pub name: String,
+    pub api_config: Arc<PiHoleConfigImplementation>,
+}
+
+/// A per-server gauge taken from the summary
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&SummaryRaw) -> f64,
+}
+
+const GAUGES: [Gauge; 9] = [
+    Gauge {
+        name: "pimon_dns_queries_today",
+        help: "Number of DNS queries today",
+        value: |summary| summary.dns_queries_today as f64,
+    },
+    Gauge {
+        name: "pimon_ads_blocked_today",
+        help: "Number of ads blocked today",
+        value: |summary| summary.ads_blocked_today as f64,
+    },
+    Gauge {
+        name: "pimon_ads_percentage_today",
+        help: "Percentage of queries blocked today",
+        value: |summary| summary.ads_percentage_today,
+    },
+    Gauge {
+        name: "pimon_unique_clients",
+        help: "Number of unique clients",
+        value: |summary| summary.unique_clients as f64,
+    },
+    Gauge {
+        name: "pimon_clients_ever_seen",
+        help: "Number of clients ever seen",
+        value: |summary| summary.clients_ever_seen as f64,
+    },
+    Gauge {
+        name: "pimon_domains_being_blocked",
+        help: "Number of domains on the blocklists",
+        value: |summary| summary.domains_being_blocked as f64,
+    },
+    Gauge {
+        name: "pimon_queries_forwarded",
+        help: "Number of queries forwarded upstream today",
+        value: |summary| summary.queries_forwarded as f64,
+    },
+    Gauge {
+        name: "pimon_queries_cached",
+        help: "Number of queries answered from the cache today",
+        value: |summary| summary.queries_cached as f64,
+    },
+    Gauge {
+        name: "pimon_status",
+        help: "Whether blocking is enabled (1) or not (0)",
+        value: |summary| (summary.status == "enabled") as u8 as f64,
+    },
+];
+
+/// Servers to export, sharing the API clients (and sessions) of the TUI
+pub fn targets(servers: &[PiHoleServer]) -> Vec<ExporterTarget> {
+    servers
+        .iter()
+        .map(|server| ExporterTarget {
+            name: server.name.clone(),
+            api_config: Arc::clone(&server.api_config),
+        })
+        .collect()
+}
+
+/// Binds the metrics endpoint to `address`.
+/// The returned future serves requests until it is dropped and must be run on the tokio runtime.
+pub fn serve(
+    address: SocketAddr,
+    targets: Vec<ExporterTarget>,
+) -> Result<impl Future<Output = Result<(), hyper::Error>>, hyper::Error> {
+    let targets = Arc::new(targets);
+    let make_service = make_service_fn(move |_| {
+        let targets = Arc::clone(&targets);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(request, Arc::clone(&targets))
+            }))
+        }
+    });
+    Ok(Server::try_bind(&address)?.serve(make_service))
+}
+
+async fn handle_request(
+    request: Request<Body>,
+    targets: Arc<Vec<ExporterTarget>>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            let mut response = Response::new(Body::from(render_metrics(&targets).await));
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            response
+        }
+        _ => {
+            let mut response = Response::new(Body::from("Not found, try /metrics\n"));
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    };
+    Ok(response)
+}
+
+/// Fetches every server's summary concurrently and formats them in the Prometheus text format
+async fn render_metrics(targets: &[ExporterTarget]) -> String {
+    let requests: Vec<_> = targets
+        .iter()
+        .map(|target| {
+            let api_config = Arc::clone(&target.api_config);
+            tokio::spawn(async move { api_config.get_summary_raw().await.ok() })
+        })
+        .collect();
+    let mut summaries = Vec::with_capacity(requests.len());
+    for request in requests {
+        summaries.push(request.await.ok().flatten());
+    }
+
+    let mut output = String::new();
+    write_header(
+        &mut output,
+        "pimon_up",
+        "Whether the last request to the Pi-Hole succeeded",
+    );
+    for (target, summary) in targets.iter().zip(&summaries) {
+        write_sample(
+            &mut output,
+            "pimon_up",
+            &target.name,
+            summary.is_some() as u8 as f64,
+        );
+    }
+    for gauge in GAUGES.iter() {
+        write_header(&mut output, gauge.name, gauge.help);
+        for (target, summary) in targets.iter().zip(&summaries) {
+            if let Some(summary) = summary {
+                write_sample(
+                    &mut output,
+                    gauge.name,
+                    &target.name,
+                    (gauge.value)(summary),
+                );
+            }
+        }
+    }
+    output
+}
+
+fn write_header(output: &mut String, metric: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", metric, help);
+    let _ = writeln!(output, "# TYPE {} gauge", metric);
+}
+
+fn write_sample(output: &mut String, metric: &str, server: &str, value: f64) {
+    let _ = writeln!(
+        output,
+        "{}{{server=\"{}\"}} {}",
+        metric,
+        escape_label_value(server),
+        value
+    );
+}
+
+/// Escapes a label value as required by the Prometheus text format
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
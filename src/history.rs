@@ -0,0 +1,144 @@
+use crate::util::{self, parse_count, DayTotals, SECONDS_PER_DAY};
+use pi_hole_api::api_types::Summary;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Minimum time between the records of a server, updates in between are not stored
+pub const RECORD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Days of records kept, as many as the longest period of the Long term view
+pub const RETENTION_DAYS: u32 = 90;
+
+/// Minimum time between removing the records older than `RETENTION_DAYS`
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A summary of a server as stored in its history file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Unix timestamp of the update
+    pub timestamp: i64,
+    pub dns_queries_today: u64,
+    pub ads_blocked_today: u64,
+    pub queries_forwarded: u64,
+    pub queries_cached: u64,
+    pub unique_clients: u64,
+    pub enabled: bool,
+}
+
+impl HistoryRecord {
+    pub fn new(timestamp: i64, summary: &Summary) -> Self {
+        HistoryRecord {
+            timestamp,
+            dns_queries_today: parse_count(&summary.dns_queries_today),
+            ads_blocked_today: parse_count(&summary.ads_blocked_today),
+            queries_forwarded: parse_count(&summary.queries_forwarded),
+            queries_cached: parse_count(&summary.queries_cached),
+            unique_clients: parse_count(&summary.unique_clients),
+            enabled: summary.status == "enabled",
+        }
+    }
+}
+
+/// History of one server, a file of JSON records one per line. It keeps the statistics across
+/// restarts and beyond the 24 hours the API reports, for the Long term view of servers whose
+/// long term database cannot be read.
+#[derive(Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// History of the server `server_name` in `directory`, the file is created on the first record
+    pub fn new(directory: &Path, server_name: &str) -> Self {
+        History {
+            path: directory.join(format!("{}.jsonl", util::file_name(server_name))),
+        }
+    }
+
+    pub fn append(&self, record: &HistoryRecord) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Records in the order they were appended, lines which cannot be read are skipped. No
+    /// records without a file yet.
+    fn records(&self) -> io::Result<Vec<HistoryRecord>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(record) = serde_json::from_str(&line?) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Totals of the `days` days starting at `from`, a local midnight. The counts of the
+    /// summary start again each day so the largest recorded in a day is its total.
+    pub fn day_totals(&self, from: i64, days: u32) -> io::Result<Vec<DayTotals>> {
+        let mut totals = DayTotals::empty(from, days);
+        for record in self.records()? {
+            let day = (record.timestamp - from).div_euclid(SECONDS_PER_DAY);
+            if let Some(totals) = usize::try_from(day)
+                .ok()
+                .and_then(|day| totals.get_mut(day))
+            {
+                totals.queries = totals.queries.max(record.dns_queries_today);
+                totals.blocked = totals.blocked.max(record.ads_blocked_today);
+            }
+        }
+        Ok(totals)
+    }
+
+    /// Removes the records from before `until`, rewriting the file only if there are any
+    pub fn prune(&self, until: i64) -> io::Result<()> {
+        let records = self.records()?;
+        let kept: Vec<&HistoryRecord> = records
+            .iter()
+            .filter(|record| record.timestamp >= until)
+            .collect();
+        if kept.len() == records.len() {
+            return Ok(());
+        }
+        let mut contents = String::new();
+        for record in kept {
+            contents.push_str(&serde_json::to_string(record)?);
+            contents.push('\n');
+        }
+        // Replaced at once so an interruption cannot lose the records kept
+        let temporary = self.path.with_extension("jsonl.tmp");
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &self.path)
+    }
+}
+
+/// `$XDG_DATA_HOME/pimon/history`, falling back to `~/.local/share` and then the current directory
+pub fn default_directory() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|path| !path.is_empty())
+                .map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .unwrap_or_default();
+    data_home.join("pimon").join("history")
+}
@@ -0,0 +1,227 @@
+use crate::discover;
+use crate::util::{
+    self, ApiVersion, HttpOptions, PiHoleConfigImplementation, PiHoleServerConfig, PimonConfig,
+};
+use pi_hole_api::errors::APIError;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::io::{self, Write};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+/// Delay between updates suggested for new configurations in milliseconds
+const DEFAULT_UPDATE_DELAY: u64 = 5000;
+
+/// Interactively creates a configuration file at `config_file_path` or the default location
+pub fn run(config_file_path: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let path = config_file_path.unwrap_or_else(util::default_config_file_path);
+    println!("Creating a pimon configuration at {}", path.display());
+    if path.exists() && !confirm("The file already exists, overwrite it?", false)? {
+        return Ok(());
+    }
+
+    let mut servers = Vec::new();
+    loop {
+        servers.push(prompt_server()?);
+        if !confirm("Add another server?", false)? {
+            break;
+        }
+    }
+    let default_delay = DEFAULT_UPDATE_DELAY.to_string();
+    let update_delay = loop {
+        match prompt(
+            "Delay between updates in milliseconds",
+            Some(&default_delay),
+        )?
+        .parse()
+        {
+            Ok(update_delay) => break update_delay,
+            Err(_) => println!("Please enter a whole number of milliseconds"),
+        }
+    };
+
+    util::write_config(&path, &PimonConfig::new(update_delay, servers))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn prompt_server() -> Result<PiHoleServerConfig, Box<dyn Error>> {
+    loop {
+        let name = prompt("Server name", None)?;
+        let mut host = prompt("Host e.g. 192.168.1.1 or https://pi.hole", None)?;
+        if !host.starts_with("http://") && !host.starts_with("https://") {
+            host = format!("http://{}", host);
+        }
+        let host = host.trim_end_matches('/').to_string();
+        let api_version = loop {
+            let version = prompt("API version (5 or 6)", Some("5"))?;
+            match version.parse::<u8>() {
+                Ok(version) => match ApiVersion::try_from(version) {
+                    Ok(api_version) => break api_version,
+                    Err(error) => println!("{}", error),
+                },
+                Err(_) => println!("Please enter 5 or 6"),
+            }
+        };
+        let api_key = prompt_api_key(api_version)?;
+
+        println!("Testing connection to {}", host);
+        match test_connection(&host, api_key.clone(), api_version) {
+            Ok(dns_queries_today) => {
+                println!("Connected, {} DNS queries today", dns_queries_today)
+            }
+            Err(error) => {
+                println!("Connection failed: {}", error);
+                if !confirm("Keep this server anyway?", false)? {
+                    continue;
+                }
+            }
+        }
+
+        return Ok(PiHoleServerConfig::new(name, host, api_key, api_version));
+    }
+}
+
+/// Asks for the API key or password without echoing it, empty for none
+fn prompt_api_key(api_version: ApiVersion) -> io::Result<Option<String>> {
+    let api_key = rpassword::prompt_password(match api_version {
+        ApiVersion::V5 => "API key (Settings -> API -> Show API Token), empty for none: ",
+        ApiVersion::V6 => "Password or app password, empty for none: ",
+    })?;
+    Ok(Some(api_key).filter(|api_key| !api_key.is_empty()))
+}
+
+/// Searches `network`, or the local network, for Pi-holes and offers to add those not
+/// configured yet to the configuration file at `config_file_path` or the default location
+pub fn discover(
+    config_file_path: Option<PathBuf>,
+    network: Option<Ipv4Addr>,
+    ports: &[u16],
+) -> Result<(), Box<dyn Error>> {
+    let network = network
+        .or_else(discover::local_network)
+        .ok_or("Could not find the local network, give it with --network")?;
+    let path = config_file_path
+        .or_else(util::find_config_file)
+        .unwrap_or_else(util::default_config_file_path);
+    let mut config = if path.exists() {
+        util::read_config(&path)?
+    } else {
+        PimonConfig::new(DEFAULT_UPDATE_DELAY, Vec::new())
+    };
+
+    println!(
+        "Searching {}/24 on port {} for Pi-holes",
+        network,
+        ports
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let found = Handle::current().block_on(discover::discover(network, ports));
+    let found = discover::not_configured(found, &config.servers);
+    if found.is_empty() {
+        println!("No Pi-holes found which are not configured already");
+        return Ok(());
+    }
+
+    let mut added = 0;
+    for server in found {
+        println!(
+            "Found Pi-hole v{} at {}",
+            u8::from(server.api_version),
+            server.host
+        );
+        if !confirm("Add it?", true)? {
+            continue;
+        }
+        let name = prompt("Server name", Some(&server.default_name()))?;
+        let api_key = prompt_api_key(server.api_version)?;
+        config.servers.push(PiHoleServerConfig::new(
+            name,
+            server.host,
+            api_key,
+            server.api_version,
+        ));
+        added += 1;
+    }
+    if added > 0 {
+        util::write_config(&path, &config)?;
+        println!("Added {} servers to {}", added, path.display());
+    }
+    Ok(())
+}
+
+/// Fetches the summary, and with an API key an authenticated endpoint, returning the number of queries today
+fn test_connection(
+    host: &str,
+    api_key: Option<String>,
+    api_version: ApiVersion,
+) -> Result<String, String> {
+    let api_config = PiHoleConfigImplementation::new(
+        host.to_string(),
+        api_key,
+        api_version,
+        &HttpOptions {
+            request_timeout: Duration::from_millis(util::DEFAULT_REQUEST_TIMEOUT_MS),
+            basic_auth: None,
+            proxy: None,
+            insecure_tls: false,
+            resolve: None,
+        },
+    )?;
+    Handle::current().block_on(async {
+        let result = async {
+            let summary = api_config.get_summary().await?;
+            if api_config.is_authenticated() {
+                api_config.get_top_items(1).await?;
+            }
+            Ok::<_, APIError>(summary.dns_queries_today)
+        }
+        .await;
+        api_config.logout().await;
+        result.map_err(|error| util::describe_api_error(&error))
+    })
+}
+
+/// Asks a question on stdin, an empty answer selects `default` if there is one
+fn prompt(question: &str, default: Option<&str>) -> io::Result<String> {
+    loop {
+        match default {
+            Some(default) => print!("{} [{}]: ", question, default),
+            None => print!("{}: ", question),
+        }
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Setup cancelled",
+            ));
+        }
+        let answer = answer.trim();
+        match (answer.is_empty(), default) {
+            (false, _) => return Ok(answer.to_string()),
+            (true, Some(default)) => return Ok(default.to_string()),
+            (true, None) => println!("An answer is required"),
+        }
+    }
+}
+
+fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    let default_answer = if default { "y" } else { "n" };
+    loop {
+        match prompt(&format!("{} (y/n)", question), Some(default_answer))?
+            .to_lowercase()
+            .as_str()
+        {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n"),
+        }
+    }
+}
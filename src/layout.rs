@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Main section of the Overview view
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    /// The row of summary boxes
+    Overview,
+    /// Queries or client activity chart
+    Chart,
+    /// Top Queries, Top Ads and Top Clients
+    Statistics,
+}
+
+/// Entry of the `sections` of the `layout` config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionConfig {
+    pub section: Section,
+    /// Share of the height below the tabs. The overview keeps the height of its boxes without
+    /// one, the other sections without one share what the percentages leave.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u16>,
+}
+
+/// The `layout` section of the config, sections not listed are hidden
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutConfig {
+    /// Shown from top to bottom in this order
+    pub sections: Vec<SectionConfig>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            sections: vec![
+                SectionConfig {
+                    section: Section::Overview,
+                    percentage: None,
+                },
+                SectionConfig {
+                    section: Section::Chart,
+                    percentage: Some(50),
+                },
+                SectionConfig {
+                    section: Section::Statistics,
+                    percentage: Some(50),
+                },
+            ],
+        }
+    }
+}
+
+impl LayoutConfig {
+    pub fn is_default(&self) -> bool {
+        *self == LayoutConfig::default()
+    }
+
+    /// Checks that there is a section, none is listed twice and the percentages add up to at
+    /// most 100
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sections.is_empty() {
+            return Err("Invalid layout: no sections".to_string());
+        }
+        for (index, section) in self.sections.iter().enumerate() {
+            if self.sections[..index]
+                .iter()
+                .any(|previous| previous.section == section.section)
+            {
+                return Err(format!(
+                    "Invalid layout: {:?} is listed twice",
+                    section.section
+                ));
+            }
+        }
+        let total: u16 = self
+            .sections
+            .iter()
+            .filter_map(|section| section.percentage)
+            .sum();
+        if total > 100 {
+            return Err(format!(
+                "Invalid layout: the percentages add up to {}",
+                total
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn shows(&self, section: Section) -> bool {
+        self.sections.iter().any(|shown| shown.section == section)
+    }
+
+    /// Percentage of the height given to each section in order, `None` for an overview
+    /// keeping the height of its boxes
+    pub fn percentages(&self) -> Vec<(Section, Option<u16>)> {
+        let given: u16 = self
+            .sections
+            .iter()
+            .filter_map(|section| section.percentage)
+            .sum();
+        let sharing = self
+            .sections
+            .iter()
+            .filter(|section| section.percentage.is_none() && section.section != Section::Overview)
+            .count() as u16;
+        let share = (100 - given.min(100))
+            .checked_div(sharing)
+            .unwrap_or_default();
+        self.sections
+            .iter()
+            .map(|section| match (section.section, section.percentage) {
+                (_, Some(percentage)) => (section.section, Some(percentage)),
+                (Section::Overview, None) => (Section::Overview, None),
+                (_, None) => (section.section, Some(share)),
+            })
+            .collect()
+    }
+}
@@ -1,40 +1,211 @@
-// mod custom_event;
+mod alerts;
+mod api_v5;
+mod api_v6;
+mod clipboard;
+mod custom_event;
+mod discover;
+mod dns_probe;
+mod export;
+mod exporter;
+mod history;
+mod init;
+mod layout;
+mod logger;
+mod notify;
+mod numbers;
+mod redact;
+mod theme;
+mod thresholds;
+mod tunnel;
 mod ui;
 mod util;
+mod webhook;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-// use custom_event::{Config, CustomEvent, CustomEvents};
-use std::path::PathBuf;
-use std::{
-    error::Error,
-    io,
-    time::{Duration, Instant},
-};
+use custom_event::{Config, CustomEvent, CustomEvents};
+use log::LevelFilter;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::{error::Error, io, panic, thread};
 use structopt::StructOpt;
-use tui::{backend::CrosstermBackend, Terminal};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+use util::App;
 
 #[derive(StructOpt)]
 struct Cli {
-    /// Path to configuration file
-    #[structopt(short, long, default_value("pimon.json"))]
-    config_file_path: PathBuf,
+    /// Path to configuration file. Defaults to the first pimon.{json,toml,yaml,yml} found in
+    /// $XDG_CONFIG_HOME/pimon, ~/.config/pimon and the current directory
+    #[structopt(short, long)]
+    config_file_path: Option<PathBuf>,
+
+    /// Serve Prometheus metrics for the configured servers on this address e.g. 0.0.0.0:9617
+    #[structopt(long)]
+    exporter: Option<SocketAddr>,
+
+    /// Only run the exporter, without the terminal interface
+    #[structopt(long, requires("exporter"))]
+    no_tui: bool,
+
+    /// Append failed and slow requests, errors and panics to this file
+    #[structopt(long)]
+    log_file: Option<PathBuf>,
+
+    /// Most detailed messages written to the log file: off, error, warn, info, debug or trace
+    #[structopt(long, default_value = "info")]
+    log_level: LevelFilter,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Interactively create a configuration file
+    Init,
+    /// Manage API keys stored in the system keyring
+    Secret(SecretCommand),
+    /// Search the network for Pi-holes and add them to the configuration file
+    Discover {
+        /// Network to search instead of the local one, a /24 given by its first address
+        /// e.g. 192.168.1.0
+        #[structopt(long)]
+        network: Option<Ipv4Addr>,
+        /// Ports tried on each address
+        #[structopt(long, use_delimiter = true, default_value = "80,8080")]
+        ports: Vec<u16>,
+    },
+}
+
+#[derive(StructOpt)]
+enum SecretCommand {
+    /// Prompt for the API key of a server and store it in the system keyring
+    Set {
+        /// Name of the server. The key is stored under its `api_key_keyring` entry if configured,
+        /// otherwise under the server name
+        server: String,
+    },
+}
+
+fn run_secret_command(
+    config_file_path: Option<PathBuf>,
+    command: SecretCommand,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        SecretCommand::Set { server } => {
+            let config_file_path = config_file_path.or_else(util::find_config_file);
+            let entry = util::keyring_entry_name(config_file_path.as_deref(), &server)?;
+            let api_key = rpassword::prompt_password(format!("API key for {}: ", server))?;
+            util::store_keyring_secret(&entry, &api_key)
+                .map_err(|error| format!("Could not store keyring entry {}: {}", entry, error))?;
+            println!(
+                "Stored in keyring entry {0:?}, use it with \"api_key_keyring\": {0:?}",
+                entry
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn run() -> Result<(), Box<dyn Error>> {
     // Parse command line arguments
     let args = Cli::from_args();
 
-    let mut app = util::load_server_from_json(&args.config_file_path)?;
+    // stderr is hidden by the interface so messages go to a file
+    if let Some(log_file) = &args.log_file {
+        logger::init(log_file, args.log_level).map_err(|error| {
+            format!(
+                "Could not open the log file {}: {}",
+                log_file.display(),
+                error
+            )
+        })?;
+    }
+
+    // API requests run on the async runtime, the UI stays on this thread
+    let runtime = tokio::runtime::Runtime::new()?;
+    let _runtime_guard = runtime.enter();
+
+    match args.command {
+        Some(Command::Init) => return init::run(args.config_file_path),
+        Some(Command::Secret(command)) => {
+            return run_secret_command(args.config_file_path, command)
+        }
+        Some(Command::Discover { network, ports }) => {
+            return init::discover(args.config_file_path, network, &ports)
+        }
+        None => {}
+    }
 
-    if app.servers.len() == 0 {
+    let config_file_path = match args.config_file_path {
+        Some(path) => path,
+        None => match util::find_config_file() {
+            Some(path) => path,
+            None => {
+                println!("No configuration file found. Searched:");
+                for path in util::config_file_candidates() {
+                    println!("    {}", path.display());
+                }
+                println!("Run `pimon init` to create one");
+                std::process::exit(1);
+            }
+        },
+    };
+    let mut app = util::load_config(&config_file_path)?;
+    log::info!(
+        "Loaded {} with {} servers",
+        config_file_path.display(),
+        app.servers.len()
+    );
+
+    if app.servers.is_empty() {
         println!("Configuration file doesn't contain any servers. Exiting");
         std::process::exit(1);
     }
 
+    if let Some(address) = args.exporter {
+        let exporter = exporter::serve(address, exporter::targets(&app.servers))?;
+        if args.no_tui {
+            println!("Serving metrics on http://{}/metrics", address);
+            runtime.block_on(async {
+                tokio::select! {
+                    result = exporter => result,
+                    _ = tokio::signal::ctrl_c() => Ok(()),
+                }
+            })?;
+            app.logout();
+            return Ok(());
+        }
+        tokio::spawn(exporter);
+    }
+
+    // Restore the terminal before a panic message is printed, otherwise it stays unusable.
+    // Only the interface thread draws, panics on the runtime threads leave it running.
+    let default_panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log::error!("Panic: {}", info);
+        if thread::current().name() == Some("main") {
+            let _ = restore_terminal();
+        }
+        default_panic_hook(info);
+    }));
+
     // Terminal initialization
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -44,67 +215,568 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
     // terminal.hide_cursor()?;
 
-    // // Setup event handlers
-    // let events = Events::with_config(Config {
-    //     tick_rate: Duration::from_millis(1000),
-    //     ..Config::default()
-    // });
+    let result = run_tui(&mut terminal, &mut app, &config_file_path);
+
+    // Restore the terminal even if the interface failed
+    restore_terminal()?;
+    terminal.show_cursor()?;
+
+    app.logout();
+
+    Ok(result?)
+}
 
+/// Leaves the alternate screen and raw mode entered for the interface
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
+}
+
+/// Translates the keys of the vim keymap to the default keys doing the same.
+/// `pending_g` remembers a first `g` of `gg`.
+fn vim_key_code(code: KeyCode, pending_g: &mut bool) -> KeyCode {
+    let was_pending_g = std::mem::replace(pending_g, false);
+    match code {
+        KeyCode::Char('h') => KeyCode::Left,
+        KeyCode::Char('l') => KeyCode::Right,
+        KeyCode::Char('j') => KeyCode::Down,
+        KeyCode::Char('k') => KeyCode::Up,
+        KeyCode::Char('G') => KeyCode::End,
+        KeyCode::Char('g') if was_pending_g => KeyCode::Home,
+        KeyCode::Char('g') => {
+            *pending_g = true;
+            KeyCode::Null
+        }
+        code => code,
+    }
+}
+
+/// Draws the interface and handles input until the user quits. Events arriving together are
+/// handled before drawing once.
+fn run_tui<B>(terminal: &mut Terminal<B>, app: &mut App, config_file_path: &Path) -> io::Result<()>
+where
+    B: Backend,
+{
+    let events = CustomEvents::with_config(Config::default());
+    app.set_data_arrived(events.sender());
+    app.on_resize(terminal.size()?.width);
     app.on_tick();
-    let tick_rate = Duration::from_millis(1000);
-    let mut last_tick = Instant::now();
-    loop {
-        terminal.draw(|mut f| ui::draw_ui(&mut f, &mut app))?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        break;
-                    }
-                    KeyCode::Left => {
-                        app.previous_server();
-                    }
-                    KeyCode::Right => {
-                        app.next_server();
-                    }
-                    KeyCode::Char(' ') => {
-                        app.on_space();
-                    }
-                    KeyCode::Char('z') => {
-                        app.on_z();
-                    }
-                    KeyCode::Char('x') => {
-                        app.on_x();
-                    }
-                    KeyCode::Char('e') => {
-                        app.on_e();
+    let mut pending_g = false;
+    terminal.draw(|f| ui::draw_ui(f, app))?;
+    while let Some(first) = events.next() {
+        let mut next = Some(first);
+        while let Some(event) = next {
+            match event {
+                CustomEvent::Input(event) => {
+                    if !handle_input(app, event, config_file_path, &mut pending_g) {
+                        return Ok(());
                     }
-                    KeyCode::Char('d') => {
-                        app.on_d();
-                    }
-                    _ => {}
+                }
+                CustomEvent::InputFailed(error) => return Err(error),
+                CustomEvent::Tick | CustomEvent::DataArrived => {
+                    app.on_tick();
                 }
             }
+            next = events.try_next();
         }
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
-        }
+        terminal.draw(|f| ui::draw_ui(f, app))?;
     }
-
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
     Ok(())
 }
+
+/// Handles a key press or mouse event, returning whether the interface keeps running
+fn handle_input(
+    app: &mut App,
+    event: Event,
+    config_file_path: &Path,
+    pending_g: &mut bool,
+) -> bool {
+    let overlay_open = app.server_editor.is_some()
+        || app.input_prompt.is_some()
+        || app.confirmation.is_some()
+        || app.selected_domain.is_some()
+        || app.actions_menu_open
+        || app
+            .gravity
+            .as_ref()
+            .is_some_and(|gravity| gravity.popup_open);
+    if let Event::Resize(width, _) = event {
+        // Drawn as soon as the event is handled
+        app.on_resize(width);
+    } else if let Event::Mouse(mouse) = event {
+        // Clicks only reach the view when nothing is drawn over it
+        if !overlay_open {
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    app.on_click(mouse.column, mouse.row);
+                }
+                MouseEventKind::ScrollDown => {
+                    app.on_scroll(mouse.column, mouse.row, true);
+                }
+                MouseEventKind::ScrollUp => {
+                    app.on_scroll(mouse.column, mouse.row, false);
+                }
+                _ => {}
+            }
+        }
+    } else if let Event::Key(mut key) = event {
+        // Text typed into forms is never translated
+        if app.keymap == util::Keymap::Vim && !overlay_open {
+            key.code = vim_key_code(key.code, pending_g);
+        }
+        if app.server_editor.is_some() {
+            match key.code {
+                KeyCode::Esc => {
+                    app.close_server_editor();
+                }
+                KeyCode::Enter => {
+                    app.save_server_editor();
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    app.server_editor_next_field();
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    app.server_editor_previous_field();
+                }
+                KeyCode::Backspace => {
+                    app.server_editor_backspace();
+                }
+                KeyCode::Char(c) => {
+                    app.server_editor_input(c);
+                }
+                _ => {}
+            }
+        } else if app.input_prompt.is_some() {
+            match key.code {
+                KeyCode::Esc => {
+                    app.close_input_prompt();
+                }
+                KeyCode::Enter => {
+                    app.confirm_input_prompt();
+                }
+                KeyCode::Backspace => {
+                    app.input_prompt_backspace();
+                }
+                KeyCode::Char(c) => {
+                    app.input_prompt_input(c);
+                }
+                _ => {}
+            }
+        } else if app.confirmation.is_some() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    app.accept_confirmation();
+                }
+                KeyCode::Char('n') | KeyCode::Esc => {
+                    app.cancel_confirmation();
+                }
+                _ => {}
+            }
+        } else if app
+            .gravity
+            .as_ref()
+            .is_some_and(|gravity| gravity.popup_open)
+        {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Esc | KeyCode::Enter => {
+                    app.close_gravity_popup();
+                }
+                _ => {}
+            }
+        } else if app.actions_menu_open {
+            match key.code {
+                KeyCode::Esc => {
+                    app.close_actions_menu();
+                }
+                KeyCode::Char(c) => {
+                    app.on_action_key(c);
+                }
+                _ => {}
+            }
+        } else if app.selected_domain.is_some() {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Esc | KeyCode::Enter => {
+                    app.close_domain_popup();
+                }
+                KeyCode::Char('y') => {
+                    app.copy_selected();
+                }
+                KeyCode::Char('r') => {
+                    app.on_regex_block();
+                }
+                KeyCode::Char(' ') => {
+                    app.on_space();
+                }
+                _ => {}
+            }
+        } else if app.view == util::View::Lists {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Left => {
+                    app.previous_server();
+                }
+                KeyCode::Right => {
+                    app.next_server();
+                }
+                KeyCode::Char(c @ '0'..='9') => {
+                    app.select_server_number(c as usize - '0' as usize);
+                }
+                KeyCode::F(number) => {
+                    app.select_page_number(number);
+                }
+                KeyCode::Tab => {
+                    app.next_list();
+                }
+                KeyCode::BackTab => {
+                    app.previous_list();
+                }
+                KeyCode::Down => {
+                    app.next_list_domain();
+                }
+                KeyCode::Up => {
+                    app.previous_list_domain();
+                }
+                KeyCode::PageDown => {
+                    app.next_list_page();
+                }
+                KeyCode::Home => {
+                    app.first_list_domain();
+                }
+                KeyCode::End => {
+                    app.last_list_domain();
+                }
+                KeyCode::PageUp => {
+                    app.previous_list_page();
+                }
+                KeyCode::Char('n') => {
+                    app.on_add_domain();
+                }
+                KeyCode::Char('u') => {
+                    app.on_u();
+                }
+                KeyCode::Delete => {
+                    app.on_remove_domain();
+                }
+                KeyCode::Char('y') => {
+                    app.copy_selected();
+                }
+                KeyCode::Char(' ') => {
+                    app.on_space();
+                }
+                KeyCode::Char('B') | KeyCode::Esc => {
+                    app.toggle_lists();
+                }
+                _ => {}
+            }
+        } else if app.view == util::View::LocalDns {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Left => {
+                    app.previous_server();
+                }
+                KeyCode::Right => {
+                    app.next_server();
+                }
+                KeyCode::Char(c @ '0'..='9') => {
+                    app.select_server_number(c as usize - '0' as usize);
+                }
+                KeyCode::F(number) => {
+                    app.select_page_number(number);
+                }
+                KeyCode::Tab | KeyCode::BackTab => {
+                    app.next_record_type();
+                }
+                KeyCode::Down => {
+                    app.next_local_record();
+                }
+                KeyCode::Up => {
+                    app.previous_local_record();
+                }
+                KeyCode::PageDown => {
+                    app.next_local_record_page();
+                }
+                KeyCode::PageUp => {
+                    app.previous_local_record_page();
+                }
+                KeyCode::Home => {
+                    app.first_local_record();
+                }
+                KeyCode::End => {
+                    app.last_local_record();
+                }
+                KeyCode::Char('n') => {
+                    app.on_add_local_record();
+                }
+                KeyCode::Delete => {
+                    app.on_remove_local_record();
+                }
+                KeyCode::Char('y') => {
+                    app.copy_selected();
+                }
+                KeyCode::Char(' ') => {
+                    app.on_space();
+                }
+                KeyCode::Esc => {
+                    app.select_page_number(1);
+                }
+                _ => {}
+            }
+        } else if app.view == util::View::Adlists {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Left => {
+                    app.previous_server();
+                }
+                KeyCode::Right => {
+                    app.next_server();
+                }
+                KeyCode::Char(c @ '0'..='9') => {
+                    app.select_server_number(c as usize - '0' as usize);
+                }
+                KeyCode::F(number) => {
+                    app.select_page_number(number);
+                }
+                KeyCode::Down => {
+                    app.next_adlist();
+                }
+                KeyCode::Up => {
+                    app.previous_adlist();
+                }
+                KeyCode::PageDown => {
+                    app.next_adlist_page();
+                }
+                KeyCode::PageUp => {
+                    app.previous_adlist_page();
+                }
+                KeyCode::Home => {
+                    app.first_adlist();
+                }
+                KeyCode::End => {
+                    app.last_adlist();
+                }
+                KeyCode::Char('n') => {
+                    app.on_add_adlist();
+                }
+                KeyCode::Char('e') => {
+                    app.toggle_selected_adlist();
+                }
+                KeyCode::Char('y') => {
+                    app.copy_selected();
+                }
+                KeyCode::Char('U') => {
+                    app.on_gravity();
+                }
+                KeyCode::Char(' ') => {
+                    app.on_space();
+                }
+                KeyCode::Esc => {
+                    app.select_page_number(1);
+                }
+                _ => {}
+            }
+        } else {
+            match key.code {
+                KeyCode::Char('q') => {
+                    return false;
+                }
+                KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    app.move_server_left();
+                }
+                KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    app.move_server_right();
+                }
+                KeyCode::Left => {
+                    app.previous_server();
+                }
+                KeyCode::Right => {
+                    app.next_server();
+                }
+                KeyCode::Char(c @ '0'..='9') => {
+                    app.select_server_number(c as usize - '0' as usize);
+                }
+                KeyCode::F(number) => {
+                    app.select_page_number(number);
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.save(config_file_path);
+                }
+                KeyCode::Char(' ') => {
+                    app.on_space();
+                }
+                KeyCode::Char('z') => {
+                    app.on_z();
+                }
+                KeyCode::Char('x') => {
+                    app.on_x();
+                }
+                KeyCode::Char('e') => {
+                    app.on_e();
+                }
+                KeyCode::Char('d') => {
+                    app.on_d();
+                }
+                KeyCode::Char('L') => {
+                    app.toggle_query_log();
+                }
+                KeyCode::Char('f') if app.view == util::View::QueryLog => {
+                    app.toggle_follow_query_log();
+                }
+                KeyCode::Char('U') => {
+                    app.on_gravity();
+                }
+                KeyCode::Char('X') => {
+                    app.open_actions_menu();
+                }
+                KeyCode::Char('B') => {
+                    app.toggle_lists();
+                }
+                KeyCode::Char('H') => {
+                    app.toggle_long_term();
+                }
+                KeyCode::Char('D') => {
+                    app.toggle_diagnostics();
+                }
+                KeyCode::Char('A') => {
+                    app.toggle_alerts();
+                }
+                KeyCode::Char('{') => {
+                    app.previous_group();
+                }
+                KeyCode::Char('}') => {
+                    app.next_group();
+                }
+                KeyCode::Char('S') => {
+                    app.toggle_split();
+                }
+                KeyCode::Char('p') => {
+                    app.toggle_paused();
+                }
+                KeyCode::Char('F') => {
+                    app.discover_servers();
+                }
+                KeyCode::Char('E') => {
+                    app.export_csv();
+                }
+                KeyCode::Char('s') => {
+                    app.export_snapshot();
+                }
+                KeyCode::Tab => {
+                    app.next_panel();
+                }
+                KeyCode::BackTab => {
+                    app.previous_panel();
+                }
+                KeyCode::Down => {
+                    app.next_statistics_row();
+                }
+                KeyCode::Up => {
+                    app.previous_statistics_row();
+                }
+                KeyCode::PageDown => {
+                    app.next_statistics_page();
+                }
+                KeyCode::Home => {
+                    app.first_statistics_row();
+                }
+                KeyCode::End => {
+                    app.last_statistics_row();
+                }
+                KeyCode::PageUp => {
+                    app.previous_statistics_page();
+                }
+                KeyCode::Esc if app.view == util::View::Client => {
+                    app.close_client();
+                }
+                KeyCode::Esc => {
+                    app.on_esc();
+                }
+                KeyCode::Enter if app.view == util::View::Overview => {
+                    app.on_enter();
+                }
+                KeyCode::Char('/') => {
+                    app.on_slash();
+                }
+                KeyCode::Char('b') => {
+                    app.on_b();
+                }
+                KeyCode::Char('w') => {
+                    app.on_w();
+                }
+                KeyCode::Char('r') => {
+                    app.on_regex_block();
+                }
+                KeyCode::Char('y') => {
+                    app.copy_selected();
+                }
+                KeyCode::Char('u') => {
+                    app.on_u();
+                }
+                KeyCode::Char('c') => {
+                    app.toggle_chart_mode();
+                }
+                KeyCode::Char('C') => {
+                    app.toggle_over_time_chart();
+                }
+                KeyCode::Char('v') => {
+                    app.toggle_top_clients_blocked();
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    app.increase_top_items_count();
+                }
+                KeyCode::Char('-') => {
+                    app.decrease_top_items_count();
+                }
+                KeyCode::Char('<') => {
+                    app.decrease_update_delay();
+                }
+                KeyCode::Char('>') => {
+                    app.increase_update_delay();
+                }
+                KeyCode::Char('[') if app.view == util::View::LongTerm => {
+                    app.shorten_long_term_period();
+                }
+                KeyCode::Char(']') if app.view == util::View::LongTerm => {
+                    app.lengthen_long_term_period();
+                }
+                KeyCode::Char('[') => {
+                    app.shorten_chart_window();
+                }
+                KeyCode::Char(']') => {
+                    app.lengthen_chart_window();
+                }
+                KeyCode::Char(',') => {
+                    app.pan_chart_back();
+                }
+                KeyCode::Char('.') => {
+                    app.pan_chart_forward();
+                }
+                KeyCode::Char('t') => {
+                    app.next_theme_preset();
+                }
+                KeyCode::Char('R') => {
+                    app.toggle_redact();
+                }
+                KeyCode::Char('a') => {
+                    app.add_server();
+                }
+                KeyCode::Char('m') => {
+                    app.edit_server();
+                }
+                _ => {}
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,46 @@
+use crate::alerts::Alert;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Shows `alert` as a desktop notification with `notify-send` on Linux and the BSDs or
+/// `osascript` on macOS. The command runs on its own thread so a missing notification daemon
+/// cannot hold up the interface, failures are only logged.
+pub fn send(alert: &Alert) {
+    let title = format!("pimon: {}", alert.server);
+    let mut command = notification_command(&title, &alert.message);
+    thread::spawn(move || {
+        let result = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match result {
+            Ok(status) if status.success() => {}
+            Ok(status) => log::warn!("Desktop notification failed with {}", status),
+            Err(error) => log::warn!("Could not show a desktop notification: {}", error),
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!(
+        "display notification {} with title {}",
+        applescript_string(body),
+        applescript_string(title)
+    ));
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", "pimon", title, body]);
+    command
+}
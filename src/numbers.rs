@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Terminals narrower than this many columns show compact counts with `auto`
+pub const COMPACT_WIDTH: u16 = 120;
+
+/// Suffixes of thousands, millions, billions and trillions
+const UNITS: [&str; 4] = ["k", "M", "G", "T"];
+
+/// How counts are written
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// Every digit, grouped by thousands e.g. `45,120`
+    #[default]
+    Full,
+    /// Rounded to 3 digits with a suffix e.g. `45.1k`, `1.2M`
+    Compact,
+    /// Compact while the terminal is narrower than `COMPACT_WIDTH`, full otherwise
+    Auto,
+}
+
+/// Writes counts with the separators of the locale, read from `LC_ALL`, `LC_NUMERIC` or
+/// `LANG`
+#[derive(Debug, Clone)]
+pub struct Numbers {
+    pub format: NumberFormat,
+    /// Between each group of 3 digits
+    thousands: char,
+    /// Before the fraction of compact counts
+    decimal: char,
+}
+
+impl Numbers {
+    pub fn new(format: NumberFormat) -> Self {
+        let (thousands, decimal) = separators(&locale());
+        Numbers {
+            format,
+            thousands,
+            decimal,
+        }
+    }
+
+    /// `count` in the configured format, `narrow` for a terminal too narrow for full counts
+    pub fn format(&self, count: u64, narrow: bool) -> String {
+        match self.format {
+            NumberFormat::Compact => self.compact(count, 1),
+            NumberFormat::Auto if narrow => self.compact(count, 1),
+            _ => self.full(count),
+        }
+    }
+
+    /// `count` with every digit e.g. `45,120`
+    pub fn full(&self, count: u64) -> String {
+        let digits = count.to_string();
+        let mut formatted = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                formatted.push(self.thousands);
+            }
+            formatted.push(digit);
+        }
+        formatted
+    }
+
+    /// `count` rounded to a suffix, with up to `decimals` digits after the point while it
+    /// is below 100 e.g. `45.1k`. Counts below 1000 are written as they are.
+    pub fn compact(&self, count: u64, decimals: usize) -> String {
+        if count < 1000 {
+            return count.to_string();
+        }
+        let mut value = count as f64 / 1000.0;
+        let mut unit = 0;
+        // Rounding up to 1000 moves on to the next suffix
+        while value >= 999.5 && unit < UNITS.len() - 1 {
+            value /= 1000.0;
+            unit += 1;
+        }
+        let decimals = if value < 99.95 { decimals } else { 0 };
+        format!("{:.*}{}", decimals, value, UNITS[unit]).replace('.', &self.decimal.to_string())
+    }
+
+    /// `count` in the configured format if narrower than `width`, or else compact and then
+    /// compact without decimals, `None` if neither is
+    pub fn fitting(&self, count: u64, width: usize, narrow: bool) -> Option<String> {
+        [
+            self.format(count, narrow),
+            self.compact(count, 1),
+            self.compact(count, 0),
+        ]
+        .iter()
+        .find(|formatted| formatted.chars().count() < width)
+        .cloned()
+    }
+}
+
+/// Language and territory of the locale e.g. `de_CH` of `de_CH.UTF-8`, empty without one
+fn locale() -> String {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| value.split(['.', '@']).next().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Thousands and decimal separators of `locale`, those of English unless known otherwise
+fn separators(locale: &str) -> (char, char) {
+    let (language, territory) = locale.split_once('_').unwrap_or((locale, ""));
+    match (language, territory) {
+        ("de" | "it" | "rm", "CH" | "LI") => ('\'', '.'),
+        (
+            "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr" | "vi",
+            _,
+        ) => ('.', ','),
+        // A space that does not break the count across lines
+        (
+            "fr" | "ru" | "uk" | "be" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no"
+            | "hu" | "bg" | "lt" | "lv" | "et" | "kk",
+            _,
+        ) => ('\u{a0}', ','),
+        _ => (',', '.'),
+    }
+}
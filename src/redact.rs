@@ -0,0 +1,71 @@
+use std::net::IpAddr;
+
+/// Replaces the hidden part of a name, always as long so it does not give away the length
+const MASK: &str = "*****";
+
+/// Keeps the start of `label`, at most 4 characters and less for short labels
+fn mask_label(label: &str) -> String {
+    let kept = ((label.chars().count() + 1) / 3).min(4);
+    label.chars().take(kept).chain(MASK.chars()).collect()
+}
+
+/// Masks the name registered below the top level domain, e.g. `ads.doub*****.net`
+pub fn domain(domain: &str) -> String {
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return mask_label(domain);
+    }
+    let registered = labels.len() - 2;
+    labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            if index == registered {
+                mask_label(label)
+            } else {
+                label.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// Masks the host part of an address, e.g. `192.168.1.xxx`. Anything else is taken as a host
+/// name.
+pub fn address(address: &str) -> String {
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            let octets = ip.octets();
+            format!("{}.{}.{}.xxx", octets[0], octets[1], octets[2])
+        }
+        // The network prefix, the interface identifier is hidden
+        Ok(IpAddr::V6(ip)) => {
+            let segments = ip.segments();
+            format!(
+                "{:x}:{:x}:{:x}:{:x}:xxxx",
+                segments[0], segments[1], segments[2], segments[3]
+            )
+        }
+        Err(_) => hostname(address),
+    }
+}
+
+/// Masks the name of the device, keeping its local domain, e.g. `lap*****.lan`
+pub fn hostname(hostname: &str) -> String {
+    if hostname.is_empty() {
+        return String::new();
+    }
+    match hostname.split_once('.') {
+        Some((name, domain)) => format!("{}.{}", mask_label(name), domain),
+        None => mask_label(hostname),
+    }
+}
+
+/// Keeps the manufacturer part of a MAC address, e.g. `aa:bb:cc:xx:xx:xx`
+pub fn mac(mac: &str) -> String {
+    mac.split(':')
+        .enumerate()
+        .map(|(index, part)| if index < 3 { part } else { "xx" })
+        .collect::<Vec<&str>>()
+        .join(":")
+}
@@ -0,0 +1,369 @@
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use tui::style::Color;
+
+/// Built-in set of colours that the overrides of a `ThemeConfig` are applied on top of
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    Default,
+    Gruvbox,
+    SolarizedDark,
+    Dracula,
+    /// Only black, white and greys
+    Monochrome,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 5] = [
+        Preset::Default,
+        Preset::Gruvbox,
+        Preset::SolarizedDark,
+        Preset::Dracula,
+        Preset::Monochrome,
+    ];
+
+    /// Name as written in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Default => "default",
+            Preset::Gruvbox => "gruvbox",
+            Preset::SolarizedDark => "solarized-dark",
+            Preset::Dracula => "dracula",
+            Preset::Monochrome => "monochrome",
+        }
+    }
+
+    /// The preset after this one, wrapping around to the first
+    pub fn next(self) -> Preset {
+        let index = Preset::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .unwrap_or_default();
+        Preset::ALL[(index + 1) % Preset::ALL.len()]
+    }
+}
+
+/// Part of the interface whose colour can be overridden
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeElement {
+    /// Background of the help bar
+    HelpBar,
+    HelpBarText,
+    /// Background of status messages reporting success
+    Success,
+    /// Background of error messages, and text of errors and blocked queries
+    Error,
+    ErrorText,
+    /// Enabled blocking, allowed queries and servers updated without errors
+    Good,
+    /// Servers with failed requests, available updates and anything else worth a look
+    Warning,
+    /// Hints and servers that have not been updated yet
+    Muted,
+    /// Names of servers that have not been updated yet
+    TabName,
+    TabHighlight,
+    /// Bars of the queries chart and query types
+    Chart,
+    /// Counts drawn on the bars of the queries chart
+    ChartValue,
+    TableHeader,
+    TableText,
+    /// Background of the selected row and border of the highlighted list
+    Selection,
+    SelectionText,
+    /// Labels of forms and prompts
+    Accent,
+}
+
+/// Colour as written in the config, a name such as `lightgreen`, `#rrggbb` or a
+/// 256 colour palette index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor {
+    name: String,
+    color: Color,
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let color = parse_color(&name)?;
+        Ok(ThemeColor { name, color })
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        color.name
+    }
+}
+
+/// The `theme` section of the config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub preset: Preset,
+    /// Colours replacing those of the preset, by the snake case name of a `ThemeElement`.
+    /// The keys are strings as TOML tables cannot be read into an enum.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, ThemeColor>,
+}
+
+/// Colours used to draw the interface
+pub struct Theme {
+    pub help_bar: Color,
+    pub help_bar_text: Color,
+    pub success: Color,
+    pub error: Color,
+    pub error_text: Color,
+    pub good: Color,
+    pub warning: Color,
+    pub muted: Color,
+    pub tab_name: Color,
+    pub tab_highlight: Color,
+    pub chart: Color,
+    pub chart_value: Color,
+    pub table_header: Color,
+    pub table_text: Color,
+    pub selection: Color,
+    pub selection_text: Color,
+    pub accent: Color,
+    /// Colours of the clients in the client activity chart, in turn
+    pub series: [Color; 8],
+}
+
+impl Theme {
+    /// Colours of the configured preset with the overrides applied
+    pub fn new(config: &ThemeConfig) -> Result<Self, String> {
+        let mut theme = Theme::preset(config.preset);
+        for (name, color) in &config.overrides {
+            let element = ThemeElement::deserialize(name.as_str().into_deserializer())
+                .map_err(|error: serde::de::value::Error| format!("Invalid theme: {}", error))?;
+            theme.set(element, color.color);
+        }
+        Ok(theme)
+    }
+
+    fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Default => Theme {
+                help_bar: Color::Cyan,
+                help_bar_text: Color::Reset,
+                success: Color::Green,
+                error: Color::Red,
+                error_text: Color::White,
+                good: Color::LightGreen,
+                warning: Color::LightYellow,
+                muted: Color::DarkGray,
+                tab_name: Color::LightYellow,
+                tab_highlight: Color::LightGreen,
+                chart: Color::Green,
+                chart_value: Color::Black,
+                table_header: Color::LightCyan,
+                table_text: Color::LightGreen,
+                selection: Color::LightYellow,
+                selection_text: Color::Black,
+                accent: Color::Cyan,
+                series: [
+                    Color::LightCyan,
+                    Color::LightMagenta,
+                    Color::LightYellow,
+                    Color::LightGreen,
+                    Color::LightBlue,
+                    Color::LightRed,
+                    Color::Cyan,
+                    Color::Magenta,
+                ],
+            },
+            // Colours of the gruvbox dark palette
+            Preset::Gruvbox => Theme {
+                help_bar: Color::Rgb(0x50, 0x49, 0x45),
+                help_bar_text: Color::Rgb(0xeb, 0xdb, 0xb2),
+                success: Color::Rgb(0x98, 0x97, 0x1a),
+                error: Color::Rgb(0xfb, 0x49, 0x34),
+                error_text: Color::Rgb(0x28, 0x28, 0x28),
+                good: Color::Rgb(0xb8, 0xbb, 0x26),
+                warning: Color::Rgb(0xfa, 0xbd, 0x2f),
+                muted: Color::Rgb(0x92, 0x83, 0x74),
+                tab_name: Color::Rgb(0xfe, 0x80, 0x19),
+                tab_highlight: Color::Rgb(0x8e, 0xc0, 0x7c),
+                chart: Color::Rgb(0x83, 0xa5, 0x98),
+                chart_value: Color::Rgb(0x28, 0x28, 0x28),
+                table_header: Color::Rgb(0xd3, 0x86, 0x9b),
+                table_text: Color::Rgb(0xeb, 0xdb, 0xb2),
+                selection: Color::Rgb(0xfa, 0xbd, 0x2f),
+                selection_text: Color::Rgb(0x28, 0x28, 0x28),
+                accent: Color::Rgb(0x83, 0xa5, 0x98),
+                series: [
+                    Color::Rgb(0x83, 0xa5, 0x98),
+                    Color::Rgb(0xd3, 0x86, 0x9b),
+                    Color::Rgb(0xfa, 0xbd, 0x2f),
+                    Color::Rgb(0xb8, 0xbb, 0x26),
+                    Color::Rgb(0xfe, 0x80, 0x19),
+                    Color::Rgb(0x8e, 0xc0, 0x7c),
+                    Color::Rgb(0xfb, 0x49, 0x34),
+                    Color::Rgb(0xeb, 0xdb, 0xb2),
+                ],
+            },
+            Preset::SolarizedDark => Theme {
+                help_bar: Color::Rgb(0x07, 0x36, 0x42),
+                help_bar_text: Color::Rgb(0x93, 0xa1, 0xa1),
+                success: Color::Rgb(0x85, 0x99, 0x00),
+                error: Color::Rgb(0xdc, 0x32, 0x2f),
+                error_text: Color::Rgb(0xfd, 0xf6, 0xe3),
+                good: Color::Rgb(0x85, 0x99, 0x00),
+                warning: Color::Rgb(0xb5, 0x89, 0x00),
+                muted: Color::Rgb(0x58, 0x6e, 0x75),
+                tab_name: Color::Rgb(0x83, 0x94, 0x96),
+                tab_highlight: Color::Rgb(0x26, 0x8b, 0xd2),
+                chart: Color::Rgb(0x2a, 0xa1, 0x98),
+                chart_value: Color::Rgb(0x00, 0x2b, 0x36),
+                table_header: Color::Rgb(0x6c, 0x71, 0xc4),
+                table_text: Color::Rgb(0x93, 0xa1, 0xa1),
+                selection: Color::Rgb(0xcb, 0x4b, 0x16),
+                selection_text: Color::Rgb(0xfd, 0xf6, 0xe3),
+                accent: Color::Rgb(0x26, 0x8b, 0xd2),
+                series: [
+                    Color::Rgb(0x26, 0x8b, 0xd2),
+                    Color::Rgb(0xd3, 0x36, 0x82),
+                    Color::Rgb(0xb5, 0x89, 0x00),
+                    Color::Rgb(0x85, 0x99, 0x00),
+                    Color::Rgb(0x2a, 0xa1, 0x98),
+                    Color::Rgb(0xcb, 0x4b, 0x16),
+                    Color::Rgb(0x6c, 0x71, 0xc4),
+                    Color::Rgb(0xdc, 0x32, 0x2f),
+                ],
+            },
+            Preset::Dracula => Theme {
+                help_bar: Color::Rgb(0x44, 0x47, 0x5a),
+                help_bar_text: Color::Rgb(0xf8, 0xf8, 0xf2),
+                success: Color::Rgb(0x50, 0xfa, 0x7b),
+                error: Color::Rgb(0xff, 0x55, 0x55),
+                error_text: Color::Rgb(0x28, 0x2a, 0x36),
+                good: Color::Rgb(0x50, 0xfa, 0x7b),
+                warning: Color::Rgb(0xf1, 0xfa, 0x8c),
+                muted: Color::Rgb(0x62, 0x72, 0xa4),
+                tab_name: Color::Rgb(0xff, 0x79, 0xc6),
+                tab_highlight: Color::Rgb(0x8b, 0xe9, 0xfd),
+                chart: Color::Rgb(0xbd, 0x93, 0xf9),
+                chart_value: Color::Rgb(0x28, 0x2a, 0x36),
+                table_header: Color::Rgb(0x8b, 0xe9, 0xfd),
+                table_text: Color::Rgb(0xf8, 0xf8, 0xf2),
+                selection: Color::Rgb(0xff, 0xb8, 0x6c),
+                selection_text: Color::Rgb(0x28, 0x2a, 0x36),
+                accent: Color::Rgb(0xbd, 0x93, 0xf9),
+                series: [
+                    Color::Rgb(0x8b, 0xe9, 0xfd),
+                    Color::Rgb(0xff, 0x79, 0xc6),
+                    Color::Rgb(0xf1, 0xfa, 0x8c),
+                    Color::Rgb(0x50, 0xfa, 0x7b),
+                    Color::Rgb(0xbd, 0x93, 0xf9),
+                    Color::Rgb(0xff, 0xb8, 0x6c),
+                    Color::Rgb(0xff, 0x55, 0x55),
+                    Color::Rgb(0xf8, 0xf8, 0xf2),
+                ],
+            },
+            Preset::Monochrome => Theme {
+                help_bar: Color::Gray,
+                help_bar_text: Color::Black,
+                success: Color::Gray,
+                error: Color::White,
+                error_text: Color::Black,
+                good: Color::Reset,
+                warning: Color::White,
+                muted: Color::DarkGray,
+                tab_name: Color::Reset,
+                tab_highlight: Color::White,
+                chart: Color::Gray,
+                chart_value: Color::Black,
+                table_header: Color::White,
+                table_text: Color::Reset,
+                selection: Color::White,
+                selection_text: Color::Black,
+                accent: Color::White,
+                series: [
+                    Color::White,
+                    Color::Gray,
+                    Color::DarkGray,
+                    Color::White,
+                    Color::Gray,
+                    Color::DarkGray,
+                    Color::White,
+                    Color::Gray,
+                ],
+            },
+        }
+    }
+
+    fn set(&mut self, element: ThemeElement, color: Color) {
+        let field = match element {
+            ThemeElement::HelpBar => &mut self.help_bar,
+            ThemeElement::HelpBarText => &mut self.help_bar_text,
+            ThemeElement::Success => &mut self.success,
+            ThemeElement::Error => &mut self.error,
+            ThemeElement::ErrorText => &mut self.error_text,
+            ThemeElement::Good => &mut self.good,
+            ThemeElement::Warning => &mut self.warning,
+            ThemeElement::Muted => &mut self.muted,
+            ThemeElement::TabName => &mut self.tab_name,
+            ThemeElement::TabHighlight => &mut self.tab_highlight,
+            ThemeElement::Chart => &mut self.chart,
+            ThemeElement::ChartValue => &mut self.chart_value,
+            ThemeElement::TableHeader => &mut self.table_header,
+            ThemeElement::TableText => &mut self.table_text,
+            ThemeElement::Selection => &mut self.selection,
+            ThemeElement::SelectionText => &mut self.selection_text,
+            ThemeElement::Accent => &mut self.accent,
+        };
+        *field = color;
+    }
+}
+
+/// Parses a colour name ignoring case, `-` and `_`, a `#rrggbb` hex colour or a palette index
+fn parse_color(name: &str) -> Result<Color, String> {
+    let normalised = name.to_lowercase().replace(['-', '_', ' '], "");
+    if let Some(hex) = normalised.strip_prefix('#') {
+        let value = match hex.len() {
+            6 => u32::from_str_radix(hex, 16).ok(),
+            _ => None,
+        };
+        return value
+            .map(|value| Color::Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+            .ok_or_else(|| format!("Invalid colour {:?}, expected #rrggbb", name));
+    }
+    if let Ok(index) = normalised.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+    Ok(match normalised.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => {
+            return Err(format!(
+                "Unknown colour {:?}, expected a name such as lightgreen, #rrggbb or 0-255",
+                name
+            ))
+        }
+    })
+}
@@ -0,0 +1,51 @@
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
+use tui::style::Color;
+
+/// Values from which a metric is shown as a warning and as critical
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Threshold {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+impl Threshold {
+    /// Good below the warning value, a warning from it and an error from the critical value
+    pub fn colour(&self, value: f64, theme: &Theme) -> Color {
+        if value >= self.critical {
+            theme.error
+        } else if value >= self.warning {
+            theme.warning
+        } else {
+            theme.good
+        }
+    }
+}
+
+/// The `thresholds` section of the config, metrics without one are not coloured
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdsConfig {
+    /// Share of blocked queries today
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ads_percentage: Option<Threshold>,
+    /// Load average of the last minute, only reported by the v6 API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<Threshold>,
+    /// Number of servers that could not be reached, on the All tab
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unreachable: Option<Threshold>,
+}
+
+impl ThresholdsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.ads_percentage.is_none() && self.load.is_none() && self.unreachable.is_none()
+    }
+}
+
+/// Colour of `value` by `threshold`, the default text colour without one
+pub fn colour(threshold: Option<&Threshold>, value: f64, theme: &Theme) -> Color {
+    match threshold {
+        Some(threshold) => threshold.colour(value, theme),
+        None => Color::Reset,
+    }
+}
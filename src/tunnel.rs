@@ -0,0 +1,99 @@
+use crate::util::SshTunnelConfig;
+use reqwest::Url;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::process::{Child, Command, Stdio};
+
+/// SSH local forward to a server, the `ssh` process ends when this is dropped
+pub struct SshTunnel {
+    ssh: Child,
+    /// URL of the server through the forward
+    pub url: Url,
+    /// Host name of the server and the local end of the forward its requests connect to.
+    /// The URL keeps the host name so https:// certificates are checked against it.
+    pub resolve: Option<(String, SocketAddr)>,
+}
+
+impl SshTunnel {
+    /// Forwards a local port to the host and port of `host` as seen from the SSH server.
+    /// The connection is made in the background, requests fail until it is up.
+    /// `insecure_tls` allows https:// hosts given by IP address, whose certificate cannot match
+    /// the local end of the forward.
+    pub fn open(config: &SshTunnelConfig, host: &str, insecure_tls: bool) -> Result<Self, String> {
+        let mut url =
+            Url::parse(host).map_err(|error| format!("Invalid host {}: {}", host, error))?;
+        let target_host = url
+            .host_str()
+            .ok_or_else(|| format!("Invalid host {}, it has no host name", host))?
+            .to_string();
+        let target_port = config
+            .remote_port
+            .or_else(|| url.port_or_known_default())
+            .unwrap_or(80);
+        let local_port = free_local_port()
+            .map_err(|error| format!("Could not find a free port for the SSH tunnel: {}", error))?;
+
+        let mut command = Command::new("ssh");
+        command
+            .arg("-N")
+            // Fail rather than prompt for a password over the interface
+            .args(["-o", "BatchMode=yes", "-o", "ExitOnForwardFailure=yes"])
+            .arg("-L")
+            .arg(format!(
+                "{}:{}:{}:{}",
+                Ipv4Addr::LOCALHOST,
+                local_port,
+                target_host,
+                target_port
+            ));
+        if let Some(user) = &config.user {
+            command.arg("-l").arg(user);
+        }
+        let ssh = command
+            .arg(&config.host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| format!("Could not run ssh: {}", error))?;
+        log::info!(
+            "Forwarding port {} to {}:{} through {}",
+            local_port,
+            target_host,
+            target_port,
+            config.host
+        );
+
+        let local = SocketAddr::from((Ipv4Addr::LOCALHOST, local_port));
+        let resolve = match url.domain() {
+            Some(domain) => Some((domain.to_string(), local)),
+            None if url.scheme() == "https" && !insecure_tls => {
+                return Err(format!(
+                    "The https:// host {} cannot be reached through an SSH tunnel by IP address, use its host name or set insecure_tls",
+                    host
+                ))
+            }
+            None => None,
+        };
+        // Both only fail for URLs without a host, which was checked above
+        if resolve.is_none() {
+            let _ = url.set_host(Some(&Ipv4Addr::LOCALHOST.to_string()));
+        }
+        let _ = url.set_port(Some(local_port));
+        Ok(SshTunnel { ssh, url, resolve })
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        let _ = self.ssh.kill();
+        let _ = self.ssh.wait();
+    }
+}
+
+/// A port nothing is listening on, there is a short window for something else to take it
+fn free_local_port() -> io::Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+        .local_addr()?
+        .port())
+}
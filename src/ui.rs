@@ -1,23 +1,116 @@
-use crate::util::{self, App};
-use chrono::{DateTime, NaiveDateTime, Utc};
+use crate::dns_probe::DnsHealth;
+use crate::layout::Section;
+use crate::theme::Theme;
+use crate::thresholds;
+use crate::util::{
+    self, App, BlockingChange, ChartMode, ClickAreas, Confirmation, DhcpLease, DomainList,
+    Freshness, InputPrompt, LocalRecordType, OverTimeChart, Page, Panel, PiHoleData, PrivacyLevel,
+    PromptKind, ReplyTimes, ServerAction, ServerEditor, StatisticsTable, View,
+};
+use chrono::Local;
+use pi_hole_api::api_types::Summary;
+use pi_hole_api::ftl_types::QueryStatus;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
-    text::{Span, Spans, Text},
-    widgets::{BarChart, Block, BorderType, Borders, Cell, Paragraph, Row, Table, Tabs},
+    symbols::{self, Marker},
+    text::{Span, Spans},
+    widgets::{
+        Axis, BarChart, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, GraphType,
+        Paragraph, Row, Sparkline, Table, TableState, Tabs, Widget, Wrap,
+    },
     Frame,
 };
 
-pub fn draw_help_bar<B>(f: &mut Frame<B>, area: Rect)
+/// How long a status message replaces the help bar
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+
+pub fn draw_help_bar<B>(f: &mut Frame<B>, app: &App, area: Rect)
 where
     B: Backend,
 {
-    let text = Text::raw(
-        "E: Enable  D: Disable  Z: Zoom+  X: Zoom-  Space: Update  LArrow: Prev  RArrow: Next",
-    );
-    let paragraph = Paragraph::new(text).style(Style::default().bg(Color::Cyan));
+    let theme = &app.theme;
+    let help_bar_style = Style::default().fg(theme.help_bar_text).bg(theme.help_bar);
+    if let Some(InputPrompt {
+        kind: PromptKind::Filter,
+        input,
+        ..
+    }) = &app.input_prompt
+    {
+        // The filter is typed here rather than in a popup so the table stays visible
+        let paragraph = Paragraph::new(Spans::from(vec![
+            Span::raw(format!("/{}_", input)),
+            Span::styled(
+                "  Enter: Keep filter  Esc: Clear filter",
+                Style::default().fg(theme.muted),
+            ),
+        ]))
+        .style(help_bar_style);
+        f.render_widget(paragraph, area);
+        return;
+    }
+    let paragraph = match &app.status_message {
+        Some((set_at, result)) if set_at.elapsed() < STATUS_MESSAGE_DURATION => match result {
+            Ok(message) => Paragraph::new(message.as_str())
+                .style(Style::default().fg(theme.help_bar_text).bg(theme.success)),
+            Err(message) => Paragraph::new(message.as_str())
+                .style(Style::default().fg(theme.error_text).bg(theme.error)),
+        },
+        _ if app.alerts.active().next().is_some() => {
+            let mut active = app.alerts.active();
+            let mut message = active
+                .next()
+                .map(|alert| format!("Alert: {}: {}", alert.server, alert.message))
+                .unwrap_or_default();
+            let more = active.count();
+            if more > 0 {
+                message = format!("{} (+{} more)", message, more);
+            }
+            // Flashes every second until the alerts clear
+            let style = if Local::now().timestamp() % 2 == 0 {
+                Style::default().fg(theme.error_text).bg(theme.error)
+            } else {
+                help_bar_style
+            };
+            Paragraph::new(message).style(style)
+        }
+        _ => {
+            let help = match app.view {
+            View::Lists => "Tab: Next list  Up/Down: Select  Y: Copy  N: Add domain  Del: Remove domain  Shift+B: Back  LArrow: Prev  RArrow: Next  Space: Update",
+            View::LongTerm => "[/]: 7, 30 or 90 days  Shift+H: Back  LArrow: Prev  RArrow: Next  Space: Update",
+            View::Diagnostics => "Shift+D: Back  Space: Update",
+            View::Alerts => "Shift+A: Back  Space: Update",
+            View::Split => "Shift+S: Back  Z: Zoom+  X: Zoom-  LArrow: Prev  RArrow: Next  Space: Update",
+            View::Client => "Esc: Back  LArrow: Prev  RArrow: Next  Space: Update",
+            View::QueryLog => "F: Follow  L: Back  E: Export  LArrow: Prev  RArrow: Next  Space: Update",
+            View::Network => "F1: Overview  LArrow: Prev  RArrow: Next  Space: Update",
+            View::LocalDns => "Tab: Next records  Up/Down: Select  Y: Copy  N: Add record  Del: Remove record  F1: Overview  LArrow: Prev  RArrow: Next  Space: Update",
+            View::Adlists => "Up/Down: Select  Y: Copy  N: Add adlist  E: Enable/disable  Shift+U: Update gravity  F1: Overview  LArrow: Prev  RArrow: Next  Space: Update",
+            _ => "E: Enable  D: Disable  Z: Zoom+  X: Zoom-  Space: Update  P: Pause  LArrow: Prev  RArrow: Next  Tab: Focus  Enter: Maximize  Up/Down: Select  Y: Copy  B: Block  W: Whitelist  R: Regex block  L: Query log  Shift+B: Lists  Shift+X: Actions  A: Add server  M: Edit server  ^S: Save config",
+            };
+            let mut spans = Vec::new();
+            if app.paused {
+                spans.push(Span::styled(
+                    " PAUSED ",
+                    Style::default().fg(theme.help_bar).bg(theme.warning),
+                ));
+                spans.push(Span::raw(" "));
+            }
+            if app.redact {
+                spans.push(Span::styled(
+                    " MASKED ",
+                    Style::default().fg(theme.help_bar).bg(theme.accent),
+                ));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::raw(help));
+            Paragraph::new(Spans::from(spans)).style(help_bar_style)
+        }
+    };
     f.render_widget(paragraph, area);
 }
 
@@ -25,23 +118,184 @@ pub fn draw_tabs<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let server_names = app
-        .servers
-        .iter()
-        .map(|server| &server.name)
-        .cloned()
-        .map(|server_name| {
-            Spans::from(vec![Span::styled(
-                server_name,
-                Style::default().fg(Color::LightYellow),
-            )])
+    let theme = &app.theme;
+    let mut server_names: Vec<Spans> = app
+        .tab_server_indices()
+        .into_iter()
+        .map(|index| {
+            let server = &app.servers[index];
+            let freshness = server.freshness();
+            let freshness_colour = match freshness {
+                Freshness::Pending => theme.muted,
+                Freshness::Fresh => theme.good,
+                Freshness::Partial | Freshness::Unreachable => theme.warning,
+                // An update which has not finished in time has most likely timed out
+                Freshness::Stale | Freshness::Failed => theme.error,
+            };
+            let name_colour = if freshness == Freshness::Pending {
+                theme.tab_name
+            } else {
+                freshness_colour
+            };
+            let mut spans = vec![
+                Span::styled("\u{25cf} ", Style::default().fg(freshness_colour)),
+                Span::styled(server.name.clone(), Style::default().fg(name_colour)),
+            ];
+            if let Some(latency) = server.latency {
+                spans.push(Span::styled(
+                    format!(" {}", format_latency(latency)),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+            // Marks servers with an update available so they stand out from any tab
+            if server
+                .last_data
+                .versions
+                .as_ref()
+                .is_some_and(util::update_available)
+            {
+                spans.push(Span::styled(
+                    " \u{2191}",
+                    Style::default().fg(theme.warning),
+                ));
+            }
+            Spans::from(spans)
         })
         .collect();
+    if app.has_all_tab() {
+        server_names.insert(0, Spans::from("All"));
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(tabs_title(app));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    draw_page_indicator(f, app, area);
+
+    // Groups take the first row when there are any, the servers of the selected group the next
+    let groups = app.groups();
+    let server_row = if groups.is_empty() {
+        app.click_areas.group_tabs.clear();
+        inner
+    } else {
+        let mut group_names = vec![Spans::from("All groups")];
+        group_names.extend(groups.iter().map(|group| Spans::from(group.to_string())));
+        let group_row = Rect::new(inner.x, inner.y, inner.width, 1).intersection(inner);
+        app.click_areas.group_tabs = tab_areas(&group_names, group_row);
+        let group_tabs = Tabs::new(group_names)
+            .highlight_style(Style::default().fg(app.theme.tab_highlight))
+            .select(app.selected_group.map_or(0, |index| index + 1));
+        f.render_widget(group_tabs, group_row);
+        Rect::new(
+            inner.x,
+            inner.y + 1,
+            inner.width,
+            inner.height.saturating_sub(1),
+        )
+    };
+    app.click_areas.tabs = tab_areas(&server_names, server_row);
     let tabs = Tabs::new(server_names)
-        .block(Block::default().borders(Borders::ALL).title("Pi Hole"))
-        .highlight_style(Style::default().fg(Color::LightGreen))
-        .select(app.selected_server_index);
-    f.render_widget(tabs, area);
+        .highlight_style(Style::default().fg(app.theme.tab_highlight))
+        .select(app.selected_tab_index());
+    f.render_widget(tabs, server_row);
+}
+
+/// Area of each of `names` in a row of tabs drawn at `row`
+fn tab_areas(names: &[Spans], row: Rect) -> Vec<Rect> {
+    // Tabs are drawn as " name " separated by a one cell divider. Those past the end of the
+    // row are not drawn and get an empty area, which no click is in.
+    let mut x = row.x;
+    names
+        .iter()
+        .map(|name| {
+            if x >= row.right() {
+                return Rect::default();
+            }
+            let width = (name.width() as u16).saturating_add(2).min(row.right() - x);
+            let tab_area = Rect::new(x, row.y, width, 1);
+            x = x.saturating_add(width).saturating_add(1);
+            tab_area
+        })
+        .collect()
+}
+
+/// How long ago the selected server was updated and when it is next, with the update delay
+/// Writes the pages with their F keys into the right of the bottom border of `area`,
+/// highlighting the page shown
+fn draw_page_indicator<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let current = Page::of(app.view);
+    let mut spans = Vec::new();
+    for (index, page) in Page::ALL.iter().enumerate() {
+        let style = if *page == current {
+            Style::default().fg(app.theme.tab_highlight)
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(
+            format!(" F{} {} ", index + 1, page.title()),
+            style,
+        ));
+    }
+    let spans = Spans::from(spans);
+    let width = (spans.width() as u16).min(area.width.saturating_sub(2));
+    let indicator_area = Rect::new(
+        area.right().saturating_sub(width + 1),
+        area.bottom().saturating_sub(1),
+        width,
+        1,
+    );
+    f.render_widget(Paragraph::new(spans), indicator_area);
+}
+
+fn tabs_title(app: &App) -> String {
+    let every = util::format_duration(Duration::from_millis(app.update_delay));
+    if app.all_tab_selected() {
+        return format!("Pi Hole, updating every {}", every);
+    }
+    let server = &app.servers[app.selected_server_index];
+    let last_update = match server.last_update {
+        Some(last_update) => format!(
+            "updated {} ({} ago)",
+            format_update_time(app, last_update),
+            util::format_duration(last_update.elapsed())
+        ),
+        None => "not updated yet".to_string(),
+    };
+    let next_update = match server.next_update_in() {
+        _ if app.paused => "paused".to_string(),
+        Some(next_update_in) if server.failed_updates > 0 => {
+            format!("retrying in {}", format_retry_delay(next_update_in))
+        }
+        Some(next_update_in) => format!("next in {}", format_retry_delay(next_update_in)),
+        None => "updating\u{2026}".to_string(),
+    };
+    format!(
+        "Pi Hole, {} / {} (every {})",
+        last_update, next_update, every
+    )
+}
+
+/// Time of day an update finished at
+fn format_update_time(app: &App, finished: Instant) -> String {
+    util::format_timestamp(
+        util::timestamp_of(finished),
+        &app.time_format.seconds,
+        app.timezone.as_ref(),
+    )
+}
+
+/// Milliseconds below a second, otherwise seconds with one decimal
+fn format_latency(latency: Duration) -> String {
+    if latency < Duration::from_millis(1) {
+        "<1ms".to_string()
+    } else if latency < Duration::from_secs(1) {
+        format!("{}ms", latency.as_millis())
+    } else {
+        format!("{:.1}s", latency.as_secs_f64())
+    }
 }
 
 pub fn draw_overview<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
@@ -52,10 +306,12 @@ where
         .direction(Direction::Horizontal)
         .constraints(
             [
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
+                Constraint::Percentage(16),
+                Constraint::Percentage(16),
+                Constraint::Percentage(16),
+                Constraint::Percentage(16),
+                Constraint::Percentage(16),
+                Constraint::Percentage(20),
             ]
             .as_ref(),
         )
@@ -73,42 +329,82 @@ where
     let other_stats_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .title("Other stats");
+        .title("Query status");
 
     let responses_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .title("Responses");
 
-    match &app.servers[app.selected_server_index].last_data.summary {
+    draw_cache_info(f, app, chunks[4]);
+    draw_query_types(f, app, chunks[5]);
+
+    let theme = &app.theme;
+    let server = (!app.all_tab_selected()).then(|| &app.servers[app.selected_server_index]);
+    match &app.displayed_data().summary {
         Some(summary) => {
             {
                 let styled_status_colour = match summary.status.as_str() {
-                    "enabled" => Color::LightGreen,
-                    _ => Color::Red,
+                    "enabled" => theme.good,
+                    _ => theme.error,
                 };
-                let styled_api_key_colour = match &app.servers[app.selected_server_index].api_key {
-                    Some(_) => Color::LightGreen,
-                    None => Color::Red,
+                let status = match server.and_then(|server| server.blocking_resumes_at) {
+                    Some(resumes_at) if summary.status != "enabled" => format!(
+                        "{} ({})",
+                        summary.status,
+                        util::format_duration(resumes_at.saturating_duration_since(Instant::now()))
+                    ),
+                    _ => summary.status.to_string(),
+                };
+                // The summary still has the status from before the change
+                let (status, styled_status_colour) = match server
+                    .and_then(|server| server.pending_blocking_change())
+                {
+                    Some(BlockingChange::Enable) => ("enabling\u{2026}".to_string(), theme.warning),
+                    Some(BlockingChange::Disable(_)) => {
+                        ("disabling\u{2026}".to_string(), theme.warning)
+                    }
+                    None => (status, styled_status_colour),
+                };
+                // The All tab counts the servers with a key instead
+                let (api_key_label, api_key, styled_api_key_colour) = match server {
+                    Some(server) => (
+                        "API key: ",
+                        server.api_key.is_some().to_string(),
+                        match &server.api_key {
+                            Some(_) => theme.good,
+                            None => theme.error,
+                        },
+                    ),
+                    None => {
+                        let servers = app.displayed_servers();
+                        let with_key = servers
+                            .iter()
+                            .filter(|server| server.api_key.is_some())
+                            .count();
+                        let colour = if with_key == servers.len() {
+                            theme.good
+                        } else if with_key == 0 {
+                            theme.error
+                        } else {
+                            theme.warning
+                        };
+                        (
+                            "API keys: ",
+                            format!("{}/{}", with_key, servers.len()),
+                            colour,
+                        )
+                    }
                 };
 
-                let text = vec![
+                let mut text = vec![
                     Spans::from(vec![
                         Span::raw("Status: "),
-                        Span::styled(
-                            format!("{}", summary.status),
-                            Style::default().fg(styled_status_colour),
-                        ),
+                        Span::styled(status, Style::default().fg(styled_status_colour)),
                     ]),
                     Spans::from(vec![
-                        Span::raw("API key: "),
-                        Span::styled(
-                            format!(
-                                "{}",
-                                !&app.servers[app.selected_server_index].api_key.is_none()
-                            ),
-                            Style::default().fg(styled_api_key_colour),
-                        ),
+                        Span::raw(api_key_label),
+                        Span::styled(api_key, Style::default().fg(styled_api_key_colour)),
                     ]),
                     Spans::from(vec![Span::raw(format!(
                         "Privacy level: {}",
@@ -116,73 +412,209 @@ where
                     ))]),
                     Spans::from(vec![Span::raw(format!(
                         "Blocklist size: {}",
-                        &summary.domains_being_blocked
+                        app.summary_count(&summary.domains_being_blocked)
                     ))]),
                 ];
+                if let Some(load) = app.displayed_data().load {
+                    text.push(Spans::from(vec![
+                        Span::raw("Load: "),
+                        Span::styled(
+                            format!("{:.2}, {:.2}, {:.2}", load[0], load[1], load[2]),
+                            Style::default().fg(thresholds::colour(
+                                app.thresholds.load.as_ref(),
+                                load[0],
+                                theme,
+                            )),
+                        ),
+                    ]));
+                }
+                // Servers that could not be reached, of those shown on the All tab
+                if server.is_none() {
+                    let servers = app.displayed_servers();
+                    let unreachable = servers
+                        .iter()
+                        .filter(|server| {
+                            matches!(
+                                server.freshness(),
+                                Freshness::Unreachable | Freshness::Failed
+                            )
+                        })
+                        .count();
+                    text.push(Spans::from(vec![
+                        Span::raw("Unreachable: "),
+                        Span::styled(
+                            format!("{}/{}", unreachable, servers.len()),
+                            Style::default().fg(thresholds::colour(
+                                app.thresholds.unreachable.as_ref(),
+                                unreachable as f64,
+                                theme,
+                            )),
+                        ),
+                    ]));
+                }
+                if let Some(dns_health) = &app.displayed_data().dns_health {
+                    text.push(dns_health_spans(theme, dns_health));
+                }
+                if let Some(server) = server.filter(|server| !server.past_downtime.is_zero()) {
+                    text.push(Spans::from(Span::raw(format!(
+                        "Downtime: {}",
+                        util::format_duration(server.past_downtime)
+                    ))));
+                }
+                if let Some(versions) = &app.displayed_data().versions {
+                    text.push(version_spans(
+                        theme,
+                        "Core",
+                        &versions.core_current,
+                        &versions.core_latest,
+                        versions.core_update,
+                    ));
+                    text.push(version_spans(
+                        theme,
+                        "Web",
+                        &versions.web_current,
+                        &versions.web_latest,
+                        versions.web_update,
+                    ));
+                    text.push(version_spans(
+                        theme,
+                        "FTL",
+                        &versions.ftl_current,
+                        &versions.ftl_latest,
+                        versions.ftl_update,
+                    ));
+                }
                 let paragraph = Paragraph::new(text).block(summary_block);
                 f.render_widget(paragraph, chunks[0]);
             }
             {
-                let text = vec![
-                    Spans::from(vec![Span::raw(format!(
-                        "Queries: {}",
-                        &summary.dns_queries_today
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "Ads blocked: {}",
-                        &summary.ads_blocked_today
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "Ads percent: {}",
-                        &summary.ads_percentage_today
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "Unique domains: {}",
-                        &summary.unique_domains
-                    ))]),
+                let query_rates = app.query_rates();
+                let mut text = vec![
+                    count_spans(app, "Queries", |summary| &summary.dns_queries_today),
+                    count_spans(app, "Ads blocked", |summary| &summary.ads_blocked_today),
+                    Spans::from(vec![
+                        Span::raw("Ads percent: "),
+                        Span::styled(
+                            summary.ads_percentage_today.to_string(),
+                            Style::default().fg(thresholds::colour(
+                                app.thresholds.ads_percentage.as_ref(),
+                                summary
+                                    .ads_percentage_today
+                                    .trim()
+                                    .parse()
+                                    .unwrap_or_default(),
+                                theme,
+                            )),
+                        ),
+                    ]),
+                    count_spans(app, "Unique domains", |summary| &summary.unique_domains),
                 ];
+                // Known from the second summary on
+                if let Some(rate) = query_rates.last() {
+                    text.push(Spans::from(Span::raw(format!(
+                        "Rate: {}/min ({}/s)",
+                        format_rate(*rate),
+                        format_rate(rate / 60.0)
+                    ))));
+                }
+                let inner = query_stats_block.inner(chunks[1]);
+                let text_height = text.len() as u16;
                 let paragraph = Paragraph::new(text).block(query_stats_block);
                 f.render_widget(paragraph, chunks[1]);
+                // The recent rates below the text
+                draw_sparkline_below(f, app, &query_rates, None, inner, text_height);
             }
 
             {
-                let text = vec![
-                    Spans::from(vec![Span::raw(format!(
-                        "Forwarded: {}",
-                        &summary.queries_forwarded
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "Cached: {}",
-                        &summary.queries_cached
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "Unique clients: {}",
-                        &summary.unique_clients
-                    ))]),
-                ];
-                let paragraph = Paragraph::new(text).block(other_stats_block);
+                let mut text = Vec::new();
+                let mut block = other_stats_block;
+                match app.status_counts() {
+                    Some((counts, from_query_log)) => {
+                        if from_query_log {
+                            block = block.title("Query status (query log)");
+                        }
+                        // Below them the unique clients
+                        let shown = usize::from(chunks[2].height.saturating_sub(3));
+                        for (group, count) in counts.into_iter().take(shown) {
+                            let colour = if group.is_blocked() {
+                                theme.error
+                            } else {
+                                theme.good
+                            };
+                            text.push(Spans::from(vec![
+                                Span::styled(
+                                    format!("{}: ", group.title()),
+                                    Style::default().fg(colour),
+                                ),
+                                Span::raw(app.count(count)),
+                            ]));
+                        }
+                    }
+                    None => {
+                        text.push(count_spans(app, "Forwarded", |summary| {
+                            &summary.queries_forwarded
+                        }));
+                        text.push(count_spans(app, "Cached", |summary| {
+                            &summary.queries_cached
+                        }));
+                    }
+                }
+                text.push(count_spans(app, "Unique clients", |summary| {
+                    &summary.unique_clients
+                }));
+                let paragraph = Paragraph::new(text).block(block);
                 f.render_widget(paragraph, chunks[2]);
             }
 
             {
                 let text = vec![
-                    Spans::from(vec![Span::raw(format!(
-                        "NODATA: {}",
-                        &summary.reply_nodata
-                    ))]),
-                    Spans::from(vec![Span::raw(format!(
-                        "NXDOMAIN: {}",
-                        &summary.reply_nxdomain
-                    ))]),
-                    Spans::from(vec![Span::raw(format!("CNAME: {}", &summary.reply_cname))]),
-                    Spans::from(vec![Span::raw(format!("IP: {}", &summary.reply_ip))]),
+                    count_spans(app, "NODATA", |summary| &summary.reply_nodata),
+                    count_spans(app, "NXDOMAIN", |summary| &summary.reply_nxdomain),
+                    count_spans(app, "CNAME", |summary| &summary.reply_cname),
+                    count_spans(app, "IP", |summary| &summary.reply_ip),
                 ];
                 let paragraph = Paragraph::new(text).block(responses_block);
                 f.render_widget(paragraph, chunks[3]);
             }
         }
         None => {
-            f.render_widget(summary_block, chunks[0]);
+            let mut text = Vec::new();
+            if let Some(server) = server {
+                if let Some(outage_since) = server.outage_since {
+                    text.push(Spans::from(Span::styled(
+                        format!(
+                            "Down since {}",
+                            util::format_timestamp(
+                                outage_since.timestamp(),
+                                &app.time_format.date_seconds(),
+                                app.timezone.as_ref()
+                            )
+                        ),
+                        Style::default().fg(theme.error),
+                    )));
+                    text.push(Spans::from(Span::raw(format!(
+                        "Downtime: {}",
+                        util::format_duration(server.total_downtime())
+                    ))));
+                }
+            }
+            if let Some(retrying_in) = server.and_then(|server| server.retrying_in()) {
+                text.push(Spans::from(Span::styled(
+                    format!(
+                        "Update failed, retrying in {}\u{2026}",
+                        format_retry_delay(retrying_in)
+                    ),
+                    Style::default().fg(theme.error),
+                )));
+            }
+            // The resolver may be answering while the API is down
+            if let Some(dns_health) = &app.displayed_data().dns_health {
+                text.push(dns_health_spans(theme, dns_health));
+            }
+            let paragraph = Paragraph::new(text)
+                .block(summary_block)
+                .wrap(Wrap { trim: true });
+            f.render_widget(paragraph, chunks[0]);
             f.render_widget(query_stats_block, chunks[1]);
             f.render_widget(other_stats_block, chunks[2]);
             f.render_widget(responses_block, chunks[3]);
@@ -190,153 +622,2522 @@ where
     };
 }
 
-pub fn draw_queries_chart<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+/// Draws the newest of `values` that fit as a sparkline in the rows of `inner` below the first
+/// `text_height`, the newest on the right. Without a `max` the largest value is the top.
+fn draw_sparkline_below<B>(
+    f: &mut Frame<B>,
+    app: &App,
+    values: &[f64],
+    max: Option<u64>,
+    inner: Rect,
+    text_height: u16,
+) where
+    B: Backend,
+{
+    if inner.height <= text_height {
+        return;
+    }
+    let area = Rect::new(
+        inner.x,
+        inner.y + text_height,
+        inner.width,
+        inner.height - text_height,
+    );
+    let shown = values.len().min(usize::from(inner.width));
+    let data: Vec<u64> = values[values.len() - shown..]
+        .iter()
+        .map(|value| value.round() as u64)
+        .collect();
+    let mut sparkline = Sparkline::default()
+        .data(&data)
+        .style(Style::default().fg(app.theme.accent));
+    if let Some(max) = max {
+        sparkline = sparkline.max(max);
+    }
+    f.render_widget(sparkline, area);
+}
+
+/// Count of the summary followed by its change since the previous update, e.g.
+/// `Queries: 45,120 ▲312`
+fn count_spans<'a>(app: &App, label: &str, count: fn(&Summary) -> &str) -> Spans<'a> {
+    let value = app
+        .displayed_data()
+        .summary
+        .as_ref()
+        .map(|summary| app.summary_count(count(summary)))
+        .unwrap_or_default();
+    let mut spans = vec![Span::raw(format!("{}: {}", label, value))];
+    match app.summary_change(count) {
+        Some(change) if change > 0 => spans.push(Span::styled(
+            format!(" \u{25b2}{}", app.count(change.unsigned_abs())),
+            Style::default().fg(app.theme.good),
+        )),
+        Some(change) if change < 0 => spans.push(Span::styled(
+            format!(" \u{25bc}{}", app.count(change.unsigned_abs())),
+            Style::default().fg(app.theme.error),
+        )),
+        _ => {}
+    }
+    Spans::from(spans)
+}
+
+/// Queries per minute or second, with a decimal while small
+fn format_rate(rate: f64) -> String {
+    if rate < 10.0 {
+        format!("{:.1}", rate)
+    } else {
+        format!("{:.0}", rate)
+    }
+}
+
+/// Outcome of the DNS probe of a server
+fn dns_health_spans<'a>(theme: &Theme, dns_health: &DnsHealth) -> Spans<'a> {
+    let (text, colour) = match dns_health {
+        DnsHealth::Ok => ("OK".to_string(), theme.good),
+        DnsHealth::NotBlocking => ("blocking not working".to_string(), theme.warning),
+        DnsHealth::Failed(error) => (format!("FAIL ({})", error), theme.error),
+    };
+    Spans::from(vec![
+        Span::raw("DNS: "),
+        Span::styled(text, Style::default().fg(colour)),
+    ])
+}
+
+/// Version of a Pi-Hole component, highlighted and followed by the latest version if it is newer
+fn version_spans<'a>(
+    theme: &Theme,
+    component: &'a str,
+    current: &str,
+    latest: &str,
+    update: bool,
+) -> Spans<'a> {
+    if update {
+        Spans::from(Span::styled(
+            format!("{}: {} -> {}", component, current, latest),
+            Style::default().fg(theme.warning),
+        ))
+    } else {
+        Spans::from(Span::raw(format!("{}: {}", component, current)))
+    }
+}
+
+pub fn draw_cache_info<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
     let block = Block::default()
-        .title("Total queries")
-        .borders(Borders::ALL);
-    match &app.servers[app.selected_server_index]
-        .last_data
-        .over_time_data
-    {
-        Some(over_time_data) => {
-            let mut queries_over_time_rows: Vec<(i64, u64)> = over_time_data
-                .domains_over_time
-                .iter()
-                .map(|(time, count)| (i64::from_str(time).unwrap(), *count))
-                .collect();
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Cache");
 
-            // Display with left as the latest entry.
-            // Otherwise the data is cut off on the right side.
-            queries_over_time_rows.sort_by(|a, b| b.0.cmp(&a.0));
-            let squashed_queries_over_time =
-                util::squash_queries_over_time(&queries_over_time_rows, app.graph_squash_factor);
-            let queries_over_time_rows: Vec<(String, u64)> = squashed_queries_over_time
-                .iter()
-                .map(|(timestamp, count)| {
-                    let naive = NaiveDateTime::from_timestamp(*timestamp, 0);
-                    let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
-                    (datetime.format("%H:%M").to_string(), *count)
-                })
-                .collect();
+    match &app.displayed_data().cache_info {
+        Some(cache_info) => {
+            // Live entries are only evicted when the cache is too small
+            let evictions_colour = match cache_info.cache_live_freed {
+                0 => Color::Reset,
+                _ => app.theme.warning,
+            };
+            let mut text = vec![
+                Spans::from(vec![Span::raw(format!(
+                    "Size: {}",
+                    app.count(cache_info.cache_size)
+                ))]),
+                Spans::from(vec![Span::raw(format!(
+                    "Insertions: {}",
+                    app.count(cache_info.cache_inserted)
+                ))]),
+                Spans::from(vec![
+                    Span::raw("Evictions: "),
+                    Span::styled(
+                        app.count(cache_info.cache_live_freed),
+                        Style::default().fg(evictions_colour),
+                    ),
+                ]),
+            ];
+            let hit_ratios = app.cache_hit_ratios();
+            if let Some(hit_ratio) = hit_ratios.last() {
+                text.push(Spans::from(Span::raw(format!(
+                    "Hit ratio: {:.1}%",
+                    hit_ratio
+                ))));
+            }
+            let inner = block.inner(area);
+            let text_height = text.len() as u16;
+            let paragraph = Paragraph::new(text).block(block);
+            f.render_widget(paragraph, area);
+            // The recent hit ratios below the text on a scale up to 100%
+            draw_sparkline_below(f, app, &hit_ratios, Some(100), inner, text_height);
+        }
+        None => f.render_widget(block, area),
+    };
+}
+
+pub fn draw_query_types<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Query types");
 
-            let queries_over_time_str_rows: Vec<(&str, u64)> = queries_over_time_rows
+    match &app.displayed_data().query_types {
+        Some(query_types) => {
+            let query_types = util::order_string_percentage_map(&query_types.querytypes);
+            let name_width = 6;
+            // Leave room for the name, the percentage and the borders
+            let bar_width = area.width.saturating_sub(name_width + 10) as f64;
+            let text: Vec<Spans> = query_types
                 .iter()
-                .map(|(timestamp, count)| (timestamp.as_str(), *count))
+                .map(|(name, percentage)| {
+                    // Names are of the form "A (IPv4)", only the record type is shown
+                    let name = name.split_whitespace().next().unwrap_or_default();
+                    let bar_length = (bar_width * percentage / 100.0).round() as usize;
+                    Spans::from(vec![
+                        Span::raw(format!(
+                            "{:<width$}{:>6.2}% ",
+                            name,
+                            percentage,
+                            width = name_width as usize
+                        )),
+                        Span::styled(
+                            "\u{2588}".repeat(bar_length),
+                            Style::default().fg(app.theme.chart),
+                        ),
+                    ])
+                })
                 .collect();
-            let bar_chart = BarChart::default()
-                .block(block)
-                .data(&queries_over_time_str_rows)
-                .bar_width(5)
-                .bar_style(Style::default().fg(Color::Green))
-                .value_style(Style::default().fg(Color::Black).bg(Color::Green));
-            f.render_widget(bar_chart, area);
+            let paragraph = Paragraph::new(text).block(block);
+            f.render_widget(paragraph, area);
         }
         None => f.render_widget(block, area),
     };
 }
 
-pub fn draw_statistics<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+pub fn draw_queries_chart<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-            ]
-            .as_ref(),
-        )
-        .split(area);
-
-    let top_queries_rows = match &app.servers[app.selected_server_index].last_data.top_items {
-        Some(top_items) => util::order_convert_string_num_map(&top_items.top_queries),
-        None => Vec::new(),
-    };
+    let over_time_rows = queries_over_time_rows(app.displayed_data());
+    draw_queries_over_time(f, app, over_time_rows, area);
+}
 
-    let top_ads_rows = match &app.servers[app.selected_server_index].last_data.top_items {
-        Some(top_items) => util::order_convert_string_num_map(&top_items.top_ads),
-        None => Vec::new(),
-    };
+/// Timestamp, queries and blocked queries of each period of `data`, `None` before they are
+/// fetched
+fn queries_over_time_rows(data: &PiHoleData) -> Option<Vec<(i64, u64, u64)>> {
+    data.over_time_data.as_ref().map(|over_time_data| {
+        over_time_data
+            .domains_over_time
+            .iter()
+            // Skip any entry whose key is not a timestamp rather than failing to draw
+            .filter_map(|(time, count)| {
+                let blocked = over_time_data.ads_over_time.get(time).copied();
+                Some((
+                    i64::from_str(time).ok()?,
+                    *count,
+                    blocked.unwrap_or_default(),
+                ))
+            })
+            .collect::<Vec<(i64, u64, u64)>>()
+    })
+}
 
-    let top_clients_rows = match &app.servers[app.selected_server_index].last_data.top_sources {
-        Some(top_sources) => util::order_convert_string_num_map(&top_sources.top_sources),
-        None => Vec::new(),
+fn draw_queries_over_time<B>(
+    f: &mut Frame<B>,
+    app: &mut App,
+    over_time_rows: Option<Vec<(i64, u64, u64)>>,
+    area: Rect,
+) where
+    B: Backend,
+{
+    let block = Block::default().borders(Borders::ALL);
+    match over_time_rows {
+        Some(mut queries_over_time_rows) => {
+            // Display with left as the latest entry.
+            // Otherwise the data is cut off on the right side.
+            queries_over_time_rows.sort_by_key(|row| std::cmp::Reverse(row.0));
+            let squash_factor = app.chart_squash_factor();
+            pan_and_window_chart(app, &mut queries_over_time_rows, squash_factor, |row| row.0);
+            let block = block.title(queries_chart_title(
+                app,
+                queries_over_time_rows.first().map(|row| row.0),
+            ));
+            // Both series have the same timestamps so they are squashed into the same periods
+            let (queries, blocked): (Vec<_>, Vec<_>) = queries_over_time_rows
+                .iter()
+                .map(|(timestamp, count, blocked)| ((*timestamp, *count), (*timestamp, *blocked)))
+                .unzip();
+            let queries = util::squash_queries_over_time(&queries, squash_factor);
+            let blocked = util::squash_queries_over_time(&blocked, squash_factor);
+            match app.chart_mode {
+                ChartMode::Line => draw_queries_line_chart(f, app, block, &queries, &blocked, area),
+                ChartMode::Bar => draw_queries_bar_chart(f, app, block, &queries, &blocked, area),
+            }
+        }
+        None => f.render_widget(block.title(queries_chart_title(app, None)), area),
     };
+}
 
-    let header = vec!["Domain".to_string(), "Count".to_string()];
-    draw_list(f, chunks[0], "Top Queries", &header, &top_queries_rows);
+fn queries_chart_title(app: &App, newest_shown: Option<i64>) -> Spans<'static> {
+    Spans::from(vec![
+        Span::styled("Total queries", Style::default().fg(app.theme.chart)),
+        Span::raw(" / "),
+        Span::styled("Blocked", Style::default().fg(app.theme.error)),
+        Span::raw(chart_window_title(app, newest_shown)),
+    ])
+}
 
-    let header = vec!["Domain".to_string(), "Count".to_string()];
-    draw_list(f, chunks[1], "Top Ads", &header, &top_ads_rows);
+/// Removes the periods of `newest_first` that the chart is panned past or that are outside
+/// the chart window, panning by `squash_factor` periods at a time. The pan is limited so the
+/// oldest period remains.
+fn pan_and_window_chart<T>(
+    app: &mut App,
+    newest_first: &mut Vec<T>,
+    squash_factor: usize,
+    timestamp: fn(&T) -> i64,
+) {
+    let max_pan = newest_first.len().saturating_sub(1) / squash_factor;
+    app.chart_pan = app.chart_pan.min(max_pan);
+    newest_first.drain(..app.chart_pan * squash_factor);
+    if let Some(newest) = newest_first.first().map(timestamp) {
+        newest_first.retain(|period| app.chart_window.contains(newest, timestamp(period)));
+    }
+}
 
-    let header = vec!["Client".to_string(), "Count".to_string()];
-    draw_list(f, chunks[2], "Top Clients", &header, &top_clients_rows);
+/// Window of the chart, and the newest period shown while panned back from the latest
+fn chart_window_title(app: &App, newest_shown: Option<i64>) -> String {
+    match newest_shown {
+        Some(newest) if app.chart_pan > 0 => format!(
+            " ({} up to {})",
+            app.chart_window.label(),
+            format_chart_time(app, newest)
+        ),
+        _ => format!(" (last {})", app.chart_window.label()),
+    }
 }
 
-pub fn draw_list<B>(
+/// Draws `queries_over_time`, newest first, as bars labelled with their count.
+/// The part of each bar that was blocked is coloured differently.
+fn draw_queries_bar_chart<B>(
     f: &mut Frame<B>,
+    app: &App,
+    block: Block,
+    queries_over_time: &[(i64, u64)],
+    blocked_over_time: &[(i64, u64)],
     area: Rect,
-    title: &str,
-    header: &Vec<String>,
-    rows: &Vec<Vec<String>>,
 ) where
     B: Backend,
 {
-    let up_style = Style::default().fg(Color::LightGreen);
-    let rows = rows.iter().map(|row| {
-        let style = up_style;
-        Row::new(row.iter().map(|text| Cell::from(text.clone()).style(style)))
-    });
-    let table = Table::new(rows)
-        .block(
-            Block::default()
-                .title(vec![Span::from(title)])
-                .borders(Borders::ALL),
-        )
-        .header(
-            Row::new(header.iter().map(|text| Cell::from(text.clone())))
-                .style(Style::default().fg(Color::LightCyan)),
-        )
-        .widths(&[Constraint::Percentage(70), Constraint::Percentage(30)]);
-    f.render_widget(table, area);
+    let queries_over_time_rows: Vec<(String, u64)> = queries_over_time
+        .iter()
+        .map(|(timestamp, count)| {
+            (
+                util::format_timestamp(*timestamp, &app.time_format.bar, app.timezone.as_ref()),
+                *count,
+            )
+        })
+        .collect();
+
+    let queries_over_time_str_rows: Vec<(&str, u64)> = queries_over_time_rows
+        .iter()
+        .map(|(timestamp, count)| (timestamp.as_str(), *count))
+        .collect();
+    let max = queries_over_time
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+    let chart_area = block.inner(area);
+    let bar_style = Style::default().fg(app.theme.chart);
+    let value_style = Style::default()
+        .fg(app.theme.chart_value)
+        .bg(app.theme.chart);
+    let bar_chart = BarChart::default()
+        .block(block)
+        .data(&queries_over_time_str_rows)
+        .bar_width(BAR_WIDTH)
+        .bar_gap(BAR_GAP)
+        .max(max)
+        .bar_style(bar_style)
+        .value_style(value_style);
+    let counts: Vec<u64> = queries_over_time.iter().map(|(_, count)| *count).collect();
+    f.render_widget(
+        BlockedBars {
+            bar_chart,
+            chart_area,
+            bar_width: BAR_WIDTH,
+            bar_gap: BAR_GAP,
+            blocked: blocked_over_time.iter().map(|(_, count)| *count).collect(),
+            max,
+            style: Style::default().fg(app.theme.error),
+            values: bar_values(app, &counts, BAR_WIDTH),
+            counts,
+            bar_style,
+            value_style,
+        },
+        area,
+    );
 }
 
-pub fn draw_ui<B>(f: &mut Frame<B>, app: &mut App)
+pub const BAR_WIDTH: u16 = 5;
+pub const BAR_GAP: u16 = 1;
+
+/// Bar chart with the bottom of each bar, in proportion to `blocked`, drawn in `style`.
+/// tui cannot stack bars so the blocked rows are recoloured after the chart is drawn.
+struct BlockedBars<'a> {
+    bar_chart: BarChart<'a>,
+    /// Area inside the block of the chart
+    chart_area: Rect,
+    bar_width: u16,
+    bar_gap: u16,
+    blocked: Vec<u64>,
+    /// Count of the tallest bar
+    max: u64,
+    style: Style,
+    /// Count of each bar, newest first as `blocked`
+    counts: Vec<u64>,
+    /// Count of each bar as drawn, `None` if it does not fit
+    values: Vec<Option<String>>,
+    bar_style: Style,
+    value_style: Style,
+}
+
+impl<'a> Widget for BlockedBars<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.bar_chart.render(area, buf);
+        // The bottom row holds the labels
+        let bars_height = self.chart_area.height.saturating_sub(1);
+        if bars_height == 0 {
+            return;
+        }
+        let bar_count = (self.chart_area.width / (self.bar_width + self.bar_gap)) as usize;
+        let values_y = self.chart_area.top() + bars_height - 1;
+        // tui writes the counts as plain numbers over the bottom row of the bars, which is
+        // drawn again to write them as configured
+        for (index, count) in self.counts.iter().take(bar_count).enumerate() {
+            let eighths = *count * u64::from(bars_height) * 8 / self.max.max(1);
+            let symbol = bar_symbol(eighths);
+            let left = self.chart_area.left() + index as u16 * (self.bar_width + self.bar_gap);
+            for x in left..left + self.bar_width {
+                let cell = buf.get_mut(x, values_y);
+                cell.reset();
+                cell.set_symbol(symbol).set_style(self.bar_style);
+            }
+        }
+        for (index, blocked) in self.blocked.iter().take(bar_count).enumerate() {
+            // Rounded to whole rows, a cell can only have one colour
+            let rows = (*blocked * u64::from(bars_height) + self.max / 2) / self.max.max(1);
+            let left = self.chart_area.left() + index as u16 * (self.bar_width + self.bar_gap);
+            for row in 0..rows.min(u64::from(bars_height)) as u16 {
+                let y = self.chart_area.top() + bars_height - 1 - row;
+                for x in left..left + self.bar_width {
+                    let cell = buf.get_mut(x, y);
+                    if cell.symbol == symbols::bar::FULL {
+                        cell.set_style(self.style);
+                    }
+                }
+            }
+        }
+        for (index, (count, value)) in self.counts.iter().zip(&self.values).enumerate() {
+            let value = match value {
+                Some(value) if *count > 0 && index < bar_count => value,
+                _ => continue,
+            };
+            let width = value.chars().count() as u16;
+            let left = self.chart_area.left() + index as u16 * (self.bar_width + self.bar_gap);
+            buf.set_string(
+                left + (self.bar_width - width) / 2,
+                values_y,
+                value,
+                self.value_style,
+            );
+        }
+    }
+}
+
+/// Symbol of the bottom row of a bar `eighths` of a row tall, as drawn by tui
+fn bar_symbol(eighths: u64) -> &'static str {
+    let set = symbols::bar::NINE_LEVELS;
+    match eighths {
+        0 => set.empty,
+        1 => set.one_eighth,
+        2 => set.one_quarter,
+        3 => set.three_eighths,
+        4 => set.half,
+        5 => set.five_eighths,
+        6 => set.three_quarters,
+        7 => set.seven_eighths,
+        _ => set.full,
+    }
+}
+
+/// Recolours the outermost cells of what was drawn in `area`, keeping the borders and titles
+struct FocusFrame {
+    style: Style,
+}
+
+impl Widget for FocusFrame {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.area() == 0 {
+            return;
+        }
+        for x in area.left()..area.right() {
+            buf.get_mut(x, area.top()).set_style(self.style);
+            buf.get_mut(x, area.bottom() - 1).set_style(self.style);
+        }
+        for y in area.top()..area.bottom() {
+            buf.get_mut(area.left(), y).set_style(self.style);
+            buf.get_mut(area.right() - 1, y).set_style(self.style);
+        }
+    }
+}
+
+/// Greys out what was drawn in `area` and writes `label` across its middle row
+struct StaleOverlay {
+    label: String,
+    style: Style,
+    label_style: Style,
+}
+
+impl Widget for StaleOverlay {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_style(self.style);
+            }
+        }
+        let width = (self.label.chars().count() as u16).min(area.width);
+        let x = area.left() + (area.width - width) / 2;
+        let y = area.top() + area.height / 2;
+        buf.set_stringn(x, y, &self.label, width as usize, self.label_style);
+    }
+}
+
+/// Marks the data of `area` as stale when a displayed server has not been updated for twice
+/// its update delay, so old numbers are not taken for current ones
+fn draw_stale_overlay<B>(f: &mut Frame<B>, app: &App, area: Rect)
 where
     B: Backend,
 {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(1),
-                Constraint::Length(3),
-                Constraint::Length(6),
-                Constraint::Percentage(40),
-                Constraint::Percentage(40),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
+    let stale: Vec<_> = app
+        .displayed_servers()
+        .into_iter()
+        .filter(|server| server.freshness() == Freshness::Stale)
+        .collect();
+    let label = match stale.as_slice() {
+        [] => return,
+        [server] if !app.all_tab_selected() => format!(
+            " STALE, updated {} ago ",
+            server
+                .last_update
+                .map(|last_update| util::format_duration(last_update.elapsed()))
+                .unwrap_or_default()
+        ),
+        servers => format!(
+            " STALE: {} ",
+            servers
+                .iter()
+                .map(|server| server.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    f.render_widget(
+        StaleOverlay {
+            label,
+            style: Style::default().fg(app.theme.muted),
+            label_style: Style::default()
+                .fg(app.theme.error_text)
+                .bg(app.theme.error),
+        },
+        area,
+    );
+}
 
-    // Help bar
-    draw_help_bar(f, chunks[0]);
+/// Time of a chart entry as shown on the axis
+fn format_chart_time(app: &App, timestamp: i64) -> String {
+    util::format_timestamp(timestamp, &app.time_format.minutes, app.timezone.as_ref())
+}
 
-    // Pi Hole tabs
-    draw_tabs(f, app, chunks[1]);
+/// Draws `queries_over_time` and `blocked_over_time`, newest first, as lines with the time
+/// on the X axis and the count on the Y axis
+fn draw_queries_line_chart<B>(
+    f: &mut Frame<B>,
+    app: &App,
+    block: Block,
+    queries_over_time: &[(i64, u64)],
+    blocked_over_time: &[(i64, u64)],
+    area: Rect,
+) where
+    B: Backend,
+{
+    let to_points = |rows: &[(i64, u64)]| -> Vec<(f64, f64)> {
+        rows.iter()
+            .rev()
+            .map(|(timestamp, count)| (*timestamp as f64, *count as f64))
+            .collect()
+    };
+    let points = to_points(queries_over_time);
+    let blocked_points = to_points(blocked_over_time);
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.0, last.0),
+        _ => {
+            f.render_widget(block, area);
+            return;
+        }
+    };
+    let max_count = points
+        .iter()
+        .map(|(_, count)| *count)
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let label_style = Style::default().fg(app.theme.muted);
+    let time_labels = (0..5)
+        .map(|index| {
+            let timestamp = first + (last - first) * index as f64 / 4.0;
+            Span::styled(format_chart_time(app, timestamp as i64), label_style)
+        })
+        .collect();
+    let count_labels = [0.0, max_count / 2.0, max_count]
+        .iter()
+        .map(|count| Span::styled(app.count(count.round() as u64), label_style))
+        .collect();
+
+    let datasets = vec![
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.chart))
+            .data(&points),
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.error))
+            .data(&blocked_points),
+    ];
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds([first, last.max(first + 1.0)])
+                .labels(time_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max_count])
+                .labels(count_labels),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Draws the queries of the busiest clients over time as lines, newest on the right, like the
+/// client activity chart of the web interface
+pub fn draw_clients_chart<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let block = Block::default().borders(Borders::ALL);
+    let clients_over_time = match &app.displayed_data().clients_over_time {
+        Some(clients_over_time) if !clients_over_time.over_time.is_empty() => {
+            clients_over_time.clone()
+        }
+        _ => {
+            let title = format!("Client activity{}", chart_window_title(app, None));
+            f.render_widget(block.title(title), area);
+            return;
+        }
+    };
 
-    // Overview
-    draw_overview(f, app, chunks[2]);
+    let mut over_time: Vec<&(i64, Vec<u64>)> = clients_over_time.over_time.iter().rev().collect();
+    let squash_factor = app.graph_squash_factor;
+    pan_and_window_chart(app, &mut over_time, squash_factor, |(timestamp, _)| {
+        *timestamp
+    });
+    let newest_shown = over_time.first().map(|(timestamp, _)| *timestamp);
+    let block = block.title(format!(
+        "Client activity{}",
+        chart_window_title(app, newest_shown)
+    ));
 
-    // Queries chart
-    draw_queries_chart(f, app, chunks[3]);
+    let mut clients: Vec<(usize, String, u64)> = clients_over_time
+        .clients
+        .iter()
+        .enumerate()
+        .map(|(index, client)| {
+            let total = over_time
+                .iter()
+                .filter_map(|(_, counts)| counts.get(index))
+                .sum();
+            (index, app.shown_client(client), total)
+        })
+        .collect();
+    clients.sort_by_key(|(_, _, total)| std::cmp::Reverse(*total));
+    // Only as many clients as there are colours can be told apart
+    clients.truncate(app.theme.series.len());
 
-    // Top domains
-    draw_statistics(f, app, chunks[4]);
+    let series: Vec<Vec<(f64, f64)>> = clients
+        .iter()
+        .map(|(index, _, _)| {
+            // Squashed from the newest period like the queries chart
+            let newest_first: Vec<(i64, u64)> = over_time
+                .iter()
+                .map(|(timestamp, counts)| {
+                    (*timestamp, counts.get(*index).copied().unwrap_or_default())
+                })
+                .collect();
+            util::squash_queries_over_time(&newest_first, app.graph_squash_factor)
+                .iter()
+                .rev()
+                .map(|(timestamp, count)| (*timestamp as f64, *count as f64))
+                .collect()
+        })
+        .collect();
+    let (first, last) = match series.first().map(|points| (points.first(), points.last())) {
+        Some((Some(first), Some(last))) => (first.0, last.0),
+        _ => {
+            f.render_widget(block, area);
+            return;
+        }
+    };
+    let max_count = series
+        .iter()
+        .flatten()
+        .map(|(_, count)| *count)
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let label_style = Style::default().fg(app.theme.muted);
+    let time_labels = (0..5)
+        .map(|index| {
+            let timestamp = first + (last - first) * index as f64 / 4.0;
+            Span::styled(format_chart_time(app, timestamp as i64), label_style)
+        })
+        .collect();
+    let count_labels = [0.0, max_count / 2.0, max_count]
+        .iter()
+        .map(|count| Span::styled(app.count(count.round() as u64), label_style))
+        .collect();
+
+    let datasets = clients
+        .iter()
+        .zip(&series)
+        .zip(app.theme.series.iter())
+        .map(|(((_, client, _), points), colour)| {
+            Dataset::default()
+                .name(client.as_str())
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(*colour))
+                .data(points)
+        })
+        .collect();
+    let chart = Chart::new(datasets)
+        .block(block)
+        // Show the legend unless it would cover most of the chart
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(3, 4)))
+        .x_axis(
+            Axis::default()
+                .bounds([first, last.max(first + 1.0)])
+                .labels(time_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max_count])
+                .labels(count_labels),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Draws the daily totals of the long term database as bars, newest first, with the
+/// blocked part of each day coloured like the queries chart
+pub fn draw_long_term<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let days = app.long_term_period.days();
+    let data = app.displayed_data();
+    // Servers whose long term database cannot be read show what their history recorded
+    let source = if data.long_term_recorded {
+        " (recorded history)"
+    } else {
+        ""
+    };
+    let long_term = match &data.long_term {
+        Some(long_term) => long_term,
+        None => {
+            let block = Block::default()
+                .title(format!("Long term, last {} days", days))
+                .borders(Borders::ALL);
+            f.render_widget(block, area);
+            return;
+        }
+    };
+    let queries: u64 = long_term.iter().map(|day| day.queries).sum();
+    let blocked: u64 = long_term.iter().map(|day| day.blocked).sum();
+    let block = Block::default()
+        .title(Spans::from(vec![
+            Span::raw(format!("Long term, last {} days{}: ", days, source)),
+            Span::styled(
+                format!("{} queries", app.count(queries)),
+                Style::default().fg(app.theme.chart),
+            ),
+            Span::raw(", "),
+            Span::styled(
+                format!(
+                    "{} blocked ({:.1}%)",
+                    app.count(blocked),
+                    blocked as f64 * 100.0 / queries.max(1) as f64
+                ),
+                Style::default().fg(app.theme.error),
+            ),
+        ]))
+        .borders(Borders::ALL);
+
+    let labels: Vec<(String, u64)> = long_term
+        .iter()
+        .rev()
+        .map(|day| {
+            (
+                util::format_timestamp(day.start, "%d %b", app.timezone.as_ref()),
+                day.queries,
+            )
+        })
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect();
+    let max = long_term
+        .iter()
+        .map(|day| day.queries)
+        .max()
+        .unwrap_or_default();
+    let chart_area = block.inner(area);
+    let bar_style = Style::default().fg(app.theme.chart);
+    let value_style = Style::default()
+        .fg(app.theme.chart_value)
+        .bg(app.theme.chart);
+    let bar_chart = BarChart::default()
+        .block(block)
+        .data(&data)
+        .bar_width(LONG_TERM_BAR_WIDTH)
+        .bar_gap(BAR_GAP)
+        .max(max)
+        .bar_style(bar_style)
+        .value_style(value_style);
+    let counts: Vec<u64> = long_term.iter().rev().map(|day| day.queries).collect();
+    f.render_widget(
+        BlockedBars {
+            bar_chart,
+            chart_area,
+            bar_width: LONG_TERM_BAR_WIDTH,
+            bar_gap: BAR_GAP,
+            blocked: long_term.iter().rev().map(|day| day.blocked).collect(),
+            max,
+            style: Style::default().fg(app.theme.error),
+            values: bar_values(app, &counts, LONG_TERM_BAR_WIDTH),
+            counts,
+            bar_style,
+            value_style,
+        },
+        area,
+    );
+}
+
+/// Wide enough for the date and a count of up to 6 digits
+const LONG_TERM_BAR_WIDTH: u16 = 7;
+
+/// `counts` as written on bars `bar_width` wide, with room to spare on either side
+fn bar_values(app: &App, counts: &[u64], bar_width: u16) -> Vec<Option<String>> {
+    counts
+        .iter()
+        .map(|count| app.count_within(*count, usize::from(bar_width)))
+        .collect()
+}
+
+pub fn draw_statistics<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Ratio(1, 3),
+                Constraint::Ratio(1, 3),
+                Constraint::Ratio(1, 3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let table_rows: Vec<Vec<Vec<String>>> = StatisticsTable::ALL
+        .iter()
+        .map(|table| app.table_rows(*table))
+        .collect();
+    for ((table, rows), area) in StatisticsTable::ALL.iter().zip(table_rows).zip(chunks) {
+        draw_statistics_table(f, app, *table, rows, area);
+        if app.focused_panel == Panel::Statistics(*table) {
+            draw_focus_frame(f, app, area);
+        }
+    }
+}
+
+/// Draws one of the overview tables with its `rows`
+fn draw_statistics_table<B>(
+    f: &mut Frame<B>,
+    app: &mut App,
+    table: StatisticsTable,
+    rows: Vec<Vec<String>>,
+    area: Rect,
+) where
+    B: Backend,
+{
+    let header = vec![table.column().to_string(), "Count".to_string()];
+    let title = app.table_title(table);
+    let privacy_level = app.privacy_level();
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|mut row| {
+            if let Some(name) = row.first_mut().filter(|_| app.redact) {
+                *name = match table {
+                    StatisticsTable::Queries | StatisticsTable::Ads => app.shown_domain(name),
+                    StatisticsTable::Clients => app.shown_client(name),
+                };
+            }
+            if let Some(count) = row.get_mut(1) {
+                *count = app.summary_count(count);
+            }
+            row
+        })
+        .collect();
+    // Only the focused table shows the selection, once something has been selected
+    let state = if table == app.focused_table && app.statistics_table_state.selected().is_some() {
+        let length = rows.len();
+        match app.statistics_table_state.selected() {
+            Some(_) if length == 0 => app.statistics_table_state.select(None),
+            Some(selected) if selected >= length => {
+                app.statistics_table_state.select(Some(length - 1))
+            }
+            _ => {}
+        }
+        Some(&mut app.statistics_table_state)
+    } else {
+        None
+    };
+    app.click_areas.statistics_tables.push((table, area));
+    let title = if table == app.focused_table && !app.statistics_filter.is_empty() {
+        format!("{} /{}", title, app.statistics_filter)
+    } else {
+        title.to_string()
+    };
+    if table.hidden_by(privacy_level) {
+        let hidden = match table {
+            StatisticsTable::Queries | StatisticsTable::Ads => "domains",
+            StatisticsTable::Clients => "clients",
+        };
+        let note = Paragraph::new(privacy_note(privacy_level, hidden))
+            .style(Style::default().fg(app.theme.muted))
+            .wrap(Wrap { trim: true })
+            .block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(note, area);
+        return;
+    }
+    draw_list(f, &app.theme, area, &title, &header, &rows, state);
+}
+
+/// Explains why the `hidden` are missing
+fn privacy_note(privacy_level: PrivacyLevel, hidden: &str) -> String {
+    format!(
+        "The Pi-hole does not record {} at privacy level {}",
+        hidden,
+        privacy_level.number()
+    )
+}
+
+/// Draws `panel` of the Overview view in `area`, marked if it has the focus
+fn draw_panel<B>(f: &mut Frame<B>, app: &mut App, panel: Panel, area: Rect)
+where
+    B: Backend,
+{
+    match panel {
+        Panel::Overview => draw_overview(f, app, area),
+        Panel::Chart => match app.over_time_chart {
+            OverTimeChart::Queries => draw_queries_chart(f, app, area),
+            OverTimeChart::Clients => draw_clients_chart(f, app, area),
+        },
+        Panel::Statistics(table) => {
+            let rows = app.table_rows(table);
+            draw_statistics_table(f, app, table, rows, area);
+        }
+    }
+    if app.focused_panel == panel {
+        draw_focus_frame(f, app, area);
+    }
+}
+
+fn draw_focus_frame<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    f.render_widget(
+        FocusFrame {
+            style: Style::default().fg(app.theme.selection),
+        },
+        area,
+    );
+}
+
+pub fn draw_list<B>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    area: Rect,
+    title: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+    state: Option<&mut TableState>,
+) where
+    B: Backend,
+{
+    let up_style = Style::default().fg(theme.table_text);
+    let rows = rows.iter().map(|row| {
+        let style = up_style;
+        Row::new(row.iter().map(|text| Cell::from(text.clone()).style(style)))
+    });
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .title(vec![Span::from(title)])
+                .borders(Borders::ALL),
+        )
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(text.clone())))
+                .style(Style::default().fg(theme.table_header)),
+        )
+        .widths(&[Constraint::Percentage(70), Constraint::Percentage(30)]);
+    match state {
+        Some(state) => {
+            let table = table.highlight_style(
+                Style::default()
+                    .fg(theme.selection_text)
+                    .bg(theme.selection),
+            );
+            f.render_stateful_widget(table, area, state);
+        }
+        None => f.render_widget(table, area),
+    }
+}
+
+pub fn query_status_text(theme: &Theme, status: &QueryStatus) -> (&'static str, Color) {
+    match status {
+        QueryStatus::QueryGravity => ("Blocked (gravity)", theme.error),
+        QueryStatus::QueryForwarded => ("OK (forwarded)", theme.good),
+        QueryStatus::QueryCache => ("OK (cached)", theme.good),
+        QueryStatus::QueryRegex => ("Blocked (regex)", theme.error),
+        QueryStatus::QueryBlacklist => ("Blocked (exact)", theme.error),
+        QueryStatus::QueryExternalBlockedIp
+        | QueryStatus::QueryExternalBlockedNull
+        | QueryStatus::QueryExternalBlockedNxra => ("Blocked (external)", theme.error),
+        QueryStatus::QueryGravityCname
+        | QueryStatus::QueryRegexCname
+        | QueryStatus::QueryBlacklistCname => ("Blocked (CNAME)", theme.error),
+        QueryStatus::QueryRetried | QueryStatus::QueryRetriedDnssec => ("OK (retried)", theme.good),
+        QueryStatus::QueryInProgress => ("In progress", theme.warning),
+        QueryStatus::QueryDbbusy => ("Database busy", theme.warning),
+        QueryStatus::QueryUnknown | QueryStatus::QueryStatusMax => ("Unknown", theme.muted),
+    }
+}
+
+/// Height of the reply times below the query log, a line for each range and the borders
+const REPLY_TIMES_HEIGHT: u16 = util::REPLY_TIME_RANGES_MS.len() as u16 + 3;
+
+pub fn draw_query_log<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    // The reply times only take space from a query log showing plenty of queries
+    let reply_times = app
+        .reply_times()
+        .filter(|_| area.height >= REPLY_TIMES_HEIGHT * 3);
+    let area = match reply_times {
+        Some(reply_times) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(REPLY_TIMES_HEIGHT)].as_ref())
+                .split(area);
+            draw_reply_times(f, app, &reply_times, chunks[1]);
+            chunks[0]
+        }
+        None => area,
+    };
+    let block = Block::default().title("Query log").borders(Borders::ALL);
+    let all_tab_selected = app.all_tab_selected();
+    let privacy_level = app.privacy_level();
+    if privacy_level == PrivacyLevel::Anonymous {
+        let note = Paragraph::new(privacy_note(privacy_level, "queries"))
+            .style(Style::default().fg(app.theme.muted))
+            .block(block);
+        f.render_widget(note, area);
+        return;
+    }
+    let mut queries = match app.query_log() {
+        Some(queries) => queries,
+        None => {
+            f.render_widget(block, area);
+            return;
+        }
+    };
+    // Followed like a tail, the newest at the bottom and the oldest scrolling off the top
+    if app.follow_query_log {
+        // Inside the borders and below the header
+        let visible = usize::from(area.height.saturating_sub(3));
+        queries.truncate(visible);
+        queries.reverse();
+    }
+    // Columns the server only fills with "hidden" are left out
+    let show_domain = !privacy_level.hides_domains();
+    let show_client = !privacy_level.hides_clients();
+    let mut title = match (show_domain, show_client) {
+        (true, true) => "Query log".to_string(),
+        (false, true) => format!(
+            "Query log (domains hidden at privacy level {})",
+            privacy_level.number()
+        ),
+        _ => format!(
+            "Query log (domains and clients hidden at privacy level {})",
+            privacy_level.number()
+        ),
+    };
+    if app.follow_query_log {
+        title.push_str(" - following");
+    }
+    let block = block.title(title);
+
+    let rows = queries.iter().map(|(name, query)| {
+        let (status, status_colour) = query_status_text(&app.theme, &query.status);
+        let mut cells = vec![
+            Cell::from(util::format_timestamp(
+                query.timestring.and_utc().timestamp(),
+                &app.time_format.seconds,
+                app.timezone.as_ref(),
+            )),
+            Cell::from(format!("{:?}", query.query_type)),
+        ];
+        if show_domain {
+            cells.push(Cell::from(app.shown_domain(&query.domain)));
+        }
+        if show_client {
+            cells.push(Cell::from(app.shown_client(&query.client)));
+        }
+        cells.push(Cell::from(status).style(Style::default().fg(status_colour)));
+        if all_tab_selected {
+            cells.push(Cell::from(name.to_string()));
+        }
+        // Blocked queries stand out from the stream of queries
+        if app.follow_query_log && util::is_blocked(&query.status) {
+            Row::new(cells).style(Style::default().fg(app.theme.error))
+        } else {
+            Row::new(cells)
+        }
+    });
+    let (domain_width, client_width) = if all_tab_selected { (40, 20) } else { (50, 25) };
+    let mut header = vec!["Time", "Type"];
+    let mut widths = vec![
+        Constraint::Length(util::time_width(&app.time_format.seconds)),
+        Constraint::Length(6),
+    ];
+    if show_domain {
+        header.push("Domain");
+        widths.push(Constraint::Percentage(domain_width));
+    }
+    if show_client {
+        header.push("Client");
+        widths.push(Constraint::Percentage(client_width));
+    }
+    header.push("Status");
+    widths.push(Constraint::Length(18));
+    if all_tab_selected {
+        header.push("Server");
+        widths.push(Constraint::Percentage(15));
+    }
+    let table = Table::new(rows)
+        .block(block)
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(app.theme.table_header)),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Draws how many of the queries of the query log were answered within each range of reply
+/// times and the average reply time of each upstream server
+fn draw_reply_times<B>(f: &mut Frame<B>, app: &App, reply_times: &ReplyTimes, area: Rect)
+where
+    B: Backend,
+{
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Reply times of {} queries: min {}  avg {}  max {}",
+        reply_times.count,
+        format_reply_time(reply_times.min),
+        format_reply_time(reply_times.average),
+        format_reply_time(reply_times.max)
+    ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(inner);
+
+    let mut labels: Vec<String> = util::REPLY_TIME_RANGES_MS
+        .iter()
+        .map(|bound| format!("<{}ms", bound))
+        .collect();
+    if let Some(last) = util::REPLY_TIME_RANGES_MS.last() {
+        labels.push(format!("{}ms+", last));
+    }
+    let label_width = 8;
+    let most = reply_times
+        .histogram
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or_default();
+    // Leave room for the label and the count
+    let bar_width = chunks[0].width.saturating_sub(label_width + 7) as usize;
+    let histogram: Vec<Spans> = labels
+        .iter()
+        .zip(&reply_times.histogram)
+        .map(|(label, count)| {
+            let bar_length = (bar_width * count).checked_div(most).unwrap_or_default();
+            Spans::from(vec![
+                Span::raw(format!(
+                    "{:<width$}{:>6} ",
+                    label,
+                    count,
+                    width = label_width as usize
+                )),
+                Span::styled(
+                    "\u{2588}".repeat(bar_length),
+                    Style::default().fg(app.theme.chart),
+                ),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(histogram), chunks[0]);
+
+    let upstreams: Vec<Spans> = if reply_times.upstreams.is_empty() {
+        vec![Spans::from(Span::styled(
+            "No forwarded queries",
+            Style::default().fg(app.theme.muted),
+        ))]
+    } else {
+        reply_times
+            .upstreams
+            .iter()
+            .map(|(upstream, count, average)| {
+                Spans::from(format!(
+                    "{}: {} forwarded, avg {}",
+                    upstream,
+                    count,
+                    format_reply_time(*average)
+                ))
+            })
+            .collect()
+    };
+    f.render_widget(Paragraph::new(upstreams), chunks[1]);
+}
+
+/// Reply time in milliseconds, with a decimal below a second
+fn format_reply_time(reply_time: Duration) -> String {
+    if reply_time < Duration::from_secs(1) {
+        format!("{:.1}ms", reply_time.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.1}s", reply_time.as_secs_f64())
+    }
+}
+
+/// Draws the devices of the network table, the most recently active first
+pub fn draw_network<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    match app.dhcp_leases() {
+        Some(leases) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+                .split(area);
+            draw_network_devices(f, app, chunks[0]);
+            draw_dhcp_leases(f, app, &leases, chunks[1]);
+        }
+        None => draw_network_devices(f, app, area),
+    }
+}
+
+fn draw_network_devices<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let block = Block::default().title("Network").borders(Borders::ALL);
+    let all_tab_selected = app.all_tab_selected();
+    let devices = match app.network_devices() {
+        Some(devices) => devices,
+        None => {
+            f.render_widget(block, area);
+            return;
+        }
+    };
+
+    let rows = devices.iter().map(|(name, device)| {
+        let last_query = match device.last_query {
+            0 => "never".to_string(),
+            last_query => util::format_timestamp(
+                last_query,
+                &app.time_format.date_minutes(),
+                app.timezone.as_ref(),
+            ),
+        };
+        let mut cells = vec![
+            Cell::from(
+                device
+                    .names
+                    .iter()
+                    .map(|name| app.shown_address(name))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            Cell::from(
+                device
+                    .ips
+                    .iter()
+                    .map(|ip| app.shown_address(ip))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            Cell::from(app.shown_mac(&device.hwaddr)),
+            Cell::from(device.interface.clone()),
+            Cell::from(last_query),
+            Cell::from(app.count(device.queries)),
+        ];
+        if all_tab_selected {
+            cells.push(Cell::from(name.to_string()));
+        }
+        Row::new(cells).style(Style::default().fg(app.theme.table_text))
+    });
+    let mut header = vec![
+        "Hostname",
+        "IP addresses",
+        "MAC address",
+        "Interface",
+        "Last query",
+        "Queries",
+    ];
+    let mut widths = vec![
+        Constraint::Percentage(25),
+        Constraint::Percentage(30),
+        Constraint::Length(17),
+        Constraint::Length(9),
+        Constraint::Length(util::time_width(&app.time_format.date_minutes())),
+        Constraint::Length(10),
+    ];
+    if all_tab_selected {
+        header.push("Server");
+        widths[0] = Constraint::Percentage(20);
+        widths[1] = Constraint::Percentage(20);
+        widths.push(Constraint::Percentage(15));
+    }
+    let table = Table::new(rows)
+        .block(block)
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(app.theme.table_header)),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+fn draw_dhcp_leases<B>(f: &mut Frame<B>, app: &App, leases: &[(&str, &DhcpLease)], area: Rect)
+where
+    B: Backend,
+{
+    let all_tab_selected = app.all_tab_selected();
+    let rows = leases.iter().map(|(name, lease)| {
+        let expires = match lease.expires {
+            0 => "never".to_string(),
+            expires => util::format_timestamp(
+                expires,
+                &app.time_format.date_minutes(),
+                app.timezone.as_ref(),
+            ),
+        };
+        let mut cells = vec![
+            Cell::from(app.shown_address(&lease.name)),
+            Cell::from(app.shown_address(&lease.ip)),
+            Cell::from(app.shown_mac(&lease.hwaddr)),
+            Cell::from(expires),
+        ];
+        if all_tab_selected {
+            cells.push(Cell::from(name.to_string()));
+        }
+        Row::new(cells).style(Style::default().fg(app.theme.table_text))
+    });
+    let mut header = vec!["Hostname", "IP address", "MAC address", "Expires"];
+    let mut widths = vec![
+        Constraint::Percentage(35),
+        Constraint::Percentage(25),
+        Constraint::Length(17),
+        Constraint::Length(util::time_width(&app.time_format.date_minutes())),
+    ];
+    if all_tab_selected {
+        header.push("Server");
+        widths[0] = Constraint::Percentage(25);
+        widths[1] = Constraint::Percentage(20);
+        widths.push(Constraint::Percentage(15));
+    }
+    let table = Table::new(rows)
+        .block(Block::default().title("DHCP leases").borders(Borders::ALL))
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(app.theme.table_header)),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Rounds up so the countdown does not show 0s while the retry is still waiting
+fn format_retry_delay(retrying_in: Duration) -> String {
+    util::format_duration(Duration::from_secs(retrying_in.as_secs_f64().ceil() as u64))
+}
+
+/// Draws the client selected in Top Clients, its counts of today, the domains of its recent
+/// queries and how many of them were made in each of the last 24 hours
+pub fn draw_client<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let details = match app.client_details() {
+        Some(details) => details,
+        None => return,
+    };
+    let theme = &app.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(area);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(8), Constraint::Min(3)].as_ref())
+        .split(chunks[0]);
+
+    let count = |count: Option<u64>| match count {
+        Some(count) => app.count(count),
+        None => "-".to_string(),
+    };
+    let mut text = vec![
+        Spans::from(format!(
+            "Name: {}",
+            details
+                .name
+                .as_deref()
+                .map_or_else(|| "(none)".to_string(), |name| app.shown_address(name))
+        )),
+        Spans::from(format!("Address: {}", app.shown_address(&details.address))),
+        Spans::from(format!("Queries today: {}", count(details.queries))),
+        Spans::from(vec![
+            Span::raw("Blocked today: "),
+            Span::styled(count(details.blocked), Style::default().fg(theme.error)),
+        ]),
+    ];
+    if let Some(recent_queries) = details.recent_queries {
+        text.push(Spans::from(vec![
+            Span::raw(format!("Last {} queries: ", recent_queries)),
+            Span::styled(
+                format!(
+                    "{} blocked ({:.1}%)",
+                    details.recent_blocked,
+                    details.recent_blocked as f64 * 100.0 / recent_queries.max(1) as f64
+                ),
+                Style::default().fg(theme.error),
+            ),
+        ]));
+    }
+    let summary = Paragraph::new(text)
+        .block(Block::default().title("Client").borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
+    f.render_widget(summary, left[0]);
+
+    let header = vec!["Domain".to_string(), "Count".to_string()];
+    let top_domains: Vec<Vec<String>> = details
+        .top_domains
+        .iter()
+        .map(|row| match row.split_first() {
+            Some((domain, rest)) => std::iter::once(app.shown_domain(domain))
+                .chain(rest.iter().map(|count| app.summary_count(count)))
+                .collect(),
+            None => Vec::new(),
+        })
+        .collect();
+    draw_list(
+        f,
+        theme,
+        left[1],
+        "Top domains of recent queries",
+        &header,
+        &top_domains,
+        None,
+    );
+
+    let block = Block::default()
+        .title("Recent queries per hour")
+        .borders(Borders::ALL);
+    let labels: Vec<(String, u64)> = details
+        .hourly
+        .iter()
+        .map(|(start, count)| {
+            (
+                util::format_timestamp(*start, &app.time_format.hour, app.timezone.as_ref()),
+                *count,
+            )
+        })
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect();
+    // As wide as the 24 bars fit
+    let hours = details.hourly.len().max(1) as u16;
+    let bar_width = ((block.inner(chunks[1]).width + BAR_GAP) / hours)
+        .saturating_sub(BAR_GAP)
+        .max(1);
+    let bar_chart = BarChart::default()
+        .block(block)
+        .data(&data)
+        .bar_width(bar_width)
+        .bar_gap(BAR_GAP)
+        .bar_style(Style::default().fg(theme.chart))
+        .value_style(Style::default().fg(theme.chart_value).bg(theme.chart));
+    f.render_widget(bar_chart, chunks[1]);
+}
+
+/// Draws the latency and errors of every endpoint of every server, the server's row group
+/// starts with when it was last updated and how long the update took
+pub fn draw_diagnostics<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = &app.theme;
+    let mut rows = Vec::new();
+    for server in &app.servers {
+        let last_update = match (server.last_update, server.last_update_duration) {
+            (Some(last_update), Some(duration)) => format!(
+                "{} ({} ago) in {} ms",
+                format_update_time(app, last_update),
+                util::format_duration(last_update.elapsed()),
+                duration.as_millis()
+            ),
+            (Some(last_update), None) => format!(
+                "{} ({} ago)",
+                format_update_time(app, last_update),
+                util::format_duration(last_update.elapsed())
+            ),
+            (None, _) => "Pending".to_string(),
+        };
+        let last_update = match server.retrying_in() {
+            Some(retrying_in) => format!(
+                "{}, retrying in {}",
+                last_update,
+                format_retry_delay(retrying_in)
+            ),
+            None => last_update,
+        };
+        if server.endpoint_statistics.is_empty() {
+            rows.push(Row::new(vec![
+                Cell::from(server.name.clone()),
+                Cell::from(last_update),
+            ]));
+            continue;
+        }
+        for (index, statistics) in server.endpoint_statistics.iter().enumerate() {
+            let (name, last_update) = if index == 0 {
+                (server.name.clone(), last_update.clone())
+            } else {
+                (String::new(), String::new())
+            };
+            let latency_colour = if statistics.last_duration > util::SLOW_REQUEST {
+                theme.warning
+            } else {
+                theme.table_text
+            };
+            let errors_colour = if statistics.errors > 0 {
+                theme.error
+            } else {
+                theme.table_text
+            };
+            rows.push(Row::new(vec![
+                Cell::from(name),
+                Cell::from(last_update),
+                Cell::from(statistics.endpoint),
+                Cell::from(format!("{} ms", statistics.last_duration.as_millis()))
+                    .style(Style::default().fg(latency_colour)),
+                Cell::from(statistics.requests.to_string()),
+                Cell::from(statistics.errors.to_string()).style(Style::default().fg(errors_colour)),
+                Cell::from(statistics.last_error.clone().unwrap_or_default())
+                    .style(Style::default().fg(theme.muted)),
+            ]));
+        }
+    }
+    let header = [
+        "Server",
+        "Last update",
+        "Endpoint",
+        "Latency",
+        "Requests",
+        "Errors",
+        "Last error",
+    ];
+    // The time of the last update and brackets around how long ago it was
+    let last_update_width = 36 + util::time_width(&app.time_format.seconds) + 3;
+    let widths = [
+        Constraint::Percentage(15),
+        Constraint::Length(last_update_width),
+        Constraint::Length(16),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(7),
+        Constraint::Percentage(40),
+    ];
+    let table = Table::new(rows)
+        .block(Block::default().title("Diagnostics").borders(Borders::ALL))
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(theme.table_header)),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Narrowest a column of the split view is drawn, more servers than fit are scrolled through
+const SPLIT_COLUMN_WIDTH: u16 = 40;
+
+/// Draws the summary and queries chart of each server in columns side by side, starting
+/// from the selected server when they do not all fit
+pub fn draw_split<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    // Only the servers of the selected group
+    let indices = app.tab_server_indices();
+    let fitting = usize::from((area.width / SPLIT_COLUMN_WIDTH).max(1));
+    let count = indices.len().min(fitting);
+    let first = match indices
+        .iter()
+        .position(|index| *index == app.selected_server_index)
+    {
+        Some(position) if !app.all_tab_selected() => position.min(indices.len() - count),
+        _ => 0,
+    };
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, count as u32); count])
+        .split(area);
+    for (index, column) in indices[first..first + count].iter().copied().zip(columns) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(8), Constraint::Min(0)].as_ref())
+            .split(column);
+        let server = &app.servers[index];
+        let theme = &app.theme;
+        let title_colour = if !app.all_tab_selected() && index == app.selected_server_index {
+            theme.tab_highlight
+        } else {
+            theme.tab_name
+        };
+        let summary = Paragraph::new(split_summary_text(app, server)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(Span::styled(
+                    server.name.clone(),
+                    Style::default().fg(title_colour),
+                )),
+        );
+        f.render_widget(summary, rows[0]);
+        let over_time_rows = queries_over_time_rows(&server.last_data);
+        draw_queries_over_time(f, app, over_time_rows, rows[1]);
+    }
+}
+
+/// The counts of today of `server` in a few lines
+fn split_summary_text<'a>(app: &App, server: &util::PiHoleServer) -> Vec<Spans<'a>> {
+    let theme = &app.theme;
+    let summary = match &server.last_data.summary {
+        Some(summary) => summary,
+        None => {
+            return vec![Spans::from(Span::styled(
+                "Unreachable",
+                Style::default().fg(theme.error),
+            ))]
+        }
+    };
+    let status_colour = match summary.status.as_str() {
+        "enabled" => theme.good,
+        _ => theme.error,
+    };
+    vec![
+        Spans::from(vec![
+            Span::raw("Status: "),
+            Span::styled(summary.status.clone(), Style::default().fg(status_colour)),
+        ]),
+        Spans::from(format!(
+            "Queries: {}",
+            app.summary_count(&summary.dns_queries_today)
+        )),
+        Spans::from(format!(
+            "Blocked: {} ({}%)",
+            app.summary_count(&summary.ads_blocked_today),
+            summary.ads_percentage_today
+        )),
+        Spans::from(format!(
+            "Forwarded: {}  Cached: {}",
+            app.summary_count(&summary.queries_forwarded),
+            app.summary_count(&summary.queries_cached)
+        )),
+        Spans::from(format!(
+            "Unique clients: {}",
+            app.summary_count(&summary.unique_clients)
+        )),
+        Spans::from(format!(
+            "Blocklist size: {}",
+            app.summary_count(&summary.domains_being_blocked)
+        )),
+    ]
+}
+
+/// Draws every alert raised since start, newest first, those still active in the error colour
+pub fn draw_alerts<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = &app.theme;
+    let rows = app.alerts.history.iter().rev().map(|alert| {
+        let (cleared, colour) = match alert.cleared_at {
+            Some(cleared_at) => (
+                cleared_at.format(&app.time_format.seconds).to_string(),
+                theme.table_text,
+            ),
+            None => ("Active".to_string(), theme.error),
+        };
+        Row::new(vec![
+            Cell::from(
+                alert
+                    .raised_at
+                    .format(&app.time_format.date_seconds())
+                    .to_string(),
+            ),
+            Cell::from(cleared),
+            Cell::from(alert.server.clone()),
+            Cell::from(alert.message.clone()),
+        ])
+        .style(Style::default().fg(colour))
+    });
+    let title = if app.alerts.config.is_empty() {
+        "Alerts (no rules configured)"
+    } else {
+        "Alerts"
+    };
+    let header = ["Raised", "Cleared", "Server", "Alert"];
+    let widths = [
+        Constraint::Length(util::time_width(&app.time_format.date_seconds())),
+        // Wide enough for "Active"
+        Constraint::Length(util::time_width(&app.time_format.seconds).max(6)),
+        Constraint::Percentage(20),
+        Constraint::Percentage(60),
+    ];
+    let table = Table::new(rows)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(theme.table_header)),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Draws the white- and blacklists in a grid, the highlighted list has a selectable row
+pub fn draw_domain_lists<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+        .split(area);
+    let areas: Vec<Rect> = rows
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+                .split(*row)
+        })
+        .collect();
+
+    // Keep the selection within the list, which may have shrunk since the last update
+    let length = app.selected_list_domains().map_or(0, <[_]>::len);
+    match app.list_table_state.selected() {
+        Some(_) if length == 0 => app.list_table_state.select(None),
+        Some(selected) if selected >= length => app.list_table_state.select(Some(length - 1)),
+        None if length > 0 => app.list_table_state.select(Some(0)),
+        _ => {}
+    }
+
+    if app.all_tab_selected() {
+        let message = Paragraph::new("Select a server to view its lists")
+            .block(Block::default().title("Lists").borders(Borders::ALL));
+        f.render_widget(message, area);
+        return;
+    }
+    let theme = &app.theme;
+    let server = &app.servers[app.selected_server_index];
+    for (list, area) in DomainList::ALL.iter().zip(areas) {
+        app.click_areas.lists.push((*list, area));
+        let highlighted = *list == app.selected_list;
+        let border_style = if highlighted {
+            Style::default().fg(theme.selection)
+        } else {
+            Style::default()
+        };
+        let block = Block::default()
+            .title(list.title())
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        let domains = match &server.last_data.domain_lists {
+            Some(domain_lists) => &domain_lists[list.index()],
+            None => {
+                let message = if server.api_config.is_authenticated() {
+                    ""
+                } else {
+                    "An API key is required to view the lists"
+                };
+                f.render_widget(Paragraph::new(message).block(block), area);
+                continue;
+            }
+        };
+
+        let rows = domains.iter().map(|entry| {
+            let (enabled, enabled_colour) = if entry.enabled {
+                ("yes", theme.good)
+            } else {
+                ("no", theme.error)
+            };
+            Row::new(vec![
+                Cell::from(app.shown_domain(&entry.domain)),
+                Cell::from(enabled).style(Style::default().fg(enabled_colour)),
+                Cell::from(entry.date_added.format("%Y-%m-%d").to_string()),
+                Cell::from(entry.comment.clone()),
+            ])
+        });
+        let header = ["Domain", "Enabled", "Added", "Comment"];
+        let table = Table::new(rows)
+            .block(block)
+            .header(
+                Row::new(header.iter().map(|text| Cell::from(*text)))
+                    .style(Style::default().fg(theme.table_header)),
+            )
+            .widths(&[
+                Constraint::Percentage(50),
+                Constraint::Length(7),
+                Constraint::Length(10),
+                Constraint::Percentage(30),
+            ])
+            .highlight_style(
+                Style::default()
+                    .fg(theme.selection_text)
+                    .bg(theme.selection),
+            );
+        if highlighted {
+            f.render_stateful_widget(table, area, &mut app.list_table_state);
+        } else {
+            f.render_widget(table, area);
+        }
+    }
+}
+
+/// Draws the DNS and CNAME records side by side, the highlighted records have a selectable row
+pub fn draw_local_records<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+        .split(area);
+
+    // Keep the selection within the records, which may have changed since the last update
+    let length = app.selected_local_records().map_or(0, <[_]>::len);
+    match app.record_table_state.selected() {
+        Some(_) if length == 0 => app.record_table_state.select(None),
+        Some(selected) if selected >= length => app.record_table_state.select(Some(length - 1)),
+        None if length > 0 => app.record_table_state.select(Some(0)),
+        _ => {}
+    }
+
+    if app.all_tab_selected() {
+        let message = Paragraph::new("Select a server to view its Local DNS records")
+            .block(Block::default().title("Local DNS").borders(Borders::ALL));
+        f.render_widget(message, area);
+        return;
+    }
+    let theme = &app.theme;
+    let server = &app.servers[app.selected_server_index];
+    for (record_type, area) in LocalRecordType::ALL.iter().zip(areas) {
+        app.click_areas.local_records.push((*record_type, area));
+        let highlighted = *record_type == app.selected_record_type;
+        let border_style = if highlighted {
+            Style::default().fg(theme.selection)
+        } else {
+            Style::default()
+        };
+        let block = Block::default()
+            .title(record_type.title())
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        let records = match &server.last_data.local_records {
+            Some(records) => &records[record_type.index()],
+            None => {
+                let message = if server.api_config.is_authenticated() {
+                    ""
+                } else {
+                    "An API key is required to view the records"
+                };
+                f.render_widget(Paragraph::new(message).block(block), area);
+                continue;
+            }
+        };
+
+        let rows = records.iter().map(|record| {
+            Row::new(vec![
+                Cell::from(app.shown_domain(&record.domain)),
+                Cell::from(app.shown_record_target(*record_type, &record.target)),
+            ])
+            .style(Style::default().fg(theme.table_text))
+        });
+        let header = ["Domain", record_type.target_title()];
+        let table = Table::new(rows)
+            .block(block)
+            .header(
+                Row::new(header.iter().map(|text| Cell::from(*text)))
+                    .style(Style::default().fg(theme.table_header)),
+            )
+            .widths(&[Constraint::Percentage(55), Constraint::Percentage(45)])
+            .highlight_style(
+                Style::default()
+                    .fg(theme.selection_text)
+                    .bg(theme.selection),
+            );
+        if highlighted {
+            f.render_stateful_widget(table, area, &mut app.record_table_state);
+        } else {
+            f.render_widget(table, area);
+        }
+    }
+}
+
+/// Draws the adlists of the selected server with a selectable row
+pub fn draw_adlists<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    // Keep the selection within the adlists, which may have changed since the last update
+    let length = app.selected_adlists().map_or(0, <[_]>::len);
+    match app.adlist_table_state.selected() {
+        Some(_) if length == 0 => app.adlist_table_state.select(None),
+        Some(selected) if selected >= length => app.adlist_table_state.select(Some(length - 1)),
+        None if length > 0 => app.adlist_table_state.select(Some(0)),
+        _ => {}
+    }
+
+    let block = Block::default().title("Adlists").borders(Borders::ALL);
+    if app.all_tab_selected() {
+        let message = Paragraph::new("Select a server to view its adlists").block(block);
+        f.render_widget(message, area);
+        return;
+    }
+    app.click_areas.adlists = Some(area);
+    let theme = &app.theme;
+    let server = &app.servers[app.selected_server_index];
+    let adlists = match &server.last_data.adlists {
+        Some(adlists) => adlists,
+        None => {
+            let message = if server.api_config.manages_adlists() {
+                ""
+            } else {
+                "The v5 API does not list the adlists"
+            };
+            f.render_widget(Paragraph::new(message).block(block), area);
+            return;
+        }
+    };
+
+    let rows = adlists.iter().map(|adlist| {
+        let (enabled, enabled_colour) = if adlist.enabled {
+            ("yes", theme.good)
+        } else {
+            ("no", theme.error)
+        };
+        let updated = match adlist.date_updated {
+            0 => "never".to_string(),
+            updated => util::format_timestamp(
+                updated,
+                &app.time_format.date_minutes(),
+                app.timezone.as_ref(),
+            ),
+        };
+        Row::new(vec![
+            Cell::from(adlist.address.clone()),
+            Cell::from(enabled).style(Style::default().fg(enabled_colour)),
+            Cell::from(app.count(adlist.domains)),
+            Cell::from(updated),
+            Cell::from(adlist.comment.clone()),
+        ])
+        .style(Style::default().fg(theme.table_text))
+    });
+    let header = ["Address", "Enabled", "Domains", "Last update", "Comment"];
+    let widths = [
+        Constraint::Percentage(50),
+        Constraint::Length(7),
+        Constraint::Length(11),
+        Constraint::Length(util::time_width(&app.time_format.date_minutes())),
+        Constraint::Percentage(30),
+    ];
+    let table = Table::new(rows)
+        .block(block)
+        .header(
+            Row::new(header.iter().map(|text| Cell::from(*text)))
+                .style(Style::default().fg(theme.table_header)),
+        )
+        .widths(&widths)
+        .highlight_style(
+            Style::default()
+                .fg(theme.selection_text)
+                .bg(theme.selection),
+        );
+    f.render_stateful_widget(table, area, &mut app.adlist_table_state);
+}
+
+/// Rectangle of at most `width` x `height` in the middle of `area`
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+pub fn draw_server_editor<B>(f: &mut Frame<B>, theme: &Theme, editor: &ServerEditor, area: Rect)
+where
+    B: Backend,
+{
+    let title = match editor.server_index {
+        Some(_) => "Edit server",
+        None => "Add server",
+    };
+    let mut lines: Vec<Spans> = util::SERVER_EDITOR_FIELDS
+        .iter()
+        .zip(&editor.values)
+        .enumerate()
+        .map(|(index, (label, value))| {
+            let value = match index {
+                ServerEditor::API_KEY_FIELD
+                    if value.is_empty() && editor.server_index.is_some() =>
+                {
+                    Span::styled("(unchanged)", Style::default().fg(theme.muted))
+                }
+                ServerEditor::API_KEY_FIELD => Span::raw("*".repeat(value.chars().count().min(50))),
+                _ => Span::raw(value.clone()),
+            };
+            let label_style = if index == editor.selected_field {
+                Style::default().fg(theme.selection_text).bg(theme.accent)
+            } else {
+                Style::default().fg(theme.accent)
+            };
+            let cursor = if index == editor.selected_field {
+                "_"
+            } else {
+                ""
+            };
+            Spans::from(vec![
+                Span::styled(format!("{:>11}", label), label_style),
+                Span::raw(" "),
+                value,
+                Span::raw(cursor),
+            ])
+        })
+        .collect();
+    lines.push(Spans::default());
+    lines.push(match &editor.error {
+        Some(error) => Spans::from(Span::styled(
+            error.clone(),
+            Style::default().fg(theme.error),
+        )),
+        None => Spans::from("Enter: Save  Esc: Cancel  Tab/Up/Down: Change field"),
+    });
+
+    let height = lines.len() as u16 + 2;
+    let popup_area = centered_rect(70, height, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+pub fn draw_input_prompt<B>(f: &mut Frame<B>, theme: &Theme, prompt: &InputPrompt, area: Rect)
+where
+    B: Backend,
+{
+    let (title, label, hint, action) = match prompt.kind {
+        PromptKind::DisableDuration => (
+            "Disable blocking".to_string(),
+            "Duration",
+            "e.g. 30s, 5m, 1h30m or 0 for permanently",
+            "Disable",
+        ),
+        PromptKind::AddDomain(list) => (
+            format!("Add to the {}", list.title().to_lowercase()),
+            "Domain",
+            match list {
+                DomainList::Whitelist | DomainList::Blacklist => "e.g. ads.example.com",
+                DomainList::RegexWhitelist | DomainList::RegexBlacklist => {
+                    r"e.g. (\.|^)example\.com$"
+                }
+            },
+            "Add",
+        ),
+        PromptKind::AddLocalRecord(record_type) => (
+            format!("Add to the {}", record_type.title()),
+            "Record",
+            match record_type {
+                LocalRecordType::Dns => "Domain and IP address, e.g. nas.lan 192.168.1.10",
+                LocalRecordType::Cname => "Domain and target, e.g. media.lan nas.lan",
+            },
+            "Add",
+        ),
+        PromptKind::AddAdlist => (
+            "Add an adlist".to_string(),
+            "URL",
+            "e.g. https://example.com/hosts.txt",
+            "Add",
+        ),
+        // Typed into the help bar instead
+        PromptKind::Filter => return,
+    };
+    let lines = vec![
+        Spans::from(vec![
+            Span::styled(format!("{} ", label), Style::default().fg(theme.accent)),
+            Span::raw(prompt.input.clone()),
+            Span::raw("_"),
+        ]),
+        Spans::default(),
+        match &prompt.error {
+            Some(error) => Spans::from(Span::styled(
+                error.clone(),
+                Style::default().fg(theme.error),
+            )),
+            None => Spans::from(hint),
+        },
+        Spans::from(format!("Enter: {}  Esc: Cancel", action)),
+    ];
+
+    let popup_area = centered_rect(70, lines.len() as u16 + 2, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Draws the counts of today of the domain selected in Top Queries or Top Ads, with its recent
+/// queries and the status of the latest, showing whether and by which list it is blocked
+pub fn draw_domain_popup<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let details = match app.domain_details() {
+        Some(details) => details,
+        None => return,
+    };
+    let theme = &app.theme;
+    let popup_area = centered_rect(area.width * 4 / 5, area.height.saturating_sub(4), area);
+    let block = Block::default()
+        .title(app.shown_domain(&details.domain))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(popup_area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(0)].as_ref())
+        .split(inner);
+
+    let count = |count: Option<u64>| match count {
+        Some(count) => app.count(count),
+        None => "-".to_string(),
+    };
+    let mut text = vec![
+        Spans::from(format!("Permitted today: {}", count(details.permitted))),
+        Spans::from(vec![
+            Span::raw("Blocked today: "),
+            Span::styled(count(details.blocked), Style::default().fg(theme.error)),
+        ]),
+    ];
+    match details.recent_queries.as_deref() {
+        Some([]) => text.push(Spans::from("No recent queries")),
+        Some(queries @ [(_, latest), ..]) => {
+            let (status, colour) = query_status_text(theme, &latest.status);
+            text.push(Spans::from(vec![
+                Span::raw("Latest query: "),
+                Span::styled(status, Style::default().fg(colour)),
+            ]));
+            let blocked = queries
+                .iter()
+                .filter(|(_, query)| util::is_blocked(&query.status))
+                .count();
+            text.push(Spans::from(format!(
+                "Last {} queries: {} blocked",
+                queries.len(),
+                blocked
+            )));
+        }
+        None => text.push(Spans::from(Span::styled(
+            "Fetching recent queries\u{2026}",
+            Style::default().fg(theme.muted),
+        ))),
+    }
+    text.push(Spans::from(Span::styled(
+        "Y: Copy  R: Regex block  Esc/Enter: Close",
+        Style::default().fg(theme.muted),
+    )));
+    f.render_widget(Paragraph::new(text), chunks[0]);
+
+    let all_tab_selected = app.all_tab_selected();
+    let rows = details
+        .recent_queries
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(server, query)| {
+            let (status, colour) = query_status_text(theme, &query.status);
+            let mut cells = vec![
+                Cell::from(util::format_timestamp(
+                    query.timestring.and_utc().timestamp(),
+                    &app.time_format.date_seconds(),
+                    app.timezone.as_ref(),
+                )),
+                Cell::from(app.shown_client(&query.client)),
+                Cell::from(status).style(Style::default().fg(colour)),
+            ];
+            if all_tab_selected {
+                cells.push(Cell::from(server));
+            }
+            Row::new(cells).style(Style::default().fg(theme.table_text))
+        });
+    let mut header = vec!["Time", "Client", "Status"];
+    let mut widths = vec![
+        Constraint::Length(util::time_width(&app.time_format.date_seconds())),
+        Constraint::Percentage(40),
+        Constraint::Length(20),
+    ];
+    if all_tab_selected {
+        header.push("Server");
+        widths.push(Constraint::Percentage(20));
+    }
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().fg(theme.table_header)))
+        .block(
+            Block::default()
+                .title("Recent queries")
+                .borders(Borders::TOP),
+        )
+        .widths(&widths);
+    f.render_widget(table, chunks[1]);
+}
+
+pub fn draw_confirmation<B>(f: &mut Frame<B>, confirmation: &Confirmation, area: Rect)
+where
+    B: Backend,
+{
+    let lines = vec![
+        Spans::from(confirmation.message.as_str()),
+        Spans::default(),
+        Spans::from("Y/Enter: Yes  N/Esc: No"),
+    ];
+
+    let popup_area = centered_rect(70, lines.len() as u16 + 2, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title("Confirm")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Lists the actions of the actions menu with their keys
+pub fn draw_actions_menu<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let mut lines: Vec<Spans> = ServerAction::ALL
+        .iter()
+        .map(|action| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{}: ", action.key().to_ascii_uppercase()),
+                    Style::default().fg(app.theme.accent),
+                ),
+                Span::raw(action.title()),
+            ])
+        })
+        .collect();
+    lines.push(Spans::default());
+    lines.push(Spans::from("Esc: Close"));
+
+    let popup_area = centered_rect(40, lines.len() as u16 + 2, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(
+                "Actions on {}",
+                app.servers[app.selected_server_index].name
+            ))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Draws the latest output of the gravity update and, once it finished, its result with the
+/// new blocklist size
+pub fn draw_gravity_popup<B>(f: &mut Frame<B>, app: &App, area: Rect)
+where
+    B: Backend,
+{
+    let gravity = match &app.gravity {
+        Some(gravity) => gravity,
+        None => return,
+    };
+    let theme = &app.theme;
+    let popup_area = centered_rect(80, 20, area);
+    // Inside the borders, below the output are a blank line, the result and the keys
+    let output_height = usize::from(popup_area.height.saturating_sub(5));
+    let mut lines: Vec<Spans> = gravity.output
+        [gravity.output.len().saturating_sub(output_height)..]
+        .iter()
+        .map(|line| Spans::from(line.as_str()))
+        .collect();
+    lines.resize(output_height, Spans::default());
+    lines.push(Spans::default());
+    lines.push(match &gravity.result {
+        None => Spans::from("Updating\u{2026}"),
+        Some(Ok(())) => Spans::from(Span::styled(
+            format!(
+                "Finished, blocklist size: {}",
+                app.gravity_blocklist_size().unwrap_or("unknown")
+            ),
+            Style::default().fg(theme.good),
+        )),
+        Some(Err(error)) => Spans::from(Span::styled(
+            format!("Failed: {}", error),
+            Style::default().fg(theme.error),
+        )),
+    });
+    lines.push(Spans::from(match gravity.result {
+        None => "Esc: Hide, the update continues",
+        Some(_) => "Esc/Enter: Close",
+    }));
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!("Gravity update on {}", gravity.server))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Narrowest terminal the interface is drawn in
+const MIN_WIDTH: u16 = 60;
+/// Height of the row of overview boxes
+const OVERVIEW_HEIGHT: u16 = 9;
+/// Least height of the chart and of the statistics, the statistics are dropped before the
+/// chart is squeezed any further
+const MIN_PANEL_HEIGHT: u16 = 8;
+
+/// Least height `section` is drawn with
+fn section_min_height(section: Section) -> u16 {
+    match section {
+        Section::Overview => OVERVIEW_HEIGHT,
+        Section::Chart | Section::Statistics => MIN_PANEL_HEIGHT,
+    }
+}
+
+/// Replaces the interface while the terminal is smaller than `min_width` by `min_height`
+fn draw_too_small<B>(f: &mut Frame<B>, min_width: u16, min_height: u16)
+where
+    B: Backend,
+{
+    let size = f.size();
+    let message = format!(
+        "Terminal too small ({}x{}, need {}x{})",
+        size.width, size.height, min_width, min_height
+    );
+    let paragraph = Paragraph::new(message)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    let area = Rect {
+        y: size.y + size.height / 2,
+        height: size.height - size.height / 2,
+        ..size
+    };
+    f.render_widget(paragraph, area);
+}
+
+pub fn draw_ui<B>(f: &mut Frame<B>, app: &mut App)
+where
+    B: Backend,
+{
+    // Only what is drawn in this frame can be clicked
+    app.click_areas = ClickAreas::default();
+
+    // A second row of tabs for the groups
+    let tabs_height = if app.groups().is_empty() { 3 } else { 4 };
+    let size = f.size();
+    let sections = &app.layout.sections;
+    let full_height = tabs_height
+        + sections
+            .iter()
+            .map(|section| section_min_height(section.section))
+            .sum::<u16>();
+    // The statistics can be dropped unless they are all there is
+    let min_height = match sections.as_slice() {
+        [only] => tabs_height + section_min_height(only.section),
+        _ => {
+            tabs_height
+                + sections
+                    .iter()
+                    .filter(|section| section.section != Section::Statistics)
+                    .map(|section| section_min_height(section.section))
+                    .sum::<u16>()
+        }
+    };
+    if size.width < MIN_WIDTH || size.height < min_height {
+        draw_too_small(f, MIN_WIDTH, min_height);
+        return;
+    }
+    // Panels are dropped as the terminal gets shorter, the help bar first then the statistics
+    let show_statistics = size.height >= full_height;
+    let show_help_bar = show_statistics && size.height > full_height;
+
+    let mut constraints = Vec::new();
+    if show_help_bar {
+        constraints.push(Constraint::Length(1));
+    }
+    constraints.push(Constraint::Length(tabs_height));
+    constraints.push(Constraint::Min(0));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(size);
+    let (tabs_area, body) = (chunks[chunks.len() - 2], chunks[chunks.len() - 1]);
+
+    // Help bar
+    if show_help_bar {
+        draw_help_bar(f, app, chunks[0]);
+    }
+
+    // Pi Hole tabs
+    draw_tabs(f, app, tabs_area);
+
+    match app.view {
+        View::Overview if app.panel_maximized => draw_panel(f, app, app.focused_panel, body),
+        View::Overview => {
+            let sections: Vec<(Section, Option<u16>)> = app
+                .layout
+                .percentages()
+                .into_iter()
+                .filter(|(section, _)| show_statistics || *section != Section::Statistics)
+                .collect();
+            let constraints: Vec<Constraint> = sections
+                .iter()
+                .map(|(_, percentage)| match percentage {
+                    Some(percentage) => Constraint::Percentage(*percentage),
+                    None => Constraint::Length(OVERVIEW_HEIGHT),
+                })
+                .collect();
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(constraints)
+                .split(body);
+            for ((section, _), area) in sections.into_iter().zip(areas) {
+                match section {
+                    Section::Overview => draw_panel(f, app, Panel::Overview, area),
+                    // Queries or client activity chart
+                    Section::Chart => draw_panel(f, app, Panel::Chart, area),
+                    // Top domains
+                    Section::Statistics => draw_statistics(f, app, area),
+                }
+            }
+        }
+        // The other views take over the space below the tabs
+        View::QueryLog => draw_query_log(f, app, body),
+        View::Network => draw_network(f, app, body),
+        View::Lists => draw_domain_lists(f, app, body),
+        View::Adlists => draw_adlists(f, app, body),
+        View::LocalDns => draw_local_records(f, app, body),
+        View::LongTerm => draw_long_term(f, app, body),
+        View::Diagnostics => draw_diagnostics(f, app, body),
+        View::Alerts => draw_alerts(f, app, body),
+        View::Split => draw_split(f, app, body),
+        View::Client => draw_client(f, app, body),
+    }
+
+    // The alerts and diagnostics are about the updates themselves
+    if !matches!(app.view, View::Alerts | View::Diagnostics) {
+        draw_stale_overlay(f, app, body);
+    }
+
+    if let Some(editor) = &app.server_editor {
+        draw_server_editor(f, &app.theme, editor, f.size());
+    }
+    if let Some(prompt) = &app.input_prompt {
+        draw_input_prompt(f, &app.theme, prompt, f.size());
+    }
+    if app.selected_domain.is_some() {
+        draw_domain_popup(f, app, f.size());
+    }
+    if app.actions_menu_open {
+        draw_actions_menu(f, app, f.size());
+    }
+    if app
+        .gravity
+        .as_ref()
+        .is_some_and(|gravity| gravity.popup_open)
+    {
+        draw_gravity_popup(f, app, f.size());
+    }
+    if let Some(confirmation) = &app.confirmation {
+        draw_confirmation(f, confirmation, f.size());
+    }
 }
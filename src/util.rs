@@ -1,247 +1,6057 @@
+use crate::alerts::{Alerts, AlertsConfig};
+use crate::api_v5::PiHoleAPIConfigV5;
+use crate::api_v6::PiHoleAPIConfigV6;
+use crate::clipboard;
+use crate::custom_event::CustomEvent;
+use crate::discover::{self, DiscoveredServer};
+use crate::dns_probe::{self, DnsHealth, DnsProbeConfig};
+use crate::export;
+use crate::history::{self, History, HistoryRecord};
+use crate::layout::{LayoutConfig, Section};
+use crate::notify;
+use crate::numbers::{self, NumberFormat, Numbers};
+use crate::redact;
+use crate::theme::{Theme, ThemeConfig};
+use crate::thresholds::ThresholdsConfig;
+use crate::tunnel::SshTunnel;
+use crate::ui::{BAR_GAP, BAR_WIDTH};
+use crate::webhook::{WebhookConfig, Webhooks};
+use base64::engine::{general_purpose, Engine};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, Utc};
 use pi_hole_api::{
-    api_types::{OverTimeData, Summary, TopClients, TopItems},
-    AuthenticatedPiHoleAPI, PiHoleAPIConfig, PiHoleAPIConfigWithKey, UnauthenticatedPiHoleAPI,
+    api_types::{
+        CacheInfo, CustomListDomainDetails, ListModificationResponse, OverTimeData, Query,
+        QueryTypes, Status, Summary, SummaryRaw, TopClients, TopItems, Versions,
+    },
+    errors::APIError,
+    ftl_types::QueryStatus,
 };
-use serde::Deserialize;
-use serde_json;
-use std::collections::HashMap;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::path::PathBuf;
-use std::sync::mpsc::{self};
-use std::thread;
+use std::ffi::OsStr;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tui::layout::Rect;
+use tui::widgets::TableState;
 
+/// Number of most recent queries fetched for the query log
+const QUERY_LOG_LENGTH: u32 = 100;
+
+/// Number of most recent queries of one client or domain fetched for the Client view or the
+/// domain popup
+const FILTERED_QUERIES_LENGTH: u32 = 1000;
+
+/// Requests taking longer are logged as slow
+pub const SLOW_REQUEST: Duration = Duration::from_secs(2);
+
+/// Request timeout in milliseconds unless configured otherwise
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 10_000;
+
+/// Number of entries fetched for Top Queries, Top Ads and Top Clients unless configured otherwise
+const DEFAULT_TOP_ITEMS_COUNT: u32 = 25;
+
+/// Update delays in milliseconds stepped through with `<` and `>`
+const UPDATE_DELAYS: [u64; 9] = [
+    1_000, 2_000, 5_000, 10_000, 15_000, 30_000, 60_000, 120_000, 300_000,
+];
+
+/// Updates in a row without reaching a server before it is considered down unless configured
+/// otherwise
+const DEFAULT_OUTAGE_THRESHOLD: u32 = 3;
+
+/// Sizes of the top lists stepped through with `+` and `-`
+const TOP_ITEMS_COUNTS: [u32; 6] = [10, 25, 50, 100, 250, 500];
+
+/// How long after a timed disable should end before the server is asked for its status
+const DISABLE_EXPIRY_GRACE: Duration = Duration::from_secs(1);
+
+/// Number of query rates kept for the sparkline of the overview
+const QUERY_RATE_SAMPLES: usize = 60;
+
+/// Shortest time a query rate is measured over, the counts of quick refreshes barely change
+const MIN_QUERY_RATE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Longest delay between updates of the servers whose query log is followed
+const FOLLOW_UPDATE_DELAY: Duration = Duration::from_secs(2);
+
+/// Delay before retrying a failed update, doubled after every further failure up to the
+/// update delay
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Extensions of the supported configuration formats, JSON is preferred
+const CONFIG_FILE_EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
+
+/// How long a domain added to a list can be removed again with undo
+const UNDO_DURATION: Duration = Duration::from_secs(10);
+
+/// How often the network table is fetched to name clients when it is not shown
+const CLIENT_NAMES_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Service name of the API keys stored in the system keyring
+const KEYRING_SERVICE: &str = "pimon";
+
+/// Everything fetched from a server in one update, serialized for snapshots
+#[derive(Default, Serialize)]
 pub struct PiHoleData {
     pub summary: Option<Summary>,
     pub top_sources: Option<TopClients>,
+    /// Top clients by blocked queries, only fetched while Top Clients shows them
+    pub top_sources_blocked: Option<TopClients>,
     pub top_items: Option<TopItems>,
     pub over_time_data: Option<OverTimeData>,
+    pub queries: Option<Vec<Query>>,
+    pub query_types: Option<QueryTypes>,
+    /// Serialized as whole seconds
+    #[serde(serialize_with = "serialize_seconds")]
+    pub disable_timer: Option<Duration>,
+    pub cache_info: Option<CacheInfo>,
+    /// Queries by status group. `None` for servers whose API does not count them.
+    pub status_counts: Option<HashMap<StatusGroup, u64>>,
+    pub versions: Option<Versions>,
+    /// Domains of each list in the order of `DomainList::ALL`, only fetched for the Lists view
+    pub domain_lists: Option<Vec<Vec<CustomListDomainDetails>>>,
+    /// Only fetched for the Adlists view. `None` for servers whose API does not list them.
+    pub adlists: Option<Vec<Adlist>>,
+    /// Records of each type in the order of `LocalRecordType::ALL`, only fetched for the
+    /// Local DNS view
+    pub local_records: Option<Vec<Vec<LocalRecord>>>,
+    /// Only fetched while the client activity chart is shown
+    pub clients_over_time: Option<ClientsOverTime>,
+    /// Totals of each day, oldest first, only fetched for the Long term view
+    pub long_term: Option<Vec<DayTotals>>,
+    /// Whether `long_term` was read from the history as the long term database could not be
+    pub long_term_recorded: bool,
+    /// Most recent queries of the client of the Client view or the domain of the domain popup
+    pub filtered_queries: Option<Vec<Query>>,
+    /// Result of the DNS queries, only with `dns_probe` configured
+    pub dns_health: Option<DnsHealth>,
+    /// Load averages of the last 1, 5 and 15 minutes. `None` for servers whose API does not
+    /// report them.
+    pub load: Option<[f64; 3]>,
+    /// Devices of the network table, only fetched for the Network view
+    pub network_devices: Option<Vec<NetworkDevice>>,
+    /// Addresses handed out by the DHCP server of the Pi-hole, only fetched for the Network
+    /// view. `None` for servers whose API does not report them.
+    pub dhcp_leases: Option<Vec<DhcpLease>>,
+}
+
+fn serialize_seconds<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    duration
+        .map(|duration| duration.as_secs())
+        .serialize(serializer)
+}
+
+/// Queries of one day from the long term database
+#[derive(Clone, Serialize)]
+pub struct DayTotals {
+    /// Local midnight the day starts at
+    pub start: i64,
+    pub queries: u64,
+    pub blocked: u64,
+}
+
+impl DayTotals {
+    /// Days without queries, `days` of them starting at `from`
+    pub fn empty(from: i64, days: u32) -> Vec<Self> {
+        (0..i64::from(days))
+            .map(|day| DayTotals {
+                start: from + day * SECONDS_PER_DAY,
+                queries: 0,
+                blocked: 0,
+            })
+            .collect()
+    }
+}
+
+/// Local midnight starting the last `days` days including today
+pub fn long_term_from(days: u32) -> i64 {
+    let today = Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map_or_else(|| Utc::now().timestamp(), |midnight| midnight.timestamp());
+    today - i64::from(days.saturating_sub(1)) * SECONDS_PER_DAY
+}
+
+/// Device of the network table of a Pi-hole, as listed under Tools > Network of the web
+/// interface
+#[derive(Clone, Serialize)]
+pub struct NetworkDevice {
+    /// MAC address, or `ip-` followed by the address for devices seen through a router
+    pub hwaddr: String,
+    pub interface: String,
+    pub ips: Vec<String>,
+    /// Host names of its addresses, without empty ones
+    pub names: Vec<String>,
+    /// Unix timestamp of its last query, 0 if it has not made one
+    pub last_query: i64,
+    pub queries: u64,
+}
+
+/// Kinds of Local DNS records, as under Local DNS of the web interface
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LocalRecordType {
+    /// A and AAAA records pointing a domain to an address
+    Dns,
+    Cname,
+}
+
+impl LocalRecordType {
+    pub const ALL: [LocalRecordType; 2] = [LocalRecordType::Dns, LocalRecordType::Cname];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            LocalRecordType::Dns => "DNS records",
+            LocalRecordType::Cname => "CNAME records",
+        }
+    }
+
+    /// Heading of the column of `LocalRecord::target`
+    pub fn target_title(self) -> &'static str {
+        match self {
+            LocalRecordType::Dns => "IP address",
+            LocalRecordType::Cname => "Target",
+        }
+    }
+
+    /// Position within `LocalRecordType::ALL`
+    pub fn index(self) -> usize {
+        LocalRecordType::ALL
+            .iter()
+            .position(|record_type| *record_type == self)
+            .unwrap_or_default()
+    }
+}
+
+/// Local DNS record answered by the Pi-hole itself
+#[derive(Clone, Serialize)]
+pub struct LocalRecord {
+    pub domain: String,
+    /// IP address of a DNS record or domain a CNAME record points to
+    pub target: String,
+}
+
+/// List of domains to block that gravity downloads from `address`
+#[derive(Clone, Serialize)]
+pub struct Adlist {
+    pub address: String,
+    pub enabled: bool,
+    pub comment: String,
+    /// Groups the list applies to, kept to send them back when the list is changed
+    pub groups: Vec<u64>,
+    /// Domains found in the list by the last gravity update
+    pub domains: u64,
+    /// Unix timestamp of the last gravity update that downloaded the list, 0 if none has
+    pub date_updated: i64,
+}
+
+/// Address handed out by the DHCP server of a Pi-hole
+#[derive(Clone, Serialize)]
+pub struct DhcpLease {
+    /// Host name the client sent, empty if it sent none
+    pub name: String,
+    pub ip: String,
+    pub hwaddr: String,
+    /// Unix timestamp the lease expires at, 0 for a lease that does not expire
+    pub expires: i64,
+}
+
+/// Queries of each client in 10 minute periods
+#[derive(Clone, Default, Serialize)]
+pub struct ClientsOverTime {
+    /// Name of each client, or its address if it has no name
+    pub clients: Vec<String>,
+    /// Start of each period, oldest first, with the count of each client in the order of `clients`
+    pub over_time: Vec<(i64, Vec<u64>)>,
+}
+
+/// Custom white- and blacklists of a Pi-Hole
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DomainList {
+    Whitelist,
+    RegexWhitelist,
+    Blacklist,
+    RegexBlacklist,
+}
+
+impl DomainList {
+    pub const ALL: [DomainList; 4] = [
+        DomainList::Whitelist,
+        DomainList::RegexWhitelist,
+        DomainList::Blacklist,
+        DomainList::RegexBlacklist,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            DomainList::Whitelist => "Whitelist",
+            DomainList::RegexWhitelist => "Regex whitelist",
+            DomainList::Blacklist => "Blacklist",
+            DomainList::RegexBlacklist => "Regex blacklist",
+        }
+    }
+
+    /// Name of the list in the v5 API
+    fn api_name(self) -> &'static str {
+        match self {
+            DomainList::Whitelist => "white",
+            DomainList::RegexWhitelist => "white_regex",
+            DomainList::Blacklist => "black",
+            DomainList::RegexBlacklist => "black_regex",
+        }
+    }
+
+    /// Position within `DomainList::ALL`
+    pub fn index(self) -> usize {
+        DomainList::ALL
+            .iter()
+            .position(|list| *list == self)
+            .unwrap_or_default()
+    }
+}
+
+/// Pi-Hole API version spoken by a server
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum ApiVersion {
+    /// PHP API at /admin/api.php
+    #[default]
+    V5,
+    /// REST API at /api
+    V6,
+}
+
+impl TryFrom<u8> for ApiVersion {
+    type Error = String;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            5 => Ok(ApiVersion::V5),
+            6 => Ok(ApiVersion::V6),
+            _ => Err(format!(
+                "Unsupported API version {}, expected 5 or 6",
+                version
+            )),
+        }
+    }
+}
+
+impl From<ApiVersion> for u8 {
+    fn from(version: ApiVersion) -> Self {
+        match version {
+            ApiVersion::V5 => 5,
+            ApiVersion::V6 => 6,
+        }
+    }
+}
+
+/// Options of the HTTP client of a server, as opposed to those of its API
+pub struct HttpOptions {
+    /// Requests taking longer fail
+    pub request_timeout: Duration,
+    /// Sent with every request, `${VAR}` references must already be expanded
+    pub basic_auth: Option<BasicAuth>,
+    /// URL of the proxy all requests go through, replacing any from `HTTP_PROXY`,
+    /// `HTTPS_PROXY` or `ALL_PROXY`
+    pub proxy: Option<String>,
+    /// Accepts any certificate of an https:// host, e.g. a self-signed one
+    pub insecure_tls: bool,
+    /// Host name connected to at another address, as for an SSH tunnel
+    pub resolve: Option<(String, SocketAddr)>,
+}
+
+impl HttpOptions {
+    fn client(&self) -> Result<Client, String> {
+        let mut headers = HeaderMap::new();
+        if let Some(basic_auth) = &self.basic_auth {
+            let credentials = general_purpose::STANDARD
+                .encode(format!("{}:{}", basic_auth.username, basic_auth.password));
+            // Base64 is always a valid header value
+            let mut value = HeaderValue::from_str(&format!("Basic {}", credentials))
+                .map_err(|error| format!("Invalid basic_auth: {}", error))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        let mut builder = Client::builder()
+            .timeout(self.request_timeout)
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.insecure_tls);
+        if let Some((domain, address)) = &self.resolve {
+            builder = builder.resolve(domain, *address);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(http_proxy(proxy)?);
+        }
+        builder
+            .build()
+            .map_err(|error| format!("Could not create the HTTP client: {}", error))
+    }
+}
+
+/// Parses the URL of an HTTP or HTTPS proxy. SOCKS needs reqwest's `socks` feature, which
+/// pimon is not built with.
+fn http_proxy(proxy: &str) -> Result<Proxy, String> {
+    let url = Url::parse(proxy).map_err(|error| format!("Invalid proxy {}: {}", proxy, error))?;
+    match url.scheme() {
+        "http" | "https" => {
+            Proxy::all(url).map_err(|error| format!("Invalid proxy {}: {}", proxy, error))
+        }
+        "socks5" | "socks5h" => Err(format!(
+            "SOCKS proxies are not supported by this build of pimon, {} must be an http:// proxy",
+            proxy
+        )),
+        _ => Err(format!(
+            "Invalid proxy {}, it must begin with http:// or https://",
+            proxy
+        )),
+    }
+}
+
+pub enum PiHoleConfigImplementation {
+    V5(PiHoleAPIConfigV5),
+    V6(PiHoleAPIConfigV6),
+}
+
+impl PiHoleConfigImplementation {
+    /// Creates the API client for `host`, which must be an http:// or https:// URL
+    pub fn new(
+        host: String,
+        api_key: Option<String>,
+        api_version: ApiVersion,
+        http_options: &HttpOptions,
+    ) -> Result<Self, String> {
+        let url = Url::parse(&host).map_err(|error| format!("Invalid host {}: {}", host, error))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!(
+                "Invalid host {}, it must begin with http:// or https://",
+                host
+            ));
+        }
+        let client = http_options.client()?;
+        Ok(match api_version {
+            ApiVersion::V5 => {
+                PiHoleConfigImplementation::V5(PiHoleAPIConfigV5::new(host, api_key, client))
+            }
+            ApiVersion::V6 => {
+                PiHoleConfigImplementation::V6(PiHoleAPIConfigV6::new(url, api_key, client))
+            }
+        })
+    }
+
+    /// Whether endpoints requiring authentication can be used
+    pub fn is_authenticated(&self) -> bool {
+        match self {
+            Self::V5(config) => config.has_api_key(),
+            // The server decides, a v6 Pi-Hole without a password allows everything
+            Self::V6(_) => true,
+        }
+    }
+
+    pub async fn get_summary_raw(&self) -> Result<SummaryRaw, APIError> {
+        match self {
+            Self::V5(config) => config.get_summary_raw().await,
+            Self::V6(config) => config.get_summary_raw().await,
+        }
+    }
+
+    pub async fn get_summary(&self) -> Result<Summary, APIError> {
+        match self {
+            Self::V5(config) => config.get_summary().await,
+            Self::V6(config) => config.get_summary().await,
+        }
+    }
+
+    pub async fn get_over_time_data_10_mins(&self) -> Result<OverTimeData, APIError> {
+        match self {
+            Self::V5(config) => config.get_over_time_data_10_mins().await,
+            Self::V6(config) => config.get_over_time_data_10_mins().await,
+        }
+    }
+
+    pub async fn get_top_items(&self, count: u32) -> Result<TopItems, APIError> {
+        match self {
+            Self::V5(config) => config.get_top_items(count).await,
+            Self::V6(config) => config.get_top_items(count).await,
+        }
+    }
+
+    pub async fn get_top_clients(&self, count: u32) -> Result<TopClients, APIError> {
+        match self {
+            Self::V5(config) => config.get_top_clients(count).await,
+            Self::V6(config) => config.get_top_clients(count, false).await,
+        }
+    }
+
+    /// Top clients by blocked queries instead of all queries
+    pub async fn get_top_clients_blocked(&self, count: u32) -> Result<TopClients, APIError> {
+        match self {
+            Self::V5(config) => config.get_top_clients_blocked(count).await,
+            Self::V6(config) => config.get_top_clients(count, true).await,
+        }
+    }
+
+    pub async fn get_query_types(&self) -> Result<QueryTypes, APIError> {
+        match self {
+            Self::V5(config) => config.get_query_types().await,
+            Self::V6(config) => config.get_query_types().await,
+        }
+    }
+
+    pub async fn get_all_queries(&self, count: u32) -> Result<Vec<Query>, APIError> {
+        match self {
+            Self::V5(config) => config.get_all_queries(count).await,
+            Self::V6(config) => config.get_all_queries(count).await,
+        }
+    }
+
+    /// Most recent queries of one client or domain, newest last
+    pub async fn get_filtered_queries(
+        &self,
+        filter: &QueryFilter,
+        count: u32,
+    ) -> Result<Vec<Query>, APIError> {
+        match self {
+            Self::V5(config) => config.get_filtered_queries(filter, count).await,
+            Self::V6(config) => config.get_filtered_queries(filter, count).await,
+        }
+    }
+
+    pub async fn get_versions(&self) -> Result<Versions, APIError> {
+        match self {
+            Self::V5(config) => config.get_versions().await,
+            Self::V6(config) => config.get_versions().await,
+        }
+    }
+
+    /// Queries by status group, the v5 API does not count them
+    pub async fn get_status_counts(&self) -> Result<Option<HashMap<StatusGroup, u64>>, APIError> {
+        match self {
+            Self::V5(_) => Ok(None),
+            Self::V6(config) => config.get_status_counts().await.map(Some),
+        }
+    }
+
+    /// Load averages of the server, the v5 API does not report them
+    pub async fn get_system_load(&self) -> Result<Option<[f64; 3]>, APIError> {
+        match self {
+            Self::V5(_) => Ok(None),
+            Self::V6(config) => config.get_system_load().await.map(Some),
+        }
+    }
+
+    pub async fn get_cache_info(&self) -> Result<CacheInfo, APIError> {
+        match self {
+            Self::V5(config) => config.get_cache_info().await,
+            Self::V6(config) => config.get_cache_info().await,
+        }
+    }
+
+    pub async fn get_clients_over_time(&self) -> Result<ClientsOverTime, APIError> {
+        match self {
+            Self::V5(config) => config.get_clients_over_time().await,
+            Self::V6(config) => config.get_clients_over_time().await,
+        }
+    }
+
+    pub async fn get_network_devices(&self) -> Result<Vec<NetworkDevice>, APIError> {
+        match self {
+            Self::V5(config) => config.get_network_devices().await,
+            Self::V6(config) => config.get_network_devices().await,
+        }
+    }
+
+    /// Active DHCP leases, the v5 API does not expose them
+    pub async fn get_dhcp_leases(&self) -> Result<Option<Vec<DhcpLease>>, APIError> {
+        match self {
+            Self::V5(_) => Ok(None),
+            Self::V6(config) => config.get_dhcp_leases().await.map(Some),
+        }
+    }
+
+    /// Totals of the last `days` days including today, from the long term database
+    pub async fn get_long_term(&self, days: u32) -> Result<Vec<DayTotals>, APIError> {
+        let from = long_term_from(days);
+        let until = Utc::now().timestamp();
+        // Periods of an hour or less so they fall within one day
+        let periods = match self {
+            Self::V5(config) => config.get_database_history(from, until).await?,
+            Self::V6(config) => config.get_database_history(from, until).await?,
+        };
+        let mut totals = DayTotals::empty(from, days);
+        for (timestamp, queries, blocked) in periods {
+            let day = (timestamp - from).div_euclid(SECONDS_PER_DAY);
+            if let Some(totals) = usize::try_from(day)
+                .ok()
+                .and_then(|day| totals.get_mut(day))
+            {
+                totals.queries += queries;
+                totals.blocked += blocked;
+            }
+        }
+        Ok(totals)
+    }
+
+    pub async fn get_list(
+        &self,
+        list: DomainList,
+    ) -> Result<Vec<CustomListDomainDetails>, APIError> {
+        match self {
+            Self::V5(config) => config.get_list(list.api_name()).await,
+            Self::V6(config) => config.get_list(list.api_name()).await,
+        }
+    }
+
+    pub async fn list_add(
+        &self,
+        domain: &str,
+        list: DomainList,
+    ) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(config) => config.list_add(domain, list.api_name()).await,
+            Self::V6(config) => config.list_add(domain, list.api_name()).await,
+        }
+    }
+
+    pub async fn list_remove(
+        &self,
+        domain: &str,
+        list: DomainList,
+    ) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(config) => config.list_remove(domain, list.api_name()).await,
+            Self::V6(config) => config.list_remove(domain, list.api_name()).await,
+        }
+    }
+
+    pub async fn get_local_records(
+        &self,
+        record_type: LocalRecordType,
+    ) -> Result<Vec<LocalRecord>, APIError> {
+        match self {
+            Self::V5(config) => config.get_local_records(record_type).await,
+            Self::V6(config) => config.get_local_records(record_type).await,
+        }
+    }
+
+    pub async fn local_record_add(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(config) => config.local_record_add(record_type, record).await,
+            Self::V6(config) => config.local_record_add(record_type, record).await,
+        }
+    }
+
+    pub async fn local_record_remove(
+        &self,
+        record_type: LocalRecordType,
+        record: &LocalRecord,
+    ) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(config) => config.local_record_remove(record_type, record).await,
+            Self::V6(config) => config.local_record_remove(record_type, record).await,
+        }
+    }
+
+    /// Whether the adlists can be listed, changed and downloaded again by a gravity update.
+    /// Otherwise `get_adlists` is always `None`.
+    pub fn manages_adlists(&self) -> bool {
+        matches!(self, Self::V6(_))
+    }
+
+    /// Adlists of the server, the v5 API does not expose them
+    pub async fn get_adlists(&self) -> Result<Option<Vec<Adlist>>, APIError> {
+        match self {
+            Self::V5(_) => Ok(None),
+            Self::V6(config) => config.get_adlists().await.map(Some),
+        }
+    }
+
+    pub async fn adlist_add(&self, address: &str) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(_) => Ok(adlists_unsupported()),
+            Self::V6(config) => config.adlist_add(address).await,
+        }
+    }
+
+    pub async fn adlist_set_enabled(
+        &self,
+        adlist: &Adlist,
+        enabled: bool,
+    ) -> Result<ListModificationResponse, APIError> {
+        match self {
+            Self::V5(_) => Ok(adlists_unsupported()),
+            Self::V6(config) => config.adlist_set_enabled(adlist, enabled).await,
+        }
+    }
+
+    /// Runs gravity on a server that `manages_adlists`, passing each line of its output to
+    /// `on_line`
+    pub async fn update_gravity(&self, on_line: impl FnMut(String)) -> Result<(), APIError> {
+        match self {
+            Self::V5(_) => Ok(()),
+            Self::V6(config) => config.update_gravity(on_line).await,
+        }
+    }
+
+    /// Whether the server can `run_action`
+    pub fn runs_actions(&self) -> bool {
+        matches!(self, Self::V6(_))
+    }
+
+    /// Runs `action` on a server that `runs_actions`
+    pub async fn run_action(&self, action: ServerAction) -> Result<(), APIError> {
+        match self {
+            Self::V5(_) => Ok(()),
+            Self::V6(config) => config.run_action(action).await,
+        }
+    }
+
+    pub async fn enable(&self) -> Result<Status, APIError> {
+        match self {
+            Self::V5(config) => config.enable().await,
+            Self::V6(config) => config.enable().await,
+        }
+    }
+
+    pub async fn disable(&self, seconds: u64) -> Result<Status, APIError> {
+        match self {
+            Self::V5(config) => config.disable(seconds).await,
+            Self::V6(config) => config.disable(seconds).await,
+        }
+    }
+
+    /// Whether `get_disable_timer` reflects the server, rather than always being `None`
+    pub fn reports_disable_timer(&self) -> bool {
+        matches!(self, Self::V6(_))
+    }
+
+    /// Time until blocking is re-enabled, the v5 API does not expose it
+    pub async fn get_disable_timer(&self) -> Result<Option<Duration>, APIError> {
+        match self {
+            Self::V5(_) => Ok(None),
+            Self::V6(config) => config.get_disable_timer().await,
+        }
+    }
+
+    pub async fn logout(&self) {
+        match self {
+            Self::V5(_) => {}
+            Self::V6(config) => config.logout().await,
+        }
+    }
+}
+
+/// Enable or disable of a server sent in the background
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlockingChange {
+    Enable,
+    /// For this many seconds, `0` for permanently
+    Disable(u64),
+}
+
+/// Change to the lists of a server made in the background by its worker
+#[derive(Clone)]
+pub enum ServerChange {
+    AddDomain {
+        domain: String,
+        list: DomainList,
+    },
+    RemoveDomain {
+        domain: String,
+        list: DomainList,
+    },
+    AddAdlist {
+        address: String,
+    },
+    SetAdlistEnabled {
+        adlist: Adlist,
+        enabled: bool,
+    },
+    AddLocalRecord {
+        record_type: LocalRecordType,
+        record: LocalRecord,
+    },
+    RemoveLocalRecord {
+        record_type: LocalRecordType,
+        record: LocalRecord,
+    },
+}
+
+pub struct PiHoleServer {
+    pub name: String,
+    /// API key after resolving `api_key_env`, `api_key_keyring` and `${VAR}` references
+    pub api_key: Option<String>,
+    /// Configuration as written in the config file, used when editing or saving the server
+    pub config: PiHoleServerConfig,
+    pub api_config: Arc<PiHoleConfigImplementation>,
+    /// When the last update finished, `None` before the first one
+    pub last_update: Option<Instant>,
+    pub update_delay: Duration,
+    pub last_data: PiHoleData,
+    /// When blocking is expected to be re-enabled after a timed disable
+    pub blocking_resumes_at: Option<Instant>,
+    /// Whether updates include the white- and blacklists
+    pub fetch_domain_lists: bool,
+    /// Whether updates include the adlists
+    pub fetch_adlists: bool,
+    /// Whether updates include the Local DNS records
+    pub fetch_local_records: bool,
+    /// Whether updates include the queries of each client over time
+    pub fetch_clients_over_time: bool,
+    /// Whether updates include the top clients by blocked queries
+    pub fetch_top_clients_blocked: bool,
+    /// Whether updates include the network table
+    pub fetch_network_devices: bool,
+    /// Whether the query log is followed, updating at least every `FOLLOW_UPDATE_DELAY`
+    pub follow_queries: bool,
+    /// Whether client addresses are looked up in the network table every
+    /// `CLIENT_NAMES_INTERVAL`
+    pub resolve_client_names: bool,
+    /// When the network table was last fetched for `client_names`
+    client_names_fetched_at: Option<Instant>,
+    /// Host name of each client address, from the network table and from clients the server
+    /// lists with their name
+    pub client_names: HashMap<String, String>,
+    /// Number of entries fetched for each top list
+    pub top_items_count: u32,
+    /// Number of days of daily totals included in updates, only for the Long term view
+    pub fetch_long_term_days: Option<u32>,
+    /// History the daily totals are read from should the long term database not be readable
+    pub long_term_history: Option<History>,
+    /// Client or domain whose queries are included in updates, only for the Client view and
+    /// the domain popup
+    pub fetch_filtered_queries: Option<QueryFilter>,
+    /// When the summary was last appended to the history
+    pub last_history_record: Option<Instant>,
+    /// When the records older than `history::RETENTION_DAYS` were last removed
+    history_pruned_at: Option<Instant>,
+    /// Forward the requests go through, closed with the server
+    _ssh_tunnel: Option<SshTunnel>,
+    /// Host name and domains queried on the server's DNS port with each update
+    dns_probe: Option<(String, DnsProbeConfig)>,
+    /// How long the last update took from start to finish
+    pub last_update_duration: Option<Duration>,
+    /// Number of requests of the last update which failed
+    pub last_update_failures: usize,
+    /// Fastest successful request of the last update, the closest to the round trip time
+    pub latency: Option<Duration>,
+    /// Summary of the update before the last one with a summary, the counts of the overview
+    /// show the change since
+    pub previous_summary: Option<Summary>,
+    /// Queries and cached queries today as of the last summary and when it arrived, the
+    /// start of the next rate
+    query_count: Option<(u64, u64, Instant)>,
+    /// Queries per minute between consecutive summaries, oldest first
+    pub query_rates: VecDeque<f64>,
+    /// Queries answered from the cache and all queries between consecutive summaries, oldest
+    /// first
+    pub cache_samples: VecDeque<(u64, u64)>,
+    /// Number of updates in a row with failed requests, retried sooner than the update delay
+    pub failed_updates: u32,
+    /// When the first of the updates in a row without a summary finished
+    pub unreachable_since: Option<Instant>,
+    /// When blocking was first reported as not enabled, `None` while it is enabled
+    pub disabled_since: Option<Instant>,
+    /// Number of updates in a row without a summary
+    pub unreachable_updates: u32,
+    /// When the current outage began, set once the server was unreachable for the outage
+    /// threshold of updates in a row
+    pub outage_since: Option<DateTime<Local>>,
+    /// Total length of the outages which have ended since start
+    pub past_downtime: Duration,
+    /// Latency and errors of each endpoint requested so far, in the order first requested
+    pub endpoint_statistics: Vec<EndpointStatistics>,
+    /// Started with the first request, see `run_worker`
+    worker: Option<Worker>,
+    /// Number given to the last update sent to the worker
+    last_update_id: u64,
+    /// Update the worker is making, `None` while waiting for the next to be due
+    update_in_flight: Option<u64>,
+    /// Enable or disable sent to the worker and not answered yet
+    blocking_change: Option<BlockingChange>,
+    /// Told whenever the worker has a response, without it responses wait for the next tick
+    pub data_arrived: Option<mpsc::Sender<CustomEvent>>,
 }
 
-pub enum PiHoleConfigImplementation {
-    Default(PiHoleAPIConfig),
-    WithKey(PiHoleAPIConfigWithKey),
-}
+impl PiHoleServer {
+    /// Creates a server from its configuration, `default_update_delay` and
+    /// `default_request_timeout` apply unless the server overrides them
+    pub fn new(
+        config: PiHoleServerConfig,
+        default_update_delay: u64,
+        default_request_timeout: u64,
+    ) -> Result<Self, String> {
+        let api_key = config.resolve_api_key()?;
+        let update_delay =
+            Duration::from_millis(config.update_delay.unwrap_or(default_update_delay));
+        let ssh_tunnel = config
+            .ssh_tunnel
+            .as_ref()
+            .map(|ssh_tunnel| SshTunnel::open(ssh_tunnel, &config.host, config.insecure_tls))
+            .transpose()
+            .map_err(|error| format!("{} (server {})", error, config.name))?;
+        let http_options = HttpOptions {
+            request_timeout: Duration::from_millis(
+                config.request_timeout_ms.unwrap_or(default_request_timeout),
+            ),
+            basic_auth: config.resolve_basic_auth()?,
+            proxy: config.proxy.clone(),
+            insecure_tls: config.insecure_tls,
+            resolve: ssh_tunnel
+                .as_ref()
+                .and_then(|ssh_tunnel| ssh_tunnel.resolve.clone()),
+        };
+        let host = match &ssh_tunnel {
+            Some(ssh_tunnel) => ssh_tunnel.url.to_string(),
+            None => config.host.clone(),
+        };
+        let dns_probe = config
+            .dns_probe
+            .as_ref()
+            .map(|dns_probe| {
+                // Queried directly, the DNS port is not forwarded by an SSH tunnel
+                Url::parse(&config.host)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .map(|host| (host, dns_probe.clone()))
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid host {} of server {}, the DNS probe needs a host name",
+                            config.host, config.name
+                        )
+                    })
+            })
+            .transpose()?;
+        let api_config = Arc::new(
+            PiHoleConfigImplementation::new(
+                config.base_url(&host),
+                api_key.clone(),
+                config.api_version,
+                &http_options,
+            )
+            .map_err(|error| format!("{} (server {})", error, config.name))?,
+        );
+        Ok(PiHoleServer {
+            name: config.name.clone(),
+            api_key,
+            config,
+            api_config,
+            update_delay,
+            last_update: None,
+            last_data: PiHoleData::default(),
+            blocking_resumes_at: None,
+            fetch_domain_lists: false,
+            fetch_adlists: false,
+            fetch_local_records: false,
+            fetch_clients_over_time: false,
+            fetch_network_devices: false,
+            follow_queries: false,
+            resolve_client_names: false,
+            client_names_fetched_at: None,
+            client_names: HashMap::new(),
+            fetch_top_clients_blocked: false,
+            top_items_count: DEFAULT_TOP_ITEMS_COUNT,
+            fetch_long_term_days: None,
+            long_term_history: None,
+            fetch_filtered_queries: None,
+            last_history_record: None,
+            history_pruned_at: None,
+            _ssh_tunnel: ssh_tunnel,
+            dns_probe,
+            last_update_duration: None,
+            last_update_failures: 0,
+            latency: None,
+            previous_summary: None,
+            query_count: None,
+            query_rates: VecDeque::new(),
+            cache_samples: VecDeque::new(),
+            failed_updates: 0,
+            unreachable_since: None,
+            disabled_since: None,
+            unreachable_updates: 0,
+            outage_since: None,
+            past_downtime: Duration::ZERO,
+            endpoint_statistics: Vec::new(),
+            worker: None,
+            last_update_id: 0,
+            update_in_flight: None,
+            blocking_change: None,
+            data_arrived: None,
+        })
+    }
+    /// Starts a new update, discarding one already in flight which may predate a change
+    pub fn refresh(&mut self) {
+        self.update_in_flight = None;
+        self.run_background_update();
+    }
+
+    pub fn run_background_update(&mut self) {
+        if self.update_in_flight.is_none() {
+            self.last_update_id += 1;
+            let names_due = self.resolve_client_names
+                && self
+                    .client_names_fetched_at
+                    .is_none_or(|fetched_at| fetched_at.elapsed() >= CLIENT_NAMES_INTERVAL);
+            if names_due {
+                self.client_names_fetched_at = Some(Instant::now());
+            }
+            let command = WorkerCommand::Update {
+                id: self.last_update_id,
+                optional: OptionalData {
+                    domain_lists: self.fetch_domain_lists,
+                    adlists: self.fetch_adlists,
+                    local_records: self.fetch_local_records,
+                    clients_over_time: self.fetch_clients_over_time,
+                    network_devices: self.fetch_network_devices || names_due,
+                    dhcp_leases: self.fetch_network_devices && !self.config.hide_dhcp_leases,
+                    top_clients_blocked: self.fetch_top_clients_blocked,
+                    long_term_days: self.fetch_long_term_days,
+                    long_term_history: self.long_term_history.clone(),
+                    filtered_queries: self.fetch_filtered_queries.clone(),
+                },
+                top_items_count: self.top_items_count,
+                dns_probe: self.dns_probe.clone(),
+            };
+            self.send_command(command);
+            self.update_in_flight = Some(self.last_update_id);
+        }
+    }
+
+    /// Sends `command` to the worker, starting it first if needed. Should the worker have
+    /// stopped the command is lost and that shows when checking for its response.
+    fn send_command(&mut self, command: WorkerCommand) {
+        let api_config = &self.api_config;
+        let name = &self.name;
+        let data_arrived = &self.data_arrived;
+        let worker = self.worker.get_or_insert_with(|| {
+            let (commands, command_receiver) = tokio::sync::mpsc::unbounded_channel();
+            let (update_sender, updates) = mpsc::channel();
+            let (blocking_change_sender, blocking_changes) = mpsc::channel();
+            let (change_sender, changes) = mpsc::channel();
+            tokio::spawn(run_worker(
+                Arc::clone(api_config),
+                name.clone(),
+                command_receiver,
+                WorkerResponses {
+                    updates: update_sender,
+                    blocking_changes: blocking_change_sender,
+                    changes: change_sender,
+                },
+                data_arrived.clone(),
+            ));
+            Worker {
+                commands,
+                updates,
+                blocking_changes,
+                changes,
+            }
+        });
+        let _ = worker.commands.send(command);
+    }
+
+    /// Follows the server's disable timer where available. Otherwise the time tracked from
+    /// the last disable is kept until the server reports blocking is enabled again.
+    fn update_blocking_resumes_at(&mut self, pi_hole_data: &PiHoleData) {
+        if let Some(disable_timer) = pi_hole_data.disable_timer {
+            self.blocking_resumes_at = Some(Instant::now() + disable_timer);
+        } else if let Some(summary) = &pi_hole_data.summary {
+            if summary.status == "enabled" || self.api_config.reports_disable_timer() {
+                self.blocking_resumes_at = None;
+            }
+        }
+    }
+
+    /// Keeps the start of a disable across updates, an unreachable server is assumed to be
+    /// in the state last reported
+    fn update_disabled_since(&mut self, pi_hole_data: &PiHoleData) {
+        match &pi_hole_data.summary {
+            Some(summary) if summary.status == "enabled" => self.disabled_since = None,
+            Some(_) if self.disabled_since.is_none() => self.disabled_since = Some(Instant::now()),
+            _ => {}
+        }
+    }
+
+    /// Sends `change` without waiting for the server, its outcome is taken by
+    /// `check_blocking_change`
+    fn change_blocking(&mut self, change: BlockingChange) {
+        self.send_command(WorkerCommand::ChangeBlocking(change));
+        self.blocking_change = Some(change);
+    }
+
+    /// Sends `change` without waiting for the server, its outcome is taken by `check_changes`
+    pub fn make_change(&mut self, change: ServerChange) {
+        self.send_command(WorkerCommand::Change(change));
+    }
+
+    /// Takes the changes answered since the last call with their outcome, refreshing to show
+    /// them
+    fn check_changes(&mut self) -> Vec<(ServerChange, ChangeResult)> {
+        let answered: Vec<(ServerChange, ChangeResult)> = match &self.worker {
+            Some(worker) => worker.changes.try_iter().collect(),
+            None => return Vec::new(),
+        };
+        if !answered.is_empty() {
+            self.refresh();
+        }
+        answered
+    }
+
+    /// Ends the API session through the worker, which then stops. The receiver is told once
+    /// it has, `None` without a worker as no session was started.
+    fn logout(mut self) -> Option<mpsc::Receiver<()>> {
+        self.worker.as_ref()?;
+        let (sender, logged_out) = mpsc::channel();
+        let ssh_tunnel = self._ssh_tunnel.take();
+        self.send_command(WorkerCommand::Logout {
+            logged_out: sender,
+            ssh_tunnel,
+        });
+        Some(logged_out)
+    }
+
+    /// Enable or disable sent and not answered yet
+    pub fn pending_blocking_change(&self) -> Option<BlockingChange> {
+        self.blocking_change
+    }
+
+    /// Takes the response to an enable or disable once it arrives, returning the status
+    /// message describing it
+    fn check_blocking_change(&mut self) -> Option<Result<String, String>> {
+        self.blocking_change?;
+        let response = self
+            .worker
+            .as_ref()
+            .map(|worker| worker.blocking_changes.try_recv());
+        let result = match response {
+            Some(Ok(result)) => result.map_err(|error| describe_api_error(&error)),
+            Some(Err(TryRecvError::Empty)) => return None,
+            Some(Err(TryRecvError::Disconnected)) | None => Err("No response".to_string()),
+        };
+        let change = self.blocking_change.take()?;
+        let message = match (change, result) {
+            (BlockingChange::Enable, Ok(_)) => {
+                self.blocking_resumes_at = None;
+                Ok(format!("Enabled blocking on {}", self.name))
+            }
+            (BlockingChange::Disable(seconds), Ok(_)) => {
+                let duration = Duration::from_secs(seconds);
+                self.blocking_resumes_at = match seconds {
+                    0 => None,
+                    _ => Some(Instant::now() + duration),
+                };
+                Ok(match seconds {
+                    0 => format!("Disabled blocking on {}", self.name),
+                    _ => format!(
+                        "Disabled blocking on {} for {}",
+                        self.name,
+                        format_duration(duration)
+                    ),
+                })
+            }
+            (BlockingChange::Enable, Err(error)) => Err(format!(
+                "Failed to enable blocking on {}: {}",
+                self.name, error
+            )),
+            (BlockingChange::Disable(_), Err(error)) => Err(format!(
+                "Failed to disable blocking on {}: {}",
+                self.name, error
+            )),
+        };
+        self.refresh();
+        Some(message)
+    }
+
+    /// How current the data shown for the server is
+    pub fn freshness(&self) -> Freshness {
+        match (self.last_update, &self.last_data.summary) {
+            (None, _) => Freshness::Pending,
+            (Some(_), None) if self.outage_since.is_some() => Freshness::Failed,
+            (Some(_), None) => Freshness::Unreachable,
+            (Some(last_update), Some(_)) if last_update.elapsed() > self.update_delay * 2 => {
+                Freshness::Stale
+            }
+            (Some(_), Some(_)) if self.last_update_failures > 0 => Freshness::Partial,
+            (Some(_), Some(_)) => Freshness::Fresh,
+        }
+    }
+
+    /// Takes the data of a finished update, returning whether there was new data. The server
+    /// is down once `outage_threshold` updates in a row could not reach it.
+    pub fn check_background_update(&mut self, outage_threshold: u32) -> bool {
+        let in_flight = match (self.update_in_flight, &self.worker) {
+            (Some(in_flight), Some(worker)) => Some((in_flight, worker)),
+            _ => None,
+        };
+        if let Some((in_flight, worker)) = in_flight {
+            match worker.updates.try_recv() {
+                // Finished before it was replaced by a newer update
+                Ok((id, _)) if id != in_flight => {}
+                Ok((_, update)) => {
+                    self.update_blocking_resumes_at(&update.data);
+                    self.update_disabled_since(&update.data);
+                    self.last_update_failures = update
+                        .timings
+                        .iter()
+                        .filter(|timing| timing.error.is_some())
+                        .count();
+                    // Requests answered without contacting the server, such as the disable
+                    // timer of v5, would pass for a latency of an unreachable server
+                    self.latency = update
+                        .timings
+                        .iter()
+                        .filter(|timing| timing.error.is_none())
+                        .map(|timing| timing.duration)
+                        .min()
+                        .filter(|_| update.data.summary.is_some());
+                    self.record_timings(update.timings);
+                    self.learn_client_names(&update.data);
+                    self.record_query_rate(&update.data);
+                    if update.data.summary.is_some() && self.last_data.summary.is_some() {
+                        self.previous_summary = self.last_data.summary.take();
+                    }
+                    self.last_data = update.data;
+                    self.last_update_duration = Some(update.duration);
+                    let failed = self.last_data.summary.is_none() || self.last_update_failures > 0;
+                    self.finish_update(failed);
+                    self.set_reachable(self.last_data.summary.is_some(), outage_threshold);
+                    return true;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    log::error!("The worker of {} stopped", self.name);
+                    self.worker = None;
+                    self.last_update_duration = None;
+                    self.latency = None;
+                    self.finish_update(true);
+                    self.set_reachable(false, outage_threshold);
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        false
+    }
+
+    /// Adds the rate of queries and the share answered from the cache since the summary the
+    /// last rate ended with. Counts lower than those, as after the reset at midnight, only
+    /// start the next rate.
+    fn record_query_rate(&mut self, data: &PiHoleData) {
+        let summary = match &data.summary {
+            Some(summary) => summary,
+            None => return,
+        };
+        let count = parse_count(&summary.dns_queries_today);
+        let cached = parse_count(&summary.queries_cached);
+        let now = Instant::now();
+        if let Some((previous, previous_cached, counted_at)) = self.query_count {
+            let period = now.duration_since(counted_at);
+            if period < MIN_QUERY_RATE_PERIOD {
+                return;
+            }
+            let minutes = period.as_secs_f64() / 60.0;
+            if count >= previous && cached >= previous_cached {
+                if self.query_rates.len() == QUERY_RATE_SAMPLES {
+                    self.query_rates.pop_front();
+                }
+                self.query_rates
+                    .push_back((count - previous) as f64 / minutes);
+                if count > previous {
+                    if self.cache_samples.len() == QUERY_RATE_SAMPLES {
+                        self.cache_samples.pop_front();
+                    }
+                    self.cache_samples
+                        .push_back((cached - previous_cached, count - previous));
+                }
+            }
+        }
+        self.query_count = Some((count, cached, now));
+    }
+
+    /// Remembers the host names of client addresses found in `data`
+    fn learn_client_names(&mut self, data: &PiHoleData) {
+        let devices = data.network_devices.iter().flatten();
+        for device in devices {
+            for (ip, name) in device.ips.iter().zip(&device.names) {
+                if !name.is_empty() {
+                    self.client_names.insert(ip.clone(), name.clone());
+                }
+            }
+        }
+        let top_clients = data
+            .top_sources
+            .iter()
+            .chain(&data.top_sources_blocked)
+            .flat_map(|top_sources| top_sources.top_sources.keys());
+        let queries = data.queries.iter().flatten().map(|query| &query.client);
+        for client in top_clients.chain(queries) {
+            if let (Some(name), address) = split_client(client) {
+                self.client_names
+                    .insert(address.to_string(), name.to_string());
+            }
+        }
+    }
+
+    fn finish_update(&mut self, failed: bool) {
+        self.last_update = Some(Instant::now());
+        self.update_in_flight = None;
+        self.failed_updates = if failed {
+            self.failed_updates.saturating_add(1)
+        } else {
+            0
+        };
+    }
+
+    fn set_reachable(&mut self, reachable: bool, outage_threshold: u32) {
+        if reachable {
+            if let Some(outage_since) = self.outage_since.take() {
+                let downtime = elapsed_since(outage_since);
+                self.past_downtime += downtime;
+                log::info!(
+                    "{} is reachable again after {}",
+                    self.name,
+                    format_duration(downtime)
+                );
+            }
+            self.unreachable_since = None;
+            self.unreachable_updates = 0;
+            return;
+        }
+        if self.unreachable_since.is_none() {
+            self.unreachable_since = self.last_update;
+        }
+        self.unreachable_updates = self.unreachable_updates.saturating_add(1);
+        if self.outage_since.is_none() && self.unreachable_updates >= outage_threshold.max(1) {
+            // Dated from the first update which failed to reach the server
+            let unreachable_for = self
+                .unreachable_since
+                .map(|since| since.elapsed())
+                .unwrap_or_default();
+            self.outage_since = Some(
+                Local::now() - chrono::Duration::from_std(unreachable_for).unwrap_or_default(),
+            );
+            log::warn!(
+                "{} is down, {} updates in a row could not reach it",
+                self.name,
+                self.unreachable_updates
+            );
+        }
+    }
+
+    /// Length of the outages since start including the current one
+    pub fn total_downtime(&self) -> Duration {
+        self.past_downtime + self.outage_since.map(elapsed_since).unwrap_or_default()
+    }
+
+    /// Time between the last update and the next, shorter while retrying failed updates
+    fn next_update_delay(&self) -> Duration {
+        match self.failed_updates {
+            0 if self.follow_queries => self.update_delay.min(FOLLOW_UPDATE_DELAY),
+            0 => self.update_delay,
+            failed_updates => RETRY_DELAY
+                .saturating_mul(2u32.saturating_pow(failed_updates - 1))
+                .min(self.update_delay),
+        }
+    }
+
+    /// Whether the next update is due
+    fn update_due(&self) -> bool {
+        self.last_update
+            .is_none_or(|last_update| last_update.elapsed() > self.next_update_delay())
+    }
+
+    /// Time left until the next update is due, `None` while an update is running or before
+    /// the first has finished
+    pub fn next_update_in(&self) -> Option<Duration> {
+        match (self.last_update, self.update_in_flight) {
+            (Some(last_update), None) => Some(
+                self.next_update_delay()
+                    .saturating_sub(last_update.elapsed()),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Time left until a failed update is retried, `None` unless a retry is waiting
+    pub fn retrying_in(&self) -> Option<Duration> {
+        match (self.failed_updates, self.last_update, self.update_in_flight) {
+            (1.., Some(last_update), None) => Some(
+                self.next_update_delay()
+                    .saturating_sub(last_update.elapsed()),
+            ),
+            _ => None,
+        }
+    }
+
+    fn record_timings(&mut self, timings: Vec<RequestTiming>) {
+        for timing in timings {
+            let index = match self
+                .endpoint_statistics
+                .iter()
+                .position(|statistics| statistics.endpoint == timing.endpoint)
+            {
+                Some(index) => index,
+                None => {
+                    self.endpoint_statistics.push(EndpointStatistics {
+                        endpoint: timing.endpoint,
+                        last_duration: Duration::ZERO,
+                        requests: 0,
+                        errors: 0,
+                        last_error: None,
+                    });
+                    self.endpoint_statistics.len() - 1
+                }
+            };
+            let statistics = &mut self.endpoint_statistics[index];
+            statistics.last_duration = timing.duration;
+            statistics.requests += 1;
+            if timing.error.is_some() {
+                statistics.errors += 1;
+                statistics.last_error = timing.error;
+            }
+        }
+    }
+
+    /// Appends the summary to the history in `directory`, at most once per `RECORD_INTERVAL`
+    fn record_history(&mut self, directory: &Path) -> Result<(), String> {
+        let summary = match &self.last_data.summary {
+            Some(summary) => summary,
+            None => return Ok(()),
+        };
+        let recorded_recently = self
+            .last_history_record
+            .is_some_and(|recorded| recorded.elapsed() < history::RECORD_INTERVAL);
+        if recorded_recently {
+            return Ok(());
+        }
+        self.last_history_record = Some(Instant::now());
+        let history = History::new(directory, &self.name);
+        let prune_due = self
+            .history_pruned_at
+            .is_none_or(|pruned_at| pruned_at.elapsed() >= history::PRUNE_INTERVAL);
+        if prune_due {
+            self.history_pruned_at = Some(Instant::now());
+            let until = long_term_from(history::RETENTION_DAYS);
+            let pruned = history.clone();
+            let name = self.name.clone();
+            // Rewrites the whole file so it is kept off the UI thread
+            tokio::task::spawn_blocking(move || {
+                if let Err(error) = pruned.prune(until) {
+                    log::warn!("Could not prune the history of {}: {}", name, error);
+                }
+            });
+        }
+        let record = HistoryRecord::new(Utc::now().timestamp(), summary);
+        history
+            .append(&record)
+            .map_err(|error| format!("Could not write the history of {}: {}", self.name, error))
+    }
+}
+
+/// Data only fetched while a view shows it, as set by the `fetch_` fields of the server
+struct OptionalData {
+    domain_lists: bool,
+    adlists: bool,
+    local_records: bool,
+    clients_over_time: bool,
+    network_devices: bool,
+    dhcp_leases: bool,
+    top_clients_blocked: bool,
+    long_term_days: Option<u32>,
+    long_term_history: Option<History>,
+    filtered_queries: Option<QueryFilter>,
+}
+
+/// Requests made by the worker of a server
+enum WorkerCommand {
+    /// Replaces the update in flight, if any
+    Update {
+        id: u64,
+        optional: OptionalData,
+        top_items_count: u32,
+        dns_probe: Option<(String, DnsProbeConfig)>,
+    },
+    ChangeBlocking(BlockingChange),
+    Change(ServerChange),
+    /// Ends the API session and stops the worker, telling `logged_out` once done
+    Logout {
+        logged_out: mpsc::Sender<()>,
+        /// Forward of the server, kept open until the session has ended
+        ssh_tunnel: Option<SshTunnel>,
+    },
+}
+
+/// Outcome of a `ServerChange`
+type ChangeResult = Result<ListModificationResponse, APIError>;
+
+/// Ends of the channels to the worker of a server, which stops once they are dropped
+struct Worker {
+    commands: UnboundedSender<WorkerCommand>,
+    /// Finished updates with the number they were sent with
+    updates: mpsc::Receiver<(u64, FinishedUpdate)>,
+    blocking_changes: mpsc::Receiver<Result<Status, APIError>>,
+    changes: mpsc::Receiver<(ServerChange, ChangeResult)>,
+}
+
+/// Ends of the channels the worker of a server answers on
+struct WorkerResponses {
+    updates: mpsc::Sender<(u64, FinishedUpdate)>,
+    blocking_changes: mpsc::Sender<Result<Status, APIError>>,
+    changes: mpsc::Sender<(ServerChange, ChangeResult)>,
+}
+
+/// What an update sends back to its server
+struct FinishedUpdate {
+    data: PiHoleData,
+    timings: Vec<RequestTiming>,
+    /// How long the update took from start to finish
+    duration: Duration,
+}
+
+/// Outcome of one request made by an update
+pub struct RequestTiming {
+    pub endpoint: &'static str,
+    pub duration: Duration,
+    /// Description of the error if the request failed
+    pub error: Option<String>,
+}
+
+/// Latency and errors of one endpoint of a server since pimon started, shown in the
+/// Diagnostics view
+pub struct EndpointStatistics {
+    pub endpoint: &'static str,
+    /// How long the most recent request took
+    pub last_duration: Duration,
+    pub requests: u64,
+    pub errors: u64,
+    /// Description of the most recent error
+    pub last_error: Option<String>,
+}
+
+/// How current the data of a server is, shown on its tab
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// The first update has not finished yet
+    Pending,
+    /// Updated within twice the update delay
+    Fresh,
+    /// The last update reached the server but some of its requests failed
+    Partial,
+    /// Updates have not finished for more than twice the update delay
+    Stale,
+    /// The last update could not reach the server, which is not down yet
+    Unreachable,
+    /// The server is down, it could not be reached for the outage threshold of updates in a
+    /// row
+    Failed,
+}
+
+/// Keys used to navigate, the arrow keys work with either
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    #[default]
+    Default,
+    /// Adds `h`/`l` to change server, `j`/`k` to move through tables and `gg`/`G` to jump
+    /// to the first/last row
+    Vim,
+}
+
+/// How the queries over time are drawn
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartMode {
+    /// Newest bar on the left, labelled with its count
+    #[default]
+    Bar,
+    /// Line with time and count axes, newest on the right
+    Line,
+}
+
+/// How clients are named in Top Clients, the query log and the client chart
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientNames {
+    /// IP address only
+    Address,
+    /// Host name, or the address of clients without one
+    Name,
+    /// Host name followed by the address in brackets
+    #[default]
+    Both,
+}
+
+/// Timezone of the times shown, as written in the config.
+/// chrono cannot look up zones by name so only the system's timezone or a fixed offset is supported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timezone {
+    name: String,
+    /// `None` for the system's timezone
+    offset: Option<FixedOffset>,
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    /// Accepts `local`, `UTC` or an offset from UTC such as `+02:00`, `-0530` or `+9`
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let invalid = || {
+            format!(
+                "Invalid timezone {:?}, expected local, UTC or an offset such as +02:00",
+                name
+            )
+        };
+        let offset = match name.to_lowercase().as_str() {
+            "local" => None,
+            "utc" | "z" => Some(0),
+            offset => {
+                let (sign, offset) = match offset.strip_prefix('-') {
+                    Some(offset) => (-1, offset),
+                    None => (1, offset.strip_prefix('+').ok_or_else(invalid)?),
+                };
+                let digits = offset.replace(':', "");
+                let (hours, minutes) = match digits.len() {
+                    1 | 2 => (digits.as_str(), "0"),
+                    4 => digits.split_at(2),
+                    _ => return Err(invalid()),
+                };
+                let hours: i32 = hours.parse().map_err(|_| invalid())?;
+                let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+                if hours > 14 || minutes >= 60 {
+                    return Err(invalid());
+                }
+                Some(sign * (hours * 3600 + minutes * 60))
+            }
+        };
+        Ok(Timezone {
+            offset: offset
+                .map(|seconds| FixedOffset::east_opt(seconds).ok_or_else(invalid))
+                .transpose()?,
+            name,
+        })
+    }
+}
+
+impl From<Timezone> for String {
+    fn from(timezone: Timezone) -> Self {
+        timezone.name
+    }
+}
+
+/// Format of the times of day shown, as written in the config: `24h`, `12h` or a strftime
+/// format used for every time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeFormat {
+    name: String,
+    /// Time to the minute, for the chart and the times of the tables
+    pub minutes: String,
+    /// Time to the second, for the query log and when updates and alerts happened
+    pub seconds: String,
+    /// Time of the bars of the queries chart, as wide as a bar
+    pub bar: String,
+    /// Hour of the bars of the queries per hour of the Client view
+    pub hour: String,
+}
+
+/// Minutes, seconds, bar and hour formats of `24h`
+const FORMATS_24H: [&str; 4] = ["%H:%M", "%H:%M:%S", "%H:%M", "%H"];
+/// The bars are too narrow for AM and PM, the hour tells them apart
+const FORMATS_12H: [&str; 4] = ["%-I:%M %p", "%-I:%M:%S %p", "%-I:%M", "%-I%P"];
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        TimeFormat::new("24h".to_string(), FORMATS_24H)
+    }
+}
+
+impl TimeFormat {
+    fn new(name: String, [minutes, seconds, bar, hour]: [&str; 4]) -> Self {
+        TimeFormat {
+            minutes: minutes.to_string(),
+            seconds: seconds.to_string(),
+            bar: bar.to_string(),
+            hour: hour.to_string(),
+            name,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == TimeFormat::default()
+    }
+
+    /// Date and time to the minute
+    pub fn date_minutes(&self) -> String {
+        format!("%Y-%m-%d {}", self.minutes)
+    }
+
+    /// Date and time to the second
+    pub fn date_seconds(&self) -> String {
+        format!("%Y-%m-%d {}", self.seconds)
+    }
+}
+
+impl TryFrom<String> for TimeFormat {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.to_lowercase().as_str() {
+            "24h" => Ok(TimeFormat::new(name, FORMATS_24H)),
+            "12h" => Ok(TimeFormat::new(name, FORMATS_12H)),
+            "" => Err("Invalid time format, expected 24h, 12h or a strftime format".to_string()),
+            _ if StrftimeItems::new(&name).any(|item| matches!(item, Item::Error)) => Err(format!(
+                "Invalid time format {:?}, expected 24h, 12h or a strftime format such as \
+                     %H:%M",
+                name
+            )),
+            _ => {
+                let format = name.clone();
+                Ok(TimeFormat::new(name, [&format; 4]))
+            }
+        }
+    }
+}
+
+impl From<TimeFormat> for String {
+    fn from(time_format: TimeFormat) -> Self {
+        time_format.name
+    }
+}
+
+/// Unix timestamp of `instant`, which has passed
+pub fn timestamp_of(instant: Instant) -> i64 {
+    Utc::now().timestamp() - instant.elapsed().as_secs() as i64
+}
+
+/// Columns taken by times written with `format`, measured at 22:58:58 on 30 December 2000
+/// for the widest hour, day and month
+pub fn time_width(format: &str) -> u16 {
+    let sample = DateTime::from_timestamp(978_217_138, 0).unwrap_or_default();
+    sample.format(format).to_string().chars().count() as u16
+}
+
+/// Formats a unix timestamp in `timezone`, or the system's timezone without one
+pub fn format_timestamp(timestamp: i64, format: &str, timezone: Option<&Timezone>) -> String {
+    let datetime = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+    match timezone.and_then(|timezone| timezone.offset) {
+        Some(offset) => datetime.with_timezone(&offset).format(format).to_string(),
+        None => datetime.with_timezone(&Local).format(format).to_string(),
+    }
+}
+
+/// How far back the chart of the overview goes from the newest period
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChartWindow {
+    Hour,
+    SixHours,
+    TwelveHours,
+    Day,
+}
+
+impl ChartWindow {
+    pub const ALL: [ChartWindow; 4] = [
+        ChartWindow::Hour,
+        ChartWindow::SixHours,
+        ChartWindow::TwelveHours,
+        ChartWindow::Day,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChartWindow::Hour => "1h",
+            ChartWindow::SixHours => "6h",
+            ChartWindow::TwelveHours => "12h",
+            ChartWindow::Day => "24h",
+        }
+    }
+
+    fn seconds(self) -> i64 {
+        match self {
+            ChartWindow::Hour => 60 * 60,
+            ChartWindow::SixHours => 6 * 60 * 60,
+            ChartWindow::TwelveHours => 12 * 60 * 60,
+            ChartWindow::Day => 24 * 60 * 60,
+        }
+    }
+
+    /// Whether the period starting at `timestamp` is shown when `newest` is the latest period
+    pub fn contains(self, newest: i64, timestamp: i64) -> bool {
+        timestamp > newest - self.seconds()
+    }
+}
+
+/// Length of the periods of the queries and clients over time data
+const OVER_TIME_PERIOD_SECONDS: i64 = 10 * 60;
+
+/// Number of days shown by the Long term view
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LongTermPeriod {
+    Week,
+    Month,
+    Quarter,
+}
+
+impl LongTermPeriod {
+    pub const ALL: [LongTermPeriod; 3] = [
+        LongTermPeriod::Week,
+        LongTermPeriod::Month,
+        LongTermPeriod::Quarter,
+    ];
+
+    pub fn days(self) -> u32 {
+        match self {
+            LongTermPeriod::Week => 7,
+            LongTermPeriod::Month => 30,
+            LongTermPeriod::Quarter => 90,
+        }
+    }
+}
+
+/// What the chart of the overview shows over time
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OverTimeChart {
+    /// Total and blocked queries
+    Queries,
+    /// Queries of each client
+    Clients,
+}
+
+/// Main area of the screen below the server tabs
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Overview,
+    QueryLog,
+    Lists,
+    LongTerm,
+    Diagnostics,
+    /// Details of the client selected in Top Clients
+    Client,
+    /// Alerts raised so far, newest first
+    Alerts,
+    /// Summary and queries chart of each server side by side
+    Split,
+    /// Devices of the network table
+    Network,
+    /// Adlists of the selected server
+    Adlists,
+    /// Local DNS records of the selected server
+    LocalDns,
+}
+
+/// Screen of the interface switched to with the F keys, each holding one or more views
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    /// The overview and the views opened from it
+    Overview,
+    Queries,
+    Network,
+    Lists,
+    Adlists,
+    LocalDns,
+}
+
+impl Page {
+    /// In the order of their F keys
+    pub const ALL: [Page; 6] = [
+        Page::Overview,
+        Page::Queries,
+        Page::Network,
+        Page::Lists,
+        Page::Adlists,
+        Page::LocalDns,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Page::Overview => "Overview",
+            Page::Queries => "Queries",
+            Page::Network => "Network",
+            Page::Lists => "Lists",
+            Page::Adlists => "Adlists",
+            Page::LocalDns => "Local DNS",
+        }
+    }
+
+    /// Page showing `view`
+    pub fn of(view: View) -> Page {
+        match view {
+            View::QueryLog => Page::Queries,
+            View::Network => Page::Network,
+            View::Lists => Page::Lists,
+            View::Adlists => Page::Adlists,
+            View::LocalDns => Page::LocalDns,
+            View::Overview
+            | View::LongTerm
+            | View::Diagnostics
+            | View::Client
+            | View::Alerts
+            | View::Split => Page::Overview,
+        }
+    }
+
+    /// View shown when switching to the page
+    fn view(self) -> View {
+        match self {
+            Page::Overview => View::Overview,
+            Page::Queries => View::QueryLog,
+            Page::Network => View::Network,
+            Page::Lists => View::Lists,
+            Page::Adlists => View::Adlists,
+            Page::LocalDns => View::LocalDns,
+        }
+    }
+}
+
+/// Statuses of FTL grouped by how the query was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum StatusGroup {
+    Forwarded,
+    Cached,
+    /// Blocked by an adlist
+    Gravity,
+    Regex,
+    /// Blocked by the exact blacklist
+    Exact,
+    /// Blocked as the target of a CNAME
+    Cname,
+    /// Blocked by the upstream server
+    External,
+    Retried,
+    Other,
+}
+
+impl StatusGroup {
+    pub const ALL: [StatusGroup; 9] = [
+        StatusGroup::Forwarded,
+        StatusGroup::Cached,
+        StatusGroup::Gravity,
+        StatusGroup::Regex,
+        StatusGroup::Exact,
+        StatusGroup::Cname,
+        StatusGroup::External,
+        StatusGroup::Retried,
+        StatusGroup::Other,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            StatusGroup::Forwarded => "Forwarded",
+            StatusGroup::Cached => "Cached",
+            StatusGroup::Gravity => "Gravity",
+            StatusGroup::Regex => "Regex",
+            StatusGroup::Exact => "Exact",
+            StatusGroup::Cname => "CNAME",
+            StatusGroup::External => "External",
+            StatusGroup::Retried => "Retried",
+            StatusGroup::Other => "Other",
+        }
+    }
+
+    pub fn of(status: &QueryStatus) -> StatusGroup {
+        match status {
+            QueryStatus::QueryForwarded => StatusGroup::Forwarded,
+            QueryStatus::QueryCache => StatusGroup::Cached,
+            QueryStatus::QueryGravity => StatusGroup::Gravity,
+            QueryStatus::QueryRegex => StatusGroup::Regex,
+            QueryStatus::QueryBlacklist => StatusGroup::Exact,
+            QueryStatus::QueryGravityCname
+            | QueryStatus::QueryRegexCname
+            | QueryStatus::QueryBlacklistCname => StatusGroup::Cname,
+            QueryStatus::QueryExternalBlockedIp
+            | QueryStatus::QueryExternalBlockedNull
+            | QueryStatus::QueryExternalBlockedNxra => StatusGroup::External,
+            QueryStatus::QueryRetried | QueryStatus::QueryRetriedDnssec => StatusGroup::Retried,
+            QueryStatus::QueryInProgress
+            | QueryStatus::QueryDbbusy
+            | QueryStatus::QueryUnknown
+            | QueryStatus::QueryStatusMax => StatusGroup::Other,
+        }
+    }
+
+    pub fn is_blocked(self) -> bool {
+        matches!(
+            self,
+            StatusGroup::Gravity
+                | StatusGroup::Regex
+                | StatusGroup::Exact
+                | StatusGroup::Cname
+                | StatusGroup::External
+        )
+    }
+}
+
+/// Upper bounds of the ranges of reply times counted, in milliseconds. A last range holds the
+/// slower replies.
+pub const REPLY_TIME_RANGES_MS: [u64; 5] = [1, 10, 50, 100, 500];
+
+/// Reply times of the queries of the query log
+pub struct ReplyTimes {
+    /// Number of queries with a known reply time
+    pub count: usize,
+    pub min: Duration,
+    pub average: Duration,
+    pub max: Duration,
+    /// Queries within each of `REPLY_TIME_RANGES_MS` and above the last
+    pub histogram: Vec<usize>,
+    /// Upstream server, the queries forwarded to it and their average reply time, the
+    /// busiest first
+    pub upstreams: Vec<(String, usize, Duration)>,
+}
+
+/// Client of the Client view with what its recent queries show of it
+pub struct ClientDetails {
+    pub name: Option<String>,
+    pub address: String,
+    /// Queries today as listed in Top Clients
+    pub queries: Option<u64>,
+    /// Blocked queries today, `None` if the client is not in the top clients by blocked queries
+    pub blocked: Option<u64>,
+    /// Number of recent queries fetched, `None` until they are
+    pub recent_queries: Option<usize>,
+    pub recent_blocked: usize,
+    /// Domain and count of the recent queries, most frequent first
+    pub top_domains: Vec<Vec<String>>,
+    /// Start of each of the last 24 hours, oldest first, with the recent queries within it
+    pub hourly: Vec<(i64, u64)>,
+}
+
+/// Domain of the domain popup with what is known of it
+pub struct DomainDetails<'a> {
+    pub domain: String,
+    /// Permitted queries today as listed in Top Queries
+    pub permitted: Option<u64>,
+    /// Blocked queries today as listed in Top Ads
+    pub blocked: Option<u64>,
+    /// Most recent queries for the domain with the name of their server, newest first.
+    /// `None` until they are fetched.
+    pub recent_queries: Option<Vec<(&'a str, &'a Query)>>,
+}
+
+/// Client or domain the queries of the Client view or the domain popup are fetched for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryFilter {
+    /// Address of a client
+    Client(String),
+    Domain(String),
+}
+
+/// Name and address of a client listed as "name|address" or "address"
+pub fn split_client(client: &str) -> (Option<&str>, &str) {
+    match client.rsplit_once('|') {
+        Some((name, address)) => (Some(name), address),
+        None => (None, client),
+    }
+}
+
+/// Whether a query was answered by blocking it
+pub fn is_blocked(status: &QueryStatus) -> bool {
+    matches!(
+        status,
+        QueryStatus::QueryGravity
+            | QueryStatus::QueryRegex
+            | QueryStatus::QueryBlacklist
+            | QueryStatus::QueryExternalBlockedIp
+            | QueryStatus::QueryExternalBlockedNull
+            | QueryStatus::QueryExternalBlockedNxra
+            | QueryStatus::QueryGravityCname
+            | QueryStatus::QueryRegexCname
+            | QueryStatus::QueryBlacklistCname
+    )
+}
+
+/// Part of the Overview view that can be focused and maximized
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    /// The row of summary boxes
+    Overview,
+    Chart,
+    Statistics(StatisticsTable),
+}
+
+impl Panel {
+    /// In the order Tab moves through them
+    pub const ALL: [Panel; 5] = [
+        Panel::Overview,
+        Panel::Chart,
+        Panel::Statistics(StatisticsTable::Queries),
+        Panel::Statistics(StatisticsTable::Ads),
+        Panel::Statistics(StatisticsTable::Clients),
+    ];
+
+    /// Section of the layout the panel is drawn in
+    pub fn section(self) -> Section {
+        match self {
+            Panel::Overview => Section::Overview,
+            Panel::Chart => Section::Chart,
+            Panel::Statistics(_) => Section::Statistics,
+        }
+    }
+}
+
+/// Tables of the busiest domains and clients in the overview
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StatisticsTable {
+    Queries,
+    Ads,
+    Clients,
+}
+
+impl StatisticsTable {
+    pub const ALL: [StatisticsTable; 3] = [
+        StatisticsTable::Queries,
+        StatisticsTable::Ads,
+        StatisticsTable::Clients,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            StatisticsTable::Queries => "Top Queries",
+            StatisticsTable::Ads => "Top Ads",
+            StatisticsTable::Clients => "Top Clients",
+        }
+    }
+
+    /// Heading of the first column
+    pub fn column(self) -> &'static str {
+        match self {
+            StatisticsTable::Queries | StatisticsTable::Ads => "Domain",
+            StatisticsTable::Clients => "Client",
+        }
+    }
+
+    /// Rows of the table for `pi_hole_data`, most frequent first
+    pub fn rows(self, pi_hole_data: &PiHoleData) -> Vec<Vec<String>> {
+        let map = match self {
+            StatisticsTable::Queries => pi_hole_data
+                .top_items
+                .as_ref()
+                .map(|top_items| &top_items.top_queries),
+            StatisticsTable::Ads => pi_hole_data
+                .top_items
+                .as_ref()
+                .map(|top_items| &top_items.top_ads),
+            StatisticsTable::Clients => pi_hole_data
+                .top_sources
+                .as_ref()
+                .map(|top_sources| &top_sources.top_sources),
+        };
+        map.map(order_convert_string_num_map).unwrap_or_default()
+    }
+
+    /// Whether the server leaves out what the table lists at `privacy_level`
+    pub fn hidden_by(self, privacy_level: PrivacyLevel) -> bool {
+        match self {
+            StatisticsTable::Queries | StatisticsTable::Ads => privacy_level.hides_domains(),
+            StatisticsTable::Clients => privacy_level.hides_clients(),
+        }
+    }
+}
+
+/// What a Pi-hole leaves out of its statistics and query log, each level hiding more
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PrivacyLevel {
+    ShowEverything,
+    HideDomains,
+    HideDomainsAndClients,
+    /// Single queries are not recorded at all
+    Anonymous,
+}
+
+impl PrivacyLevel {
+    /// Level reported in `summary`, both APIs number them from 0 to 3
+    fn of(summary: &Summary) -> Self {
+        match summary.privacy_level.trim() {
+            "1" => PrivacyLevel::HideDomains,
+            "2" => PrivacyLevel::HideDomainsAndClients,
+            "3" => PrivacyLevel::Anonymous,
+            _ => PrivacyLevel::ShowEverything,
+        }
+    }
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    pub fn hides_domains(self) -> bool {
+        self >= PrivacyLevel::HideDomains
+    }
+
+    pub fn hides_clients(self) -> bool {
+        self >= PrivacyLevel::HideDomainsAndClients
+    }
+}
+
+/// Areas of the last drawn frame that respond to mouse clicks, recorded while drawing
+#[derive(Default)]
+pub struct ClickAreas {
+    /// One area per tab, in the order they are shown
+    pub tabs: Vec<Rect>,
+    /// One area per group tab, starting with the tab of every group
+    pub group_tabs: Vec<Rect>,
+    pub statistics_tables: Vec<(StatisticsTable, Rect)>,
+    pub lists: Vec<(DomainList, Rect)>,
+    pub adlists: Option<Rect>,
+    pub local_records: Vec<(LocalRecordType, Rect)>,
+}
+
+/// Something drawn at a position of the screen that responds to the mouse
+#[derive(Clone, Copy)]
+enum MouseTarget {
+    /// Index of a tab, counting the All tab if it is shown
+    Tab(usize),
+    GroupTab(usize),
+    StatisticsTable(StatisticsTable),
+    List(DomainList),
+}
+
+impl ClickAreas {
+    fn target_at(&self, column: u16, row: u16) -> Option<MouseTarget> {
+        let contains = |area: &Rect| {
+            column >= area.left()
+                && column < area.right()
+                && row >= area.top()
+                && row < area.bottom()
+        };
+        if let Some(index) = self.tabs.iter().position(contains) {
+            return Some(MouseTarget::Tab(index));
+        }
+        if let Some(index) = self.group_tabs.iter().position(contains) {
+            return Some(MouseTarget::GroupTab(index));
+        }
+        if let Some((table, _)) = self
+            .statistics_tables
+            .iter()
+            .find(|(_, area)| contains(area))
+        {
+            return Some(MouseTarget::StatisticsTable(*table));
+        }
+        self.lists
+            .iter()
+            .find(|(_, area)| contains(area))
+            .map(|(list, _)| MouseTarget::List(*list))
+    }
+}
+
+/// What the text typed into an `InputPrompt` is used for
+#[derive(Clone, Copy)]
+pub enum PromptKind {
+    /// How long to disable blocking
+    DisableDuration,
+    /// Domain to add to a list
+    AddDomain(DomainList),
+    /// URL of an adlist to add
+    AddAdlist,
+    /// Domain and target of a Local DNS record to add
+    AddLocalRecord(LocalRecordType),
+    /// Text the rows of the focused overview table must contain, applied while typing
+    Filter,
+}
+
+/// Single line text prompt shown over the current view
+pub struct InputPrompt {
+    pub kind: PromptKind,
+    pub input: String,
+    /// Why the typed text could not be used
+    pub error: Option<String>,
+}
+
+/// Maintenance action of the actions menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerAction {
+    /// Deletes the queries recorded so far
+    FlushLogs,
+    /// Forgets the devices of the network table
+    FlushNetworkTable,
+    RestartDns,
+}
+
+impl ServerAction {
+    pub const ALL: [ServerAction; 3] = [
+        ServerAction::FlushLogs,
+        ServerAction::FlushNetworkTable,
+        ServerAction::RestartDns,
+    ];
+
+    /// Key selecting the action in the actions menu
+    pub fn key(self) -> char {
+        match self {
+            ServerAction::FlushLogs => 'l',
+            ServerAction::FlushNetworkTable => 'n',
+            ServerAction::RestartDns => 'r',
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ServerAction::FlushLogs => "Flush the query log",
+            ServerAction::FlushNetworkTable => "Flush the network table",
+            ServerAction::RestartDns => "Restart the DNS resolver",
+        }
+    }
+
+    fn from_key(key: char) -> Option<ServerAction> {
+        ServerAction::ALL
+            .iter()
+            .copied()
+            .find(|action| action.key() == key)
+    }
+}
+
+/// Change waiting for the user to confirm it
+pub enum PendingAction {
+    AddDomain {
+        domain: String,
+        list: DomainList,
+    },
+    RemoveDomain {
+        domain: String,
+        list: DomainList,
+    },
+    AddServers(Vec<DiscoveredServer>),
+    /// Blocking of the selected server for this many seconds, `0` for permanently
+    Disable(u64),
+    /// Download the adlists of the selected server again
+    UpdateGravity,
+    RemoveLocalRecord {
+        record_type: LocalRecordType,
+        record: LocalRecord,
+    },
+    /// Action of the actions menu on the selected server
+    Run(ServerAction),
+}
+
+/// Sent by the task running gravity as its output arrives
+enum GravityProgress {
+    Line(String),
+    Finished(Result<(), String>),
+}
+
+/// Gravity update started from the interface, its output is shown in a popup
+pub struct GravityUpdate {
+    /// Name of the server it runs on
+    pub server: String,
+    /// Lines of output so far, oldest first
+    pub output: Vec<String>,
+    /// `None` while gravity is running
+    pub result: Option<Result<(), String>>,
+    /// Whether the popup is shown, it can be closed while gravity keeps running
+    pub popup_open: bool,
+    api_config: Arc<PiHoleConfigImplementation>,
+    progress: mpsc::Receiver<GravityProgress>,
+}
+
+/// Server an action ran on and the status message describing its outcome
+type ActionOutcome = (Arc<PiHoleConfigImplementation>, Result<String, String>);
+
+/// Change reverting the last list addition, available for `UNDO_DURATION`
+pub struct Undo {
+    /// Server the change was made on, servers may have been reordered since
+    api_config: Arc<PiHoleConfigImplementation>,
+    action: PendingAction,
+    available_until: Instant,
+}
+
+/// Yes/no question asked before applying `action`
+pub struct Confirmation {
+    pub message: String,
+    pub action: PendingAction,
+}
+
+/// Labels of the server editor form fields, in the order of `ServerEditor::values`
+pub const SERVER_EDITOR_FIELDS: [&str; 4] = ["Name", "Host", "API key", "API version"];
+
+/// Form for adding a server or changing an existing one
+pub struct ServerEditor {
+    /// Index of the server being edited, `None` when adding a new server
+    pub server_index: Option<usize>,
+    /// Name, host, API key and API version as typed
+    pub values: [String; 4],
+    pub selected_field: usize,
+    /// Why the last attempt to save failed
+    pub error: Option<String>,
+}
+
+impl ServerEditor {
+    /// Index of the API key field, which is masked and left empty to keep the current key
+    pub const API_KEY_FIELD: usize = 2;
+
+    fn new(server_index: Option<usize>, config: Option<&PiHoleServerConfig>) -> Self {
+        let values = match config {
+            Some(config) => [
+                config.name.clone(),
+                config.host.clone(),
+                String::new(),
+                u8::from(config.api_version).to_string(),
+            ],
+            None => [
+                String::new(),
+                "http://".to_string(),
+                String::new(),
+                u8::from(ApiVersion::default()).to_string(),
+            ],
+        };
+        ServerEditor {
+            server_index,
+            values,
+            selected_field: 0,
+            error: None,
+        }
+    }
+
+    /// Server configuration described by the form, based on `config` when editing
+    fn to_config(&self, config: Option<&PiHoleServerConfig>) -> Result<PiHoleServerConfig, String> {
+        let [name, host, api_key, api_version] = &self.values;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Name is required".to_string());
+        }
+        let host = host.trim().trim_end_matches('/');
+        if !(host.starts_with("http://") || host.starts_with("https://")) {
+            return Err("Host must begin with http:// or https://".to_string());
+        }
+        let api_version = api_version
+            .trim()
+            .parse::<u8>()
+            .map_err(|_| "API version must be 5 or 6".to_string())
+            .and_then(ApiVersion::try_from)?;
+
+        let mut config = config.cloned().unwrap_or_else(|| {
+            PiHoleServerConfig::new(String::new(), String::new(), None, api_version)
+        });
+        config.name = name.to_string();
+        config.host = host.to_string();
+        config.api_version = api_version;
+        if !api_key.is_empty() {
+            config.api_key = Some(api_key.clone());
+            config.api_key_env = None;
+            config.api_key_keyring = None;
+        }
+        Ok(config)
+    }
+}
+
+pub struct App {
+    pub selected_server_index: usize,
+    /// Whether the All tab is shown instead of the selected server, only with several servers
+    all_selected: bool,
+    /// Index in `groups()` of the group whose servers are shown, `None` for every server
+    pub selected_group: Option<usize>,
+    /// Combined data of every server for the All tab
+    pub aggregate: PiHoleData,
+    pub view: View,
+    pub servers: Vec<PiHoleServer>,
+    /// Delay between updates in milliseconds for servers without their own
+    pub update_delay: u64,
+    /// Request timeout in milliseconds for servers without their own
+    pub request_timeout_ms: u64,
+    pub graph_squash_factor: usize,
+    /// Width of the terminal as of the last resize
+    terminal_width: u16,
+    pub chart_mode: ChartMode,
+    pub over_time_chart: OverTimeChart,
+    /// Whether Top Clients counts only blocked queries
+    pub top_clients_blocked: bool,
+    /// Client shown in the Client view, as listed in Top Clients
+    pub selected_client: Option<String>,
+    /// Domain shown in the domain popup, `None` while it is closed
+    pub selected_domain: Option<String>,
+    /// Number of entries fetched for each top list
+    pub top_items_count: u32,
+    /// Updates in a row without reaching a server before it is considered down
+    pub outage_threshold: u32,
+    /// Whether the scheduled updates are stopped so the data shown stays put
+    pub paused: bool,
+    /// Whether the query log updates every few seconds with the newest queries at the bottom
+    pub follow_query_log: bool,
+    pub chart_window: ChartWindow,
+    /// Number of bars the chart is panned back from the newest
+    pub chart_pan: usize,
+    pub long_term_period: LongTermPeriod,
+    pub server_editor: Option<ServerEditor>,
+    /// Search of the network for Pi-holes in progress
+    discovery: Option<mpsc::Receiver<Vec<DiscoveredServer>>>,
+    /// Given to every server, see `PiHoleServer::data_arrived`
+    data_arrived: Option<mpsc::Sender<CustomEvent>>,
+    /// When the message replacing the help bar was set, it is an error message if `Err`
+    pub status_message: Option<(Instant, Result<String, String>)>,
+    pub input_prompt: Option<InputPrompt>,
+    pub confirmation: Option<Confirmation>,
+    /// Last gravity update, kept until it finishes and its popup is closed
+    pub gravity: Option<GravityUpdate>,
+    /// Whether the actions menu is shown
+    pub actions_menu_open: bool,
+    /// Actions running in the background
+    running_actions: Vec<mpsc::Receiver<ActionOutcome>>,
+    /// Last duration used to disable blocking, as typed e.g. `5m`
+    pub disable_duration: String,
+    pub keymap: Keymap,
+    /// Where the summaries of the servers are recorded, `None` unless `history` is enabled
+    pub history_directory: Option<PathBuf>,
+    /// Timezone of the chart and query log, the system's if `None`
+    pub timezone: Option<Timezone>,
+    pub time_format: TimeFormat,
+    /// Theme as configured, kept to be saved unchanged
+    pub theme_config: ThemeConfig,
+    /// Sections of the Overview view and their heights
+    pub layout: LayoutConfig,
+    pub alerts: Alerts,
+    /// Values from which metrics of the overview are coloured as warnings and errors
+    pub thresholds: ThresholdsConfig,
+    /// Whether raised alerts are also shown as desktop notifications
+    pub notifications: bool,
+    /// Whether domains, clients and addresses are partly masked, for sharing the screen
+    pub redact: bool,
+    pub client_names: ClientNames,
+    /// Writes the counts shown
+    pub numbers: Numbers,
+    pub webhooks: Webhooks,
+    pub theme: Theme,
+    /// List highlighted in the Lists view
+    pub selected_list: DomainList,
+    /// Selected row and scroll position of the highlighted list
+    pub list_table_state: TableState,
+    /// Selected row and scroll position of the Adlists view
+    pub adlist_table_state: TableState,
+    /// Records highlighted in the Local DNS view
+    pub selected_record_type: LocalRecordType,
+    /// Selected row and scroll position of the highlighted records
+    pub record_table_state: TableState,
+    /// Panel of the Overview view that Tab moved to
+    pub focused_panel: Panel,
+    /// Whether the focused panel takes the whole Overview view
+    pub panel_maximized: bool,
+    /// Overview table that the selection keys act on
+    pub focused_table: StatisticsTable,
+    /// Selected row and scroll position of the focused overview table
+    pub statistics_table_state: TableState,
+    /// Only rows of the focused overview table containing this are shown, ignoring case
+    pub statistics_filter: String,
+    pub undo: Option<Undo>,
+    pub click_areas: ClickAreas,
+}
+
+impl App {
+    /// The All tab is shown before the servers when there is more than one
+    pub fn has_all_tab(&self) -> bool {
+        self.servers.len() > 1
+    }
+
+    pub fn all_tab_selected(&self) -> bool {
+        self.all_selected && self.has_all_tab()
+    }
+
+    /// Groups of the servers in the order they are first configured
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups = Vec::new();
+        for group in self
+            .servers
+            .iter()
+            .filter_map(|server| server.config.group.as_deref())
+        {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    /// Whether `server` is in the selected group, they all are while no group is selected
+    fn in_selected_group(&self, server: &PiHoleServer) -> bool {
+        match self.selected_group {
+            Some(index) => server.config.group.as_deref() == self.groups().get(index).copied(),
+            None => true,
+        }
+    }
+
+    /// Indices of the servers with a tab, those of the selected group
+    pub fn tab_server_indices(&self) -> Vec<usize> {
+        (0..self.servers.len())
+            .filter(|index| self.in_selected_group(&self.servers[*index]))
+            .collect()
+    }
+
+    /// Whether the selected tab shows the server at `index`
+    fn shows_server(&self, index: usize) -> bool {
+        if self.all_tab_selected() {
+            self.in_selected_group(&self.servers[index])
+        } else {
+            index == self.selected_server_index
+        }
+    }
+
+    /// Servers of the tabs in order, `None` for the All tab
+    fn tabs(&self) -> Vec<Option<usize>> {
+        let mut tabs = Vec::new();
+        if self.has_all_tab() {
+            tabs.push(None);
+        }
+        tabs.extend(self.tab_server_indices().into_iter().map(Some));
+        tabs
+    }
+
+    fn select_server_tab(&mut self, tab: Option<usize>) {
+        match tab {
+            Some(index) => {
+                self.all_selected = false;
+                self.selected_server_index = index;
+            }
+            None => self.all_selected = true,
+        }
+    }
+
+    /// Selects the tab of the server `index`, switching to its group so the tab is shown
+    fn show_server(&mut self, index: usize) {
+        let group = self.servers[index].config.group.as_deref();
+        let group = group.and_then(|group| self.groups().iter().position(|name| *name == group));
+        self.select_group(group);
+        self.select_server_tab(Some(index));
+    }
+
+    /// Shows the servers of the next group, after the last group every server again
+    pub fn next_group(&mut self) {
+        let group_count = self.groups().len();
+        let group = match self.selected_group {
+            None if group_count > 0 => Some(0),
+            Some(index) if index + 1 < group_count => Some(index + 1),
+            _ => None,
+        };
+        self.select_group(group);
+    }
+
+    pub fn previous_group(&mut self) {
+        let group_count = self.groups().len();
+        let group = match self.selected_group {
+            None => group_count.checked_sub(1),
+            Some(0) => None,
+            Some(index) => Some(index - 1),
+        };
+        self.select_group(group);
+    }
+
+    /// Shows the servers of `group`, starting on its All tab
+    fn select_group(&mut self, group: Option<usize>) {
+        self.selected_group = group;
+        let first = self.tabs().first().copied().flatten();
+        self.select_server_tab(first);
+        if self.has_all_tab() {
+            self.aggregate = aggregate_data(&self.displayed_servers());
+        }
+    }
+
+    /// Data of the selected tab, combined from every server of the group on the All tab
+    pub fn displayed_data(&self) -> &PiHoleData {
+        if self.all_tab_selected() {
+            &self.aggregate
+        } else {
+            &self.servers[self.selected_server_index].last_data
+        }
+    }
+
+    /// Privacy level of the selected server. On the All tab the lowest level of the servers,
+    /// as what one server hides the others may still show.
+    pub fn privacy_level(&self) -> PrivacyLevel {
+        self.displayed_servers()
+            .iter()
+            .filter_map(|server| server.last_data.summary.as_ref())
+            .map(PrivacyLevel::of)
+            .min()
+            .unwrap_or(PrivacyLevel::ShowEverything)
+    }
+
+    /// Index of the selected tab, counting the All tab if it is shown
+    pub fn selected_tab_index(&self) -> usize {
+        let selected = (!self.all_tab_selected()).then_some(self.selected_server_index);
+        self.tabs()
+            .iter()
+            .position(|tab| *tab == selected)
+            .unwrap_or_default()
+    }
+
+    pub fn next_server(&mut self) {
+        let tabs = self.tabs();
+        let next = tabs[(self.selected_tab_index() + 1) % tabs.len()];
+        self.select_server_tab(next);
+    }
+
+    pub fn previous_server(&mut self) {
+        let tabs = self.tabs();
+        let previous = tabs[(self.selected_tab_index() + tabs.len() - 1) % tabs.len()];
+        self.select_server_tab(previous);
+    }
+
+    /// Selects the `number`th server tab counting from 1, or the All tab for 0
+    pub fn select_server_number(&mut self, number: usize) {
+        if number == 0 {
+            if self.has_all_tab() {
+                self.select_server_tab(None);
+            }
+        } else if let Some(index) = self.tab_server_indices().get(number - 1) {
+            self.select_server_tab(Some(*index));
+        }
+    }
+
+    /// Selects the tab at `index`, counting the All tab if it is shown
+    fn select_tab(&mut self, index: usize) {
+        if let Some(tab) = self.tabs().get(index) {
+            self.select_server_tab(*tab);
+        }
+    }
+
+    /// Switches to a clicked tab, or focuses a clicked table or list
+    pub fn on_click(&mut self, column: u16, row: u16) {
+        match self.click_areas.target_at(column, row) {
+            Some(MouseTarget::Tab(index)) => self.select_tab(index),
+            // The first group tab shows every server
+            Some(MouseTarget::GroupTab(index)) => self.select_group(index.checked_sub(1)),
+            Some(MouseTarget::StatisticsTable(table)) => self.focus_statistics_table(table),
+            Some(MouseTarget::List(list)) if list != self.selected_list => self.select_list(list),
+            _ => {}
+        }
+    }
+
+    /// Scrolls the table or list under the cursor by moving its selection one row,
+    /// the first scroll only focuses it
+    pub fn on_scroll(&mut self, column: u16, row: u16, down: bool) {
+        match self.click_areas.target_at(column, row) {
+            Some(MouseTarget::StatisticsTable(table))
+                if table == self.focused_table
+                    && self.statistics_table_state.selected().is_some() =>
+            {
+                if down {
+                    self.next_statistics_row();
+                } else {
+                    self.previous_statistics_row();
+                }
+            }
+            Some(MouseTarget::StatisticsTable(table)) => self.focus_statistics_table(table),
+            Some(MouseTarget::List(list)) if list == self.selected_list => {
+                if down {
+                    self.next_list_domain();
+                } else {
+                    self.previous_list_domain();
+                }
+            }
+            Some(MouseTarget::List(list)) => self.select_list(list),
+            _ => {}
+        }
+    }
+
+    /// Shows an error and returns false if the All tab is selected,
+    /// for actions that apply to a single server
+    fn require_single_server(&mut self) -> bool {
+        if self.all_tab_selected() {
+            self.set_status_message(Err("Select a server first".to_string()));
+            return false;
+        }
+        true
+    }
+
+    /// Swaps the selected server with the one before it
+    pub fn move_server_left(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        if self.selected_server_index > 0 {
+            self.servers
+                .swap(self.selected_server_index, self.selected_server_index - 1);
+            self.selected_server_index -= 1;
+        }
+    }
+
+    /// Swaps the selected server with the one after it
+    pub fn move_server_right(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        if self.selected_server_index + 1 < self.servers.len() {
+            self.servers
+                .swap(self.selected_server_index, self.selected_server_index + 1);
+            self.selected_server_index += 1;
+        }
+    }
+
+    /// Writes the current servers and settings to `path`, recording the outcome for the help bar
+    pub fn save(&mut self, path: &Path) {
+        let result = save_config(self, path)
+            .map(|_| format!("Saved configuration to {}", path.display()))
+            .map_err(|error| format!("Failed to save configuration: {}", error));
+        self.set_status_message(result);
+    }
+
+    pub fn toggle_query_log(&mut self) {
+        self.view = match self.view {
+            View::QueryLog => View::Overview,
+            _ => View::QueryLog,
+        };
+        self.update_query_log_following();
+    }
+
+    /// Starts or stops following the query log
+    pub fn toggle_follow_query_log(&mut self) {
+        self.follow_query_log = !self.follow_query_log;
+        self.update_query_log_following();
+    }
+
+    /// Updates the servers of the query log often while it is followed and shown,
+    /// starting an update when following starts
+    fn update_query_log_following(&mut self) {
+        let shown: Vec<bool> = (0..self.servers.len())
+            .map(|index| self.shows_server(index))
+            .collect();
+        for (server, shown) in self.servers.iter_mut().zip(shown) {
+            let follow_queries = self.follow_query_log && self.view == View::QueryLog && shown;
+            if server.follow_queries != follow_queries {
+                server.follow_queries = follow_queries;
+                if follow_queries {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Switches to the page of F key `number`, counting from 1. The Lists page is only shown
+    /// for a single server.
+    pub fn select_page_number(&mut self, number: u8) {
+        let page = match Page::ALL.get(usize::from(number).wrapping_sub(1)) {
+            Some(page) => *page,
+            None => return,
+        };
+        if matches!(page, Page::Lists | Page::Adlists | Page::LocalDns)
+            && !self.require_single_server()
+        {
+            return;
+        }
+        self.view = page.view();
+        self.selected_client = None;
+        self.update_domain_list_fetching();
+        self.update_adlist_fetching();
+        self.update_local_record_fetching();
+        self.update_long_term_fetching();
+        self.update_network_devices_fetching();
+        self.update_top_clients_blocked_fetching();
+        self.update_filtered_queries_fetching();
+    }
+
+    pub fn toggle_alerts(&mut self) {
+        self.view = match self.view {
+            View::Alerts => View::Overview,
+            _ => View::Alerts,
+        };
+    }
+
+    pub fn toggle_split(&mut self) {
+        self.view = match self.view {
+            View::Split => View::Overview,
+            _ => View::Split,
+        };
+    }
+
+    pub fn toggle_diagnostics(&mut self) {
+        self.view = match self.view {
+            View::Diagnostics => View::Overview,
+            _ => View::Diagnostics,
+        };
+    }
+
+    pub fn toggle_long_term(&mut self) {
+        self.view = match self.view {
+            View::LongTerm => View::Overview,
+            _ => View::LongTerm,
+        };
+        self.update_long_term_fetching();
+    }
+
+    /// Shows the previous, shorter period of the Long term view
+    pub fn shorten_long_term_period(&mut self) {
+        let index = self.long_term_period_index();
+        self.long_term_period = LongTermPeriod::ALL[index.saturating_sub(1)];
+        self.update_long_term_fetching();
+    }
+
+    /// Shows the next, longer period of the Long term view
+    pub fn lengthen_long_term_period(&mut self) {
+        let index = self.long_term_period_index();
+        self.long_term_period = LongTermPeriod::ALL[(index + 1).min(LongTermPeriod::ALL.len() - 1)];
+        self.update_long_term_fetching();
+    }
+
+    fn long_term_period_index(&self) -> usize {
+        LongTermPeriod::ALL
+            .iter()
+            .position(|period| *period == self.long_term_period)
+            .unwrap_or_default()
+    }
+
+    /// Fetches the daily totals of the selected server, or every server on the All tab,
+    /// while the Long term view is shown
+    fn update_long_term_fetching(&mut self) {
+        let shown: Vec<bool> = (0..self.servers.len())
+            .map(|index| self.shows_server(index))
+            .collect();
+        let days = self.long_term_period.days();
+        for (server, shown) in self.servers.iter_mut().zip(shown) {
+            let fetch_long_term_days = (self.view == View::LongTerm && shown).then_some(days);
+            server.long_term_history = match (&self.history_directory, fetch_long_term_days) {
+                (Some(directory), Some(_)) => Some(History::new(directory, &server.name)),
+                _ => None,
+            };
+            if server.fetch_long_term_days != fetch_long_term_days {
+                server.fetch_long_term_days = fetch_long_term_days;
+                if fetch_long_term_days.is_some() {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Fetches the network table of the selected server, or every server on the All tab,
+    /// while the Network view is shown
+    fn update_network_devices_fetching(&mut self) {
+        let shown: Vec<bool> = (0..self.servers.len())
+            .map(|index| self.shows_server(index))
+            .collect();
+        for (server, shown) in self.servers.iter_mut().zip(shown) {
+            let fetch_network_devices = self.view == View::Network && shown;
+            if server.fetch_network_devices != fetch_network_devices {
+                server.fetch_network_devices = fetch_network_devices;
+                if fetch_network_devices {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Opens the Client view of the client selected in Top Clients, or the domain popup of the
+    /// domain selected in Top Queries or Top Ads
+    pub fn open_selected_row(&mut self) {
+        let selected = match self.statistics_table_state.selected() {
+            Some(selected) => selected,
+            None => return,
+        };
+        let name = match self
+            .statistics_rows()
+            .into_iter()
+            .nth(selected)
+            .and_then(|row| row.into_iter().next())
+        {
+            Some(name) => name,
+            None => return,
+        };
+        match self.focused_table {
+            StatisticsTable::Clients => {
+                self.selected_client = Some(name);
+                self.view = View::Client;
+                self.update_top_clients_blocked_fetching();
+            }
+            StatisticsTable::Queries | StatisticsTable::Ads => self.selected_domain = Some(name),
+        }
+        self.update_filtered_queries_fetching();
+    }
+
+    /// Domain or client address selected in the current view with how it is drawn, the
+    /// domain of the domain popup while it is open
+    fn selected_value(&self) -> Option<(String, String)> {
+        if let Some(domain) = &self.selected_domain {
+            return Some((domain.clone(), self.shown_domain(domain)));
+        }
+        let selected_domain = |domain: &str| Some((domain.to_string(), self.shown_domain(domain)));
+        match self.view {
+            View::Lists => {
+                let domains = self.selected_list_domains()?;
+                selected_domain(&domains.get(self.list_table_state.selected()?)?.domain)
+            }
+            View::Adlists => {
+                let adlists = self.selected_adlists()?;
+                let adlist = adlists.get(self.adlist_table_state.selected()?)?;
+                Some((adlist.address.clone(), adlist.address.clone()))
+            }
+            View::LocalDns => {
+                let records = self.selected_local_records()?;
+                selected_domain(&records.get(self.record_table_state.selected()?)?.domain)
+            }
+            View::Client => {
+                let client = self.selected_client.as_ref()?;
+                Some((
+                    split_client(client).1.to_string(),
+                    self.shown_client(client),
+                ))
+            }
+            _ => {
+                if !matches!(self.focused_panel, Panel::Statistics(_)) {
+                    return None;
+                }
+                let selected = self.statistics_table_state.selected()?;
+                let name = self
+                    .statistics_rows()
+                    .into_iter()
+                    .nth(selected)?
+                    .into_iter()
+                    .next()?;
+                match self.focused_table {
+                    StatisticsTable::Clients => {
+                        Some((split_client(&name).1.to_string(), self.shown_client(&name)))
+                    }
+                    StatisticsTable::Queries | StatisticsTable::Ads => selected_domain(&name),
+                }
+            }
+        }
+    }
+
+    /// Copies the selected domain or client address to the clipboard
+    pub fn copy_selected(&mut self) {
+        let (value, shown) = match self.selected_value() {
+            Some(selected) => selected,
+            None => return,
+        };
+        let message =
+            clipboard::copy(&value).map(|()| format!("Copied {} to the clipboard", shown));
+        self.set_status_message(message);
+    }
+
+    pub fn close_domain_popup(&mut self) {
+        self.selected_domain = None;
+        self.update_filtered_queries_fetching();
+    }
+
+    /// What the domain popup shows, `None` while it is closed
+    pub fn domain_details(&self) -> Option<DomainDetails<'_>> {
+        let domain = self.selected_domain.as_ref()?;
+        let data = self.displayed_data();
+        let count_of = |top: Option<&HashMap<String, u64>>| top?.get(domain).copied();
+        Some(DomainDetails {
+            domain: domain.clone(),
+            permitted: count_of(
+                data.top_items
+                    .as_ref()
+                    .map(|top_items| &top_items.top_queries),
+            ),
+            blocked: count_of(data.top_items.as_ref().map(|top_items| &top_items.top_ads)),
+            recent_queries: self.filtered_queries(),
+        })
+    }
+
+    pub fn close_client(&mut self) {
+        self.view = View::Overview;
+        self.selected_client = None;
+        self.update_top_clients_blocked_fetching();
+        self.update_filtered_queries_fetching();
+    }
+
+    /// Fetches the queries of the domain of the domain popup, or else of the client of the
+    /// Client view, from the selected server or every server on the All tab
+    fn update_filtered_queries_fetching(&mut self) {
+        let shown: Vec<bool> = (0..self.servers.len())
+            .map(|index| self.shows_server(index))
+            .collect();
+        let filter = match (&self.selected_domain, &self.selected_client) {
+            (Some(domain), _) => Some(QueryFilter::Domain(domain.clone())),
+            (None, Some(client)) if self.view == View::Client => {
+                Some(QueryFilter::Client(split_client(client).1.to_string()))
+            }
+            _ => None,
+        };
+        for (server, shown) in self.servers.iter_mut().zip(shown) {
+            let fetch_filtered_queries = filter.clone().filter(|_| shown);
+            if server.fetch_filtered_queries != fetch_filtered_queries {
+                // The queries of the previous filter must not be shown for the new one
+                server.last_data.filtered_queries = None;
+                server.fetch_filtered_queries = fetch_filtered_queries;
+                if server.fetch_filtered_queries.is_some() {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Servers whose data the selected tab shows
+    pub fn displayed_servers(&self) -> Vec<&PiHoleServer> {
+        (0..self.servers.len())
+            .filter(|index| self.shows_server(*index))
+            .map(|index| &self.servers[index])
+            .collect()
+    }
+
+    /// Change of a count of the summary since the previous update, added up over the displayed
+    /// servers. `None` before any of them has updated twice.
+    pub fn summary_change(&self, count: impl Fn(&Summary) -> &str) -> Option<i64> {
+        self.displayed_servers()
+            .into_iter()
+            .filter_map(|server| {
+                let previous = parse_count(count(server.previous_summary.as_ref()?));
+                let current = parse_count(count(server.last_data.summary.as_ref()?));
+                Some(current as i64 - previous as i64)
+            })
+            .reduce(|total, change| total + change)
+    }
+
+    /// Percentage of the queries of the displayed servers answered from the cache between
+    /// updates, oldest first. Servers are added up from the newest, as for `query_rates`.
+    pub fn cache_hit_ratios(&self) -> Vec<f64> {
+        let mut samples: Vec<(u64, u64)> = Vec::new();
+        for server in self.displayed_servers() {
+            for (index, (cached, total)) in server.cache_samples.iter().rev().enumerate() {
+                match samples.get_mut(index) {
+                    Some(sum) => *sum = (sum.0 + cached, sum.1 + total),
+                    None => samples.push((*cached, *total)),
+                }
+            }
+        }
+        samples
+            .into_iter()
+            .rev()
+            .map(|(cached, total)| cached as f64 * 100.0 / total as f64)
+            .collect()
+    }
+
+    /// Queries per minute of the displayed servers, oldest first. The rates of several servers
+    /// are added up from the newest, as they update at different times.
+    pub fn query_rates(&self) -> Vec<f64> {
+        let mut rates: Vec<f64> = Vec::new();
+        for server in self.displayed_servers() {
+            for (index, rate) in server.query_rates.iter().rev().enumerate() {
+                match rates.get_mut(index) {
+                    Some(total) => *total += rate,
+                    None => rates.push(*rate),
+                }
+            }
+        }
+        rates.reverse();
+        rates
+    }
+
+    /// Filtered queries of the displayed servers with the name of their server, newest first.
+    /// `None` until a server has reported them.
+    fn filtered_queries(&self) -> Option<Vec<(&str, &Query)>> {
+        let servers = self.displayed_servers();
+        if servers
+            .iter()
+            .all(|server| server.last_data.filtered_queries.is_none())
+        {
+            return None;
+        }
+        let mut queries: Vec<(&str, &Query)> = servers
+            .iter()
+            .filter_map(|server| {
+                Some((
+                    server.name.as_str(),
+                    server.last_data.filtered_queries.as_ref()?,
+                ))
+            })
+            .flat_map(|(name, queries)| queries.iter().map(move |query| (name, query)))
+            .collect();
+        queries.sort_by_key(|(_, query)| std::cmp::Reverse(query.timestring));
+        Some(queries)
+    }
+
+    /// What the Client view shows, `None` outside of it
+    pub fn client_details(&self) -> Option<ClientDetails> {
+        let client = self.selected_client.as_ref()?;
+        let (name, address) = split_client(client);
+        let name = name.or_else(|| self.client_name(address));
+        let data = self.displayed_data();
+        let count_of = |top_sources: &Option<TopClients>| {
+            top_sources
+                .as_ref()
+                .and_then(|top_sources| top_sources.top_sources.get(client).copied())
+        };
+        let filtered_queries = self.filtered_queries();
+        let fetched = filtered_queries.is_some();
+        let queries: Vec<&Query> = filtered_queries
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, query)| query)
+            .collect();
+
+        let mut domains = HashMap::new();
+        let mut hourly = BTreeMap::new();
+        let now = Utc::now().timestamp();
+        // The last 24 hours, including the current one
+        let first_hour = (now - 23 * 3600).div_euclid(3600) * 3600;
+        for hour in (first_hour..=now).step_by(3600) {
+            hourly.insert(hour, 0);
+        }
+        for query in &queries {
+            *domains.entry(query.domain.clone()).or_insert(0) += 1;
+            let timestamp = query.timestring.and_utc().timestamp();
+            if let Some(count) = hourly.get_mut(&(timestamp.div_euclid(3600) * 3600)) {
+                *count += 1;
+            }
+        }
+        Some(ClientDetails {
+            name: name.map(str::to_string),
+            address: address.to_string(),
+            queries: count_of(&data.top_sources),
+            blocked: count_of(&data.top_sources_blocked),
+            recent_queries: fetched.then_some(queries.len()),
+            recent_blocked: queries
+                .iter()
+                .filter(|query| is_blocked(&query.status))
+                .count(),
+            top_domains: order_convert_string_num_map(&domains),
+            hourly: hourly.into_iter().collect(),
+        })
+    }
+
+    pub fn toggle_lists(&mut self) {
+        if self.view != View::Lists && !self.require_single_server() {
+            return;
+        }
+        self.view = match self.view {
+            View::Lists => View::Overview,
+            _ => View::Lists,
+        };
+        self.update_domain_list_fetching();
+    }
+
+    /// Fetches the lists of the selected server only while they are shown,
+    /// starting an update when they are first needed
+    fn update_domain_list_fetching(&mut self) {
+        let all_tab_selected = self.all_tab_selected();
+        for (index, server) in self.servers.iter_mut().enumerate() {
+            let fetch_domain_lists = self.view == View::Lists
+                && !all_tab_selected
+                && index == self.selected_server_index;
+            if server.fetch_domain_lists != fetch_domain_lists {
+                server.fetch_domain_lists = fetch_domain_lists;
+                if fetch_domain_lists {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Fetches the adlists of the selected server only while they are shown
+    fn update_adlist_fetching(&mut self) {
+        let all_tab_selected = self.all_tab_selected();
+        for (index, server) in self.servers.iter_mut().enumerate() {
+            let fetch_adlists = self.view == View::Adlists
+                && !all_tab_selected
+                && index == self.selected_server_index;
+            if server.fetch_adlists != fetch_adlists {
+                server.fetch_adlists = fetch_adlists;
+                if fetch_adlists {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Fetches the Local DNS records of the selected server only while they are shown
+    fn update_local_record_fetching(&mut self) {
+        let all_tab_selected = self.all_tab_selected();
+        for (index, server) in self.servers.iter_mut().enumerate() {
+            let fetch_local_records = self.view == View::LocalDns
+                && !all_tab_selected
+                && index == self.selected_server_index;
+            if server.fetch_local_records != fetch_local_records {
+                server.fetch_local_records = fetch_local_records;
+                if fetch_local_records {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    /// Fetches the activity of clients from every server while it is charted, so switching
+    /// tabs does not wait for an update
+    fn update_clients_over_time_fetching(&mut self) {
+        let fetch_clients_over_time =
+            self.view == View::Overview && self.over_time_chart == OverTimeChart::Clients;
+        for server in &mut self.servers {
+            if server.fetch_clients_over_time != fetch_clients_over_time {
+                server.fetch_clients_over_time = fetch_clients_over_time;
+                if fetch_clients_over_time {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    fn update_top_clients_blocked_fetching(&mut self) {
+        // The Client view shows the blocked queries of its client
+        let fetch_top_clients_blocked =
+            (self.view == View::Overview && self.top_clients_blocked) || self.view == View::Client;
+        for server in &mut self.servers {
+            if server.fetch_top_clients_blocked != fetch_top_clients_blocked {
+                server.fetch_top_clients_blocked = fetch_top_clients_blocked;
+                if fetch_top_clients_blocked {
+                    server.refresh();
+                }
+            }
+        }
+    }
+
+    fn update_top_items_count(&mut self) {
+        for server in &mut self.servers {
+            if server.top_items_count != self.top_items_count {
+                server.top_items_count = self.top_items_count;
+                server.refresh();
+            }
+        }
+    }
+
+    /// Looks up client names in the network table unless only addresses are shown
+    fn update_client_name_resolving(&mut self) {
+        let resolve = self.client_names != ClientNames::Address;
+        for server in &mut self.servers {
+            server.resolve_client_names = resolve;
+        }
+    }
+
+    /// Host name of a client address, as learnt by any of the servers
+    pub fn client_name(&self, address: &str) -> Option<&str> {
+        self.servers
+            .iter()
+            .find_map(|server| server.client_names.get(address))
+            .map(String::as_str)
+    }
+
+    /// Updates every server on its own schedule, not only the selected one
+    pub fn on_tick(&mut self) {
+        self.check_discovery();
+        self.check_gravity();
+        self.check_actions();
+        self.update_domain_list_fetching();
+        self.update_adlist_fetching();
+        self.update_local_record_fetching();
+        self.update_clients_over_time_fetching();
+        self.update_top_clients_blocked_fetching();
+        self.update_long_term_fetching();
+        self.update_network_devices_fetching();
+        self.update_query_log_following();
+        self.update_filtered_queries_fetching();
+        self.update_top_items_count();
+        self.update_client_name_resolving();
+        let mut answered = Vec::new();
+        for server in &mut self.servers {
+            if let Some(message) = server.check_blocking_change() {
+                self.status_message = Some((Instant::now(), message));
+            }
+            for (change, result) in server.check_changes() {
+                answered.push((Arc::clone(&server.api_config), change, result));
+            }
+            if server.check_background_update(self.outage_threshold) {
+                if let Some(directory) = &self.history_directory {
+                    if let Err(error) = server.record_history(directory) {
+                        log::warn!("{}", error);
+                        self.status_message = Some((Instant::now(), Err(error)));
+                    }
+                }
+            }
+            // Refresh shortly after a timed disable ends so the status follows the server
+            if let Some(resumes_at) = server.blocking_resumes_at {
+                if Instant::now() > resumes_at + DISABLE_EXPIRY_GRACE {
+                    server.blocking_resumes_at = None;
+                    server.refresh();
+                }
+            }
+            if server.update_due() && !self.paused {
+                server.run_background_update();
+            }
+        }
+        for (api_config, change, result) in answered {
+            self.finish_change(api_config, change, result);
+        }
+        if self.has_all_tab() {
+            self.aggregate = aggregate_data(&self.displayed_servers());
+        }
+        let raised = self.alerts.evaluate(&self.servers);
+        let history = &self.alerts.history;
+        for alert in &history[history.len() - raised..] {
+            if self.notifications {
+                notify::send(alert);
+            }
+            self.webhooks.send(alert);
+        }
+    }
+
+    /// Stops or restarts the scheduled updates, Space still updates while paused
+    pub fn toggle_paused(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Updates the selected server now, or every server of the group on the All tab
+    pub fn on_space(&mut self) {
+        for index in 0..self.servers.len() {
+            if self.shows_server(index) {
+                self.servers[index].run_background_update();
+            }
+        }
+    }
+
+    /// Remembers the new size of the terminal, the layout follows on the next draw
+    pub fn on_resize(&mut self, width: u16) {
+        self.terminal_width = width;
+    }
+
+    /// Periods combined into each bar or point of the charts. The bar chart combines more
+    /// than `graph_squash_factor` when the chart window would not fit the terminal otherwise.
+    pub fn chart_squash_factor(&self) -> usize {
+        if self.chart_mode != ChartMode::Bar || self.terminal_width == 0 {
+            return self.graph_squash_factor;
+        }
+        // Inside the borders, every bar but the last is followed by a gap
+        let bars = (self.terminal_width.saturating_sub(2) + BAR_GAP) / (BAR_WIDTH + BAR_GAP);
+        let periods = self.chart_window.seconds() / OVER_TIME_PERIOD_SECONDS;
+        let fitting = (periods as usize).div_ceil(usize::from(bars.max(1)));
+        self.graph_squash_factor.max(fitting)
+    }
+
+    pub fn on_z(&mut self) {
+        if self.graph_squash_factor > 1 {
+            self.graph_squash_factor /= 2;
+        }
+    }
+
+    pub fn on_x(&mut self) {
+        if self.graph_squash_factor < usize::MAX {
+            self.graph_squash_factor *= 2;
+        }
+    }
+
+    /// Pans the chart one bar further into the past, limited when drawn to the data available
+    pub fn pan_chart_back(&mut self) {
+        self.chart_pan = self.chart_pan.saturating_add(1);
+    }
+
+    /// Pans the chart one bar towards the newest data
+    pub fn pan_chart_forward(&mut self) {
+        self.chart_pan = self.chart_pan.saturating_sub(1);
+    }
+
+    /// Switches the chart between the queries and the activity of each client
+    pub fn toggle_over_time_chart(&mut self) {
+        self.over_time_chart = match self.over_time_chart {
+            OverTimeChart::Queries => OverTimeChart::Clients,
+            OverTimeChart::Clients => OverTimeChart::Queries,
+        };
+        self.update_clients_over_time_fetching();
+    }
+
+    /// Fetches longer top lists, up to the largest of `TOP_ITEMS_COUNTS`
+    pub fn increase_top_items_count(&mut self) {
+        let count = TOP_ITEMS_COUNTS
+            .iter()
+            .copied()
+            .find(|count| *count > self.top_items_count)
+            .unwrap_or(self.top_items_count);
+        self.set_top_items_count(count);
+    }
+
+    /// Fetches shorter top lists, down to the smallest of `TOP_ITEMS_COUNTS`
+    pub fn decrease_top_items_count(&mut self) {
+        let count = TOP_ITEMS_COUNTS
+            .iter()
+            .rev()
+            .copied()
+            .find(|count| *count < self.top_items_count)
+            .unwrap_or(self.top_items_count);
+        self.set_top_items_count(count);
+    }
+
+    /// Updates more often, down to the shortest of `UPDATE_DELAYS`
+    pub fn decrease_update_delay(&mut self) {
+        let delay = UPDATE_DELAYS
+            .iter()
+            .rev()
+            .copied()
+            .find(|delay| *delay < self.update_delay)
+            .unwrap_or(self.update_delay);
+        self.set_update_delay(delay);
+    }
+
+    /// Updates less often, up to the longest of `UPDATE_DELAYS`
+    pub fn increase_update_delay(&mut self) {
+        let delay = UPDATE_DELAYS
+            .iter()
+            .copied()
+            .find(|delay| *delay > self.update_delay)
+            .unwrap_or(self.update_delay);
+        self.set_update_delay(delay);
+    }
+
+    /// Servers with an update delay of their own keep it
+    fn set_update_delay(&mut self, delay: u64) {
+        self.update_delay = delay;
+        for server in &mut self.servers {
+            if server.config.update_delay.is_none() {
+                server.update_delay = Duration::from_millis(delay);
+            }
+        }
+        self.set_status_message(Ok(format!(
+            "Updating every {}",
+            format_duration(Duration::from_millis(delay))
+        )));
+    }
+
+    fn set_top_items_count(&mut self, count: u32) {
+        self.top_items_count = count;
+        self.update_top_items_count();
+        self.set_status_message(Ok(format!("Fetching the top {} of each list", count)));
+    }
+
+    /// Switches Top Clients between all queries and only blocked queries
+    pub fn toggle_top_clients_blocked(&mut self) {
+        self.top_clients_blocked = !self.top_clients_blocked;
+        self.update_top_clients_blocked_fetching();
+    }
+
+    /// Shows less of the past in the chart, down to the last hour
+    pub fn shorten_chart_window(&mut self) {
+        let index = self.chart_window_index();
+        self.chart_window = ChartWindow::ALL[index.saturating_sub(1)];
+    }
+
+    /// Shows more of the past in the chart, up to the last 24 hours
+    pub fn lengthen_chart_window(&mut self) {
+        let index = self.chart_window_index();
+        self.chart_window = ChartWindow::ALL[(index + 1).min(ChartWindow::ALL.len() - 1)];
+    }
+
+    fn chart_window_index(&self) -> usize {
+        ChartWindow::ALL
+            .iter()
+            .position(|window| *window == self.chart_window)
+            .unwrap_or_default()
+    }
+
+    /// Switches the queries chart between bars and a line
+    pub fn toggle_chart_mode(&mut self) {
+        self.chart_mode = match self.chart_mode {
+            ChartMode::Bar => ChartMode::Line,
+            ChartMode::Line => ChartMode::Bar,
+        };
+    }
+
+    /// Switches to the next theme preset, keeping the overrides of the config
+    pub fn next_theme_preset(&mut self) {
+        self.theme_config.preset = self.theme_config.preset.next();
+        match Theme::new(&self.theme_config) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.set_status_message(Ok(format!(
+                    "Theme {}, press Ctrl+S to keep it",
+                    self.theme_config.preset.name()
+                )));
+            }
+            Err(error) => self.set_status_message(Err(error)),
+        }
+    }
+
+    /// Masks or shows again the domains, clients and addresses
+    pub fn toggle_redact(&mut self) {
+        self.redact = !self.redact;
+        let message = if self.redact {
+            "Domains, clients and addresses are masked, press Ctrl+S to start masked"
+        } else {
+            "Domains, clients and addresses are shown"
+        };
+        self.set_status_message(Ok(message.to_string()));
+    }
+
+    /// `domain` as drawn, partly masked while redacting
+    pub fn shown_domain(&self, domain: &str) -> String {
+        if self.redact {
+            redact::domain(domain)
+        } else {
+            domain.to_string()
+        }
+    }
+
+    /// `count` as drawn, compact if configured or with `auto` in a narrow terminal
+    pub fn count(&self, count: u64) -> String {
+        self.numbers
+            .format(count, self.terminal_width < numbers::COMPACT_WIDTH)
+    }
+
+    /// `count` as drawn in less than `width` columns, compact if it does not fit otherwise
+    pub fn count_within(&self, count: u64, width: usize) -> Option<String> {
+        self.numbers
+            .fitting(count, width, self.terminal_width < numbers::COMPACT_WIDTH)
+    }
+
+    /// Count of the summary as drawn, the v5 API sends them already formatted
+    pub fn summary_count(&self, count: &str) -> String {
+        self.count(parse_count(count))
+    }
+
+    /// Client listed as "name|address" or "address" as drawn, named as configured by
+    /// `client_names` and partly masked while redacting
+    pub fn shown_client(&self, client: &str) -> String {
+        let (name, address) = split_client(client);
+        // Clients the server already lists by host name have no address to show
+        let (name, address) = match name {
+            None if address.parse::<IpAddr>().is_err() => (Some(address), None),
+            None => (self.client_name(address), Some(address)),
+            Some(name) => (Some(name), Some(address)),
+        };
+        let name = name.map(|name| {
+            if self.redact {
+                redact::hostname(name)
+            } else {
+                name.to_string()
+            }
+        });
+        let address = address.map(|address| self.shown_address(address));
+        match (self.client_names, name, address) {
+            (ClientNames::Address, _, Some(address)) => address,
+            (ClientNames::Both, Some(name), Some(address)) => format!("{} ({})", name, address),
+            (_, Some(name), _) => name,
+            (_, None, address) => address.unwrap_or_default(),
+        }
+    }
+
+    /// IP address or host name as drawn, partly masked while redacting
+    pub fn shown_address(&self, address: &str) -> String {
+        if self.redact {
+            redact::address(address)
+        } else {
+            address.to_string()
+        }
+    }
+
+    /// Target of a Local DNS record as drawn, an address or a domain by `record_type`
+    pub fn shown_record_target(&self, record_type: LocalRecordType, target: &str) -> String {
+        match record_type {
+            LocalRecordType::Dns => self.shown_address(target),
+            LocalRecordType::Cname => self.shown_domain(target),
+        }
+    }
+
+    pub fn shown_mac(&self, mac: &str) -> String {
+        if self.redact {
+            redact::mac(mac)
+        } else {
+            mac.to_string()
+        }
+    }
+
+    /// Shows `message` in place of the help bar, in red if it is an error
+    pub fn set_status_message(&mut self, message: Result<String, String>) {
+        if let Err(error) = &message {
+            log::warn!("{}", error);
+        }
+        self.status_message = Some((Instant::now(), message));
+    }
+
+    pub fn on_e(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        let server = &mut self.servers[self.selected_server_index];
+        if server.api_config.is_authenticated() {
+            server.change_blocking(BlockingChange::Enable);
+        } else {
+            let message = format!(
+                "An API key is required to enable blocking on {}",
+                server.name
+            );
+            self.set_status_message(Err(message));
+        }
+    }
+
+    /// Opens the disable prompt with the last used duration
+    pub fn on_d(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        self.open_input_prompt(PromptKind::DisableDuration, self.disable_duration.clone());
+    }
+
+    fn open_input_prompt(&mut self, kind: PromptKind, input: String) {
+        self.input_prompt = Some(InputPrompt {
+            kind,
+            input,
+            error: None,
+        });
+    }
+
+    /// Closes the prompt without using it, cancelling a filter clears it
+    pub fn close_input_prompt(&mut self) {
+        if let Some(InputPrompt {
+            kind: PromptKind::Filter,
+            ..
+        }) = self.input_prompt.take()
+        {
+            self.set_statistics_filter(String::new());
+        }
+    }
+
+    pub fn input_prompt_input(&mut self, c: char) {
+        if let Some(prompt) = &mut self.input_prompt {
+            prompt.input.push(c);
+            prompt.error = None;
+        }
+        self.update_live_filter();
+    }
+
+    pub fn input_prompt_backspace(&mut self) {
+        if let Some(prompt) = &mut self.input_prompt {
+            prompt.input.pop();
+            prompt.error = None;
+        }
+        self.update_live_filter();
+    }
+
+    /// Applies the filter being typed so the table narrows with each key
+    fn update_live_filter(&mut self) {
+        if let Some(InputPrompt {
+            kind: PromptKind::Filter,
+            input,
+            ..
+        }) = &self.input_prompt
+        {
+            let filter = input.clone();
+            self.set_statistics_filter(filter);
+        }
+    }
+
+    /// Opens the filter of the focused overview table
+    pub fn on_slash(&mut self) {
+        self.open_input_prompt(PromptKind::Filter, self.statistics_filter.clone());
+    }
+
+    fn set_statistics_filter(&mut self, filter: String) {
+        if filter != self.statistics_filter {
+            self.statistics_filter = filter;
+            // The selected row may no longer be shown
+            if self.statistics_table_state.selected().is_some() {
+                self.statistics_table_state = TableState::default();
+                self.next_statistics_row();
+            }
+        }
+    }
+
+    /// Uses the text typed into the prompt, leaving the prompt open with an error if it is invalid
+    pub fn confirm_input_prompt(&mut self) {
+        let prompt = match &mut self.input_prompt {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let input = prompt.input.trim().to_string();
+        match prompt.kind {
+            PromptKind::DisableDuration => match parse_duration(&input) {
+                Ok(seconds) => {
+                    self.input_prompt = None;
+                    self.disable_duration = input;
+                    // Asked again as d is easily pressed by accident
+                    let server = &self.servers[self.selected_server_index].name;
+                    self.confirmation = Some(Confirmation {
+                        message: match seconds {
+                            0 => format!("Disable blocking on {} permanently?", server),
+                            _ => format!(
+                                "Disable blocking on {} for {}?",
+                                server,
+                                format_duration(Duration::from_secs(seconds))
+                            ),
+                        },
+                        action: PendingAction::Disable(seconds),
+                    });
+                }
+                Err(error) => prompt.error = Some(error),
+            },
+            PromptKind::Filter => self.input_prompt = None,
+            PromptKind::AddDomain(list) => {
+                if input.is_empty() {
+                    prompt.error = Some("A domain is required".to_string());
+                } else {
+                    self.input_prompt = None;
+                    self.add_domain(self.selected_server_index, &input, list);
+                }
+            }
+            PromptKind::AddLocalRecord(record_type) => {
+                match parse_local_record(record_type, &input) {
+                    Ok(record) => {
+                        self.input_prompt = None;
+                        self.add_local_record(record_type, &record);
+                    }
+                    Err(error) => prompt.error = Some(error),
+                }
+            }
+            PromptKind::AddAdlist => {
+                if !input.starts_with("http://") && !input.starts_with("https://") {
+                    prompt.error = Some("The URL must start with http:// or https://".to_string());
+                } else {
+                    self.input_prompt = None;
+                    self.add_adlist(&input);
+                }
+            }
+        }
+    }
+
+    /// Disables the selected server for `seconds`, or permanently if zero
+    fn disable(&mut self, seconds: u64) {
+        let server = &mut self.servers[self.selected_server_index];
+        if server.api_config.is_authenticated() {
+            server.change_blocking(BlockingChange::Disable(seconds));
+        } else {
+            let message = format!(
+                "An API key is required to disable blocking on {}",
+                server.name
+            );
+            self.set_status_message(Err(message));
+        }
+    }
+
+    /// Highlights the next list in the Lists view
+    pub fn next_list(&mut self) {
+        let index = (self.selected_list.index() + 1) % DomainList::ALL.len();
+        self.select_list(DomainList::ALL[index]);
+    }
+
+    /// Highlights the previous list in the Lists view
+    pub fn previous_list(&mut self) {
+        let index =
+            (self.selected_list.index() + DomainList::ALL.len() - 1) % DomainList::ALL.len();
+        self.select_list(DomainList::ALL[index]);
+    }
+
+    fn select_list(&mut self, list: DomainList) {
+        self.selected_list = list;
+        self.list_table_state = TableState::default();
+    }
+
+    /// Domains of the highlighted list of the selected server, if they have been fetched
+    pub fn selected_list_domains(&self) -> Option<&[CustomListDomainDetails]> {
+        if self.all_tab_selected() {
+            return None;
+        }
+        self.servers[self.selected_server_index]
+            .last_data
+            .domain_lists
+            .as_ref()
+            .and_then(|lists| lists.get(self.selected_list.index()))
+            .map(Vec::as_slice)
+    }
+
+    /// Moves the selection of the highlighted list by `rows`
+    fn move_list_selection(&mut self, rows: isize) {
+        let length = self.selected_list_domains().map_or(0, <[_]>::len);
+        move_selection(&mut self.list_table_state, length, rows);
+    }
+
+    pub fn next_list_domain(&mut self) {
+        self.move_list_selection(1);
+    }
+
+    pub fn previous_list_domain(&mut self) {
+        self.move_list_selection(-1);
+    }
+
+    pub fn first_list_domain(&mut self) {
+        self.move_list_selection(isize::MIN);
+    }
+
+    pub fn last_list_domain(&mut self) {
+        self.move_list_selection(isize::MAX);
+    }
+
+    pub fn next_list_page(&mut self) {
+        self.move_list_selection(self.list_page_size());
+    }
+
+    pub fn previous_list_page(&mut self) {
+        self.move_list_selection(-self.list_page_size());
+    }
+
+    /// Number of domains the highlighted list showed when it was last drawn
+    fn list_page_size(&self) -> isize {
+        let area = self
+            .click_areas
+            .lists
+            .iter()
+            .find(|(list, _)| *list == self.selected_list)
+            .map(|(_, area)| *area);
+        page_size(area)
+    }
+
+    /// Opens a prompt for a domain to add to the highlighted list
+    pub fn on_add_domain(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        self.open_input_prompt(PromptKind::AddDomain(self.selected_list), String::new());
+    }
+
+    /// Asks whether to remove the selected domain from the highlighted list
+    pub fn on_remove_domain(&mut self) {
+        let list = self.selected_list;
+        let domain = match (
+            self.selected_list_domains(),
+            self.list_table_state.selected(),
+        ) {
+            (Some(domains), Some(selected)) => match domains.get(selected) {
+                Some(entry) => entry.domain.clone(),
+                None => return,
+            },
+            _ => return,
+        };
+        self.confirmation = Some(Confirmation {
+            message: format!(
+                "Remove {} from the {}?",
+                self.shown_domain(&domain),
+                list.title().to_lowercase()
+            ),
+            action: PendingAction::RemoveDomain { domain, list },
+        });
+    }
+
+    /// Adlists of the selected server, if they have been fetched
+    pub fn selected_adlists(&self) -> Option<&[Adlist]> {
+        if self.all_tab_selected() {
+            return None;
+        }
+        self.servers[self.selected_server_index]
+            .last_data
+            .adlists
+            .as_deref()
+    }
+
+    fn move_adlist_selection(&mut self, rows: isize) {
+        let length = self.selected_adlists().map_or(0, <[_]>::len);
+        move_selection(&mut self.adlist_table_state, length, rows);
+    }
+
+    pub fn next_adlist(&mut self) {
+        self.move_adlist_selection(1);
+    }
+
+    pub fn previous_adlist(&mut self) {
+        self.move_adlist_selection(-1);
+    }
+
+    pub fn first_adlist(&mut self) {
+        self.move_adlist_selection(isize::MIN);
+    }
+
+    pub fn last_adlist(&mut self) {
+        self.move_adlist_selection(isize::MAX);
+    }
+
+    pub fn next_adlist_page(&mut self) {
+        self.move_adlist_selection(page_size(self.click_areas.adlists));
+    }
+
+    pub fn previous_adlist_page(&mut self) {
+        self.move_adlist_selection(-page_size(self.click_areas.adlists));
+    }
+
+    /// Opens a prompt for the URL of an adlist to add to the selected server
+    pub fn on_add_adlist(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        self.open_input_prompt(PromptKind::AddAdlist, String::new());
+    }
+
+    fn add_adlist(&mut self, address: &str) {
+        self.servers[self.selected_server_index].make_change(ServerChange::AddAdlist {
+            address: address.to_string(),
+        });
+        self.set_status_message(Ok(format!("Adding {}\u{2026}", address)));
+    }
+
+    /// Enables the selected adlist if it is disabled and disables it otherwise
+    pub fn toggle_selected_adlist(&mut self) {
+        let adlist = match (self.selected_adlists(), self.adlist_table_state.selected()) {
+            (Some(adlists), Some(selected)) => match adlists.get(selected) {
+                Some(adlist) => adlist.clone(),
+                None => return,
+            },
+            _ => return,
+        };
+        let change = if adlist.enabled {
+            "Disabling"
+        } else {
+            "Enabling"
+        };
+        self.set_status_message(Ok(format!("{} {}\u{2026}", change, adlist.address)));
+        let enabled = !adlist.enabled;
+        self.servers[self.selected_server_index]
+            .make_change(ServerChange::SetAdlistEnabled { adlist, enabled });
+    }
+
+    /// Highlights the other records in the Local DNS view
+    pub fn next_record_type(&mut self) {
+        let index = (self.selected_record_type.index() + 1) % LocalRecordType::ALL.len();
+        self.selected_record_type = LocalRecordType::ALL[index];
+        self.record_table_state = TableState::default();
+    }
+
+    /// Records of the highlighted type of the selected server, if they have been fetched
+    pub fn selected_local_records(&self) -> Option<&[LocalRecord]> {
+        if self.all_tab_selected() {
+            return None;
+        }
+        self.servers[self.selected_server_index]
+            .last_data
+            .local_records
+            .as_ref()
+            .and_then(|records| records.get(self.selected_record_type.index()))
+            .map(Vec::as_slice)
+    }
+
+    fn move_record_selection(&mut self, rows: isize) {
+        let length = self.selected_local_records().map_or(0, <[_]>::len);
+        move_selection(&mut self.record_table_state, length, rows);
+    }
+
+    pub fn next_local_record(&mut self) {
+        self.move_record_selection(1);
+    }
+
+    pub fn previous_local_record(&mut self) {
+        self.move_record_selection(-1);
+    }
+
+    pub fn first_local_record(&mut self) {
+        self.move_record_selection(isize::MIN);
+    }
+
+    pub fn last_local_record(&mut self) {
+        self.move_record_selection(isize::MAX);
+    }
+
+    pub fn next_local_record_page(&mut self) {
+        self.move_record_selection(self.local_record_page_size());
+    }
+
+    pub fn previous_local_record_page(&mut self) {
+        self.move_record_selection(-self.local_record_page_size());
+    }
+
+    /// Number of records the highlighted table showed when it was last drawn
+    fn local_record_page_size(&self) -> isize {
+        let area = self
+            .click_areas
+            .local_records
+            .iter()
+            .find(|(record_type, _)| *record_type == self.selected_record_type)
+            .map(|(_, area)| *area);
+        page_size(area)
+    }
+
+    /// Opens a prompt for a record of the highlighted type to add
+    pub fn on_add_local_record(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        self.open_input_prompt(
+            PromptKind::AddLocalRecord(self.selected_record_type),
+            String::new(),
+        );
+    }
+
+    /// Asks whether to remove the selected record
+    pub fn on_remove_local_record(&mut self) {
+        let record_type = self.selected_record_type;
+        let record = match (
+            self.selected_local_records(),
+            self.record_table_state.selected(),
+        ) {
+            (Some(records), Some(selected)) => match records.get(selected) {
+                Some(record) => record.clone(),
+                None => return,
+            },
+            _ => return,
+        };
+        self.confirmation = Some(Confirmation {
+            message: format!(
+                "Remove the record of {} pointing to {}?",
+                self.shown_domain(&record.domain),
+                self.shown_record_target(record_type, &record.target)
+            ),
+            action: PendingAction::RemoveLocalRecord {
+                record_type,
+                record,
+            },
+        });
+    }
+
+    fn add_local_record(&mut self, record_type: LocalRecordType, record: &LocalRecord) {
+        self.servers[self.selected_server_index].make_change(ServerChange::AddLocalRecord {
+            record_type,
+            record: record.clone(),
+        });
+        self.set_status_message(Ok(format!(
+            "Adding {} pointing to {}\u{2026}",
+            self.shown_domain(&record.domain),
+            self.shown_record_target(record_type, &record.target)
+        )));
+    }
+
+    fn remove_local_record(&mut self, record_type: LocalRecordType, record: &LocalRecord) {
+        self.servers[self.selected_server_index].make_change(ServerChange::RemoveLocalRecord {
+            record_type,
+            record: record.clone(),
+        });
+        self.set_status_message(Ok(format!(
+            "Removing the record of {}\u{2026}",
+            self.shown_domain(&record.domain)
+        )));
+    }
+
+    /// Moves the focus to the next panel of the Overview view, wrapping around
+    pub fn next_panel(&mut self) {
+        self.move_panel_focus(true);
+    }
+
+    pub fn previous_panel(&mut self) {
+        self.move_panel_focus(false);
+    }
+
+    /// Moves one panel forward or back among those of the sections in the layout, in the
+    /// order the sections are shown
+    fn move_panel_focus(&mut self, forward: bool) {
+        let panels: Vec<Panel> = self
+            .layout
+            .sections
+            .iter()
+            .flat_map(|section| {
+                Panel::ALL
+                    .iter()
+                    .copied()
+                    .filter(move |panel| panel.section() == section.section)
+            })
+            .collect();
+        let index = panels
+            .iter()
+            .position(|panel| *panel == self.focused_panel)
+            .unwrap_or(panels.len() - 1);
+        let steps = if forward { 1 } else { panels.len() - 1 };
+        self.focused_panel = panels[(index + steps) % panels.len()];
+        match self.focused_panel {
+            Panel::Statistics(table) => self.set_focused_table(table),
+            // The selection is only shown while a table is focused
+            Panel::Overview | Panel::Chart => self.statistics_table_state.select(None),
+        }
+    }
+
+    /// Opens the selected row of the focused table, otherwise maximizes the focused panel
+    pub fn on_enter(&mut self) {
+        if matches!(self.focused_panel, Panel::Statistics(_))
+            && self.statistics_table_state.selected().is_some()
+        {
+            self.open_selected_row();
+        } else if self.layout.shows(self.focused_panel.section()) {
+            self.panel_maximized = true;
+        }
+    }
+
+    /// Clears the filter or the selection of the focused table, once neither is left the
+    /// normal layout is restored
+    pub fn on_esc(&mut self) {
+        if self.statistics_filter.is_empty() && self.statistics_table_state.selected().is_none() {
+            self.panel_maximized = false;
+        } else {
+            self.clear_statistics_selection();
+        }
+    }
+
+    /// Focuses `table` without a selection, the filter only applied to the previous table
+    fn set_focused_table(&mut self, table: StatisticsTable) {
+        self.focused_table = table;
+        self.statistics_table_state = TableState::default();
+        self.statistics_filter.clear();
+    }
+
+    /// Moves the selection to `table`, keeping the selected row if it is already focused
+    fn focus_statistics_table(&mut self, table: StatisticsTable) {
+        if self.focused_table != table {
+            self.set_focused_table(table);
+        }
+        self.focused_panel = Panel::Statistics(table);
+        if self.statistics_table_state.selected().is_none() {
+            self.next_statistics_row();
+        }
+    }
+
+    /// Clears the filter of the focused table if there is one, otherwise the selection
+    fn clear_statistics_selection(&mut self) {
+        if self.statistics_filter.is_empty() {
+            self.statistics_table_state.select(None);
+        } else {
+            self.set_statistics_filter(String::new());
+        }
+    }
+
+    /// Moves the selection of the focused overview table by `rows`
+    fn move_statistics_selection(&mut self, rows: isize) {
+        self.focused_panel = Panel::Statistics(self.focused_table);
+        let length = self.statistics_rows().len();
+        move_selection(&mut self.statistics_table_state, length, rows);
+    }
+
+    pub fn next_statistics_row(&mut self) {
+        self.move_statistics_selection(1);
+    }
+
+    pub fn previous_statistics_row(&mut self) {
+        self.move_statistics_selection(-1);
+    }
+
+    pub fn first_statistics_row(&mut self) {
+        self.move_statistics_selection(isize::MIN);
+    }
+
+    pub fn last_statistics_row(&mut self) {
+        self.move_statistics_selection(isize::MAX);
+    }
+
+    pub fn next_statistics_page(&mut self) {
+        self.move_statistics_selection(self.statistics_page_size());
+    }
+
+    pub fn previous_statistics_page(&mut self) {
+        self.move_statistics_selection(-self.statistics_page_size());
+    }
+
+    /// Number of rows the focused overview table showed when it was last drawn
+    fn statistics_page_size(&self) -> isize {
+        let area = self
+            .click_areas
+            .statistics_tables
+            .iter()
+            .find(|(table, _)| *table == self.focused_table)
+            .map(|(_, area)| *area);
+        page_size(area)
+    }
+
+    /// Rows of the focused overview table of the selected tab
+    pub fn statistics_rows(&self) -> Vec<Vec<String>> {
+        self.table_rows(self.focused_table)
+    }
+
+    /// Queries of the selected server, or interleaved from every server on the All tab, newest
+    /// first with the name of their server. `None` if no server has reported its queries.
+    pub fn query_log(&self) -> Option<Vec<(&str, &Query)>> {
+        let servers = self.displayed_servers();
+        if servers
+            .iter()
+            .all(|server| server.last_data.queries.is_none())
+        {
+            return None;
+        }
+        let mut queries: Vec<(&str, &Query)> = servers
+            .iter()
+            .filter_map(|server| Some((server.name.as_str(), server.last_data.queries.as_ref()?)))
+            .flat_map(|(name, queries)| queries.iter().map(move |query| (name, query)))
+            .collect();
+        queries.sort_by_key(|(_, query)| std::cmp::Reverse(query.timestring));
+        Some(queries)
+    }
+
+    /// Queries of the displayed servers by status group, the most frequent first, and whether
+    /// they were counted from the query log as the servers do not count them
+    pub fn status_counts(&self) -> Option<(Vec<(StatusGroup, u64)>, bool)> {
+        let (counts, from_query_log) = match &self.displayed_data().status_counts {
+            Some(counts) => (counts.clone(), false),
+            None => {
+                let mut counts = HashMap::new();
+                for (_, query) in self.query_log()? {
+                    *counts.entry(StatusGroup::of(&query.status)).or_default() += 1;
+                }
+                (counts, true)
+            }
+        };
+        let mut counts: Vec<(StatusGroup, u64)> = StatusGroup::ALL
+            .iter()
+            .filter_map(|group| Some((*group, *counts.get(group)?)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        Some((counts, from_query_log))
+    }
+
+    /// Reply times of the queries of the query log, `None` until one with a known reply time
+    /// arrived
+    pub fn reply_times(&self) -> Option<ReplyTimes> {
+        let queries = self.query_log()?;
+        // Unknown reply times are zero
+        let replied: Vec<&Query> = queries
+            .into_iter()
+            .map(|(_, query)| query)
+            .filter(|query| !query.response_time.is_zero())
+            .collect();
+        let total: Duration = replied.iter().map(|query| query.response_time).sum();
+        let mut histogram = vec![0; REPLY_TIME_RANGES_MS.len() + 1];
+        let mut upstreams: HashMap<&str, (usize, Duration)> = HashMap::new();
+        for query in &replied {
+            let range = REPLY_TIME_RANGES_MS
+                .iter()
+                .position(|bound| query.response_time < Duration::from_millis(*bound))
+                .unwrap_or(REPLY_TIME_RANGES_MS.len());
+            histogram[range] += 1;
+            if query.status == QueryStatus::QueryForwarded && !query.upstream_destination.is_empty()
+            {
+                let upstream = upstreams
+                    .entry(query.upstream_destination.as_str())
+                    .or_default();
+                upstream.0 += 1;
+                upstream.1 += query.response_time;
+            }
+        }
+        let mut upstreams: Vec<(String, usize, Duration)> = upstreams
+            .into_iter()
+            .map(|(upstream, (count, total))| (upstream.to_string(), count, total / count as u32))
+            .collect();
+        upstreams.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Some(ReplyTimes {
+            count: replied.len(),
+            min: replied.iter().map(|query| query.response_time).min()?,
+            average: total / replied.len() as u32,
+            max: replied.iter().map(|query| query.response_time).max()?,
+            histogram,
+            upstreams,
+        })
+    }
+
+    /// Devices of the network table of the selected server, or of every server on the All tab
+    /// with the name of their server, most recently active first. `None` if no server has
+    /// reported its network table.
+    pub fn network_devices(&self) -> Option<Vec<(&str, &NetworkDevice)>> {
+        let servers = self.displayed_servers();
+        if servers
+            .iter()
+            .all(|server| server.last_data.network_devices.is_none())
+        {
+            return None;
+        }
+        let mut devices: Vec<(&str, &NetworkDevice)> = servers
+            .iter()
+            .filter_map(|server| {
+                Some((
+                    server.name.as_str(),
+                    server.last_data.network_devices.as_ref()?,
+                ))
+            })
+            .flat_map(|(name, devices)| devices.iter().map(move |device| (name, device)))
+            .collect();
+        devices.sort_by_key(|(_, device)| std::cmp::Reverse(device.last_query));
+        Some(devices)
+    }
+
+    /// DHCP leases of the selected server, or of every server on the All tab with the name of
+    /// their server, soonest to expire first. `None` if no server has reported its leases.
+    pub fn dhcp_leases(&self) -> Option<Vec<(&str, &DhcpLease)>> {
+        let servers = self.displayed_servers();
+        if servers
+            .iter()
+            .all(|server| server.last_data.dhcp_leases.is_none())
+        {
+            return None;
+        }
+        let mut leases: Vec<(&str, &DhcpLease)> = servers
+            .iter()
+            .filter_map(|server| {
+                Some((server.name.as_str(), server.last_data.dhcp_leases.as_ref()?))
+            })
+            .flat_map(|(name, leases)| leases.iter().map(move |lease| (name, lease)))
+            .collect();
+        // Leases that do not expire last
+        leases.sort_by_key(|(_, lease)| (lease.expires == 0, lease.expires));
+        Some(leases)
+    }
+
+    /// Writes everything last fetched from the selected server, or the combined data of the
+    /// All tab, to a JSON file in the current directory
+    pub fn export_snapshot(&mut self) {
+        let name = if self.all_tab_selected() {
+            "all"
+        } else {
+            &self.servers[self.selected_server_index].name
+        };
+        let result = export::write_snapshot(Path::new("."), name, self.displayed_data());
+        self.set_status_message(
+            result
+                .map(|path| format!("Saved a snapshot to {}", path.display()))
+                .map_err(|error| format!("Could not save a snapshot: {}", error)),
+        );
+    }
+
+    /// Writes the top lists, or the queries in the query log view, to a CSV file in the
+    /// current directory
+    pub fn export_csv(&mut self) {
+        let name = if self.all_tab_selected() {
+            "all"
+        } else {
+            &self.servers[self.selected_server_index].name
+        };
+        let directory = Path::new(".");
+        let result = match self.view {
+            View::Overview => export::write_statistics(directory, name, self.displayed_data())
+                .map(|path| ("top lists", path)),
+            View::QueryLog => match self.query_log() {
+                Some(queries) => export::write_query_log(
+                    directory,
+                    name,
+                    &queries,
+                    &self.theme,
+                    self.timezone.as_ref(),
+                )
+                .map(|path| ("query log", path)),
+                None => Err(io::Error::other("the query log has not been fetched")),
+            },
+            _ => Err(io::Error::other(
+                "only the overview and query log can be exported",
+            )),
+        };
+        self.set_status_message(
+            result
+                .map(|(what, path)| format!("Exported the {} to {}", what, path.display()))
+                .map_err(|error| format!("Could not export: {}", error)),
+        );
+    }
+
+    /// Title of `table`, Top Clients may show blocked queries only
+    pub fn table_title(&self, table: StatisticsTable) -> &'static str {
+        match table {
+            StatisticsTable::Clients if self.top_clients_blocked => "Top Clients (blocked)",
+            table => table.title(),
+        }
+    }
+
+    /// Rows of `table` of the selected tab, filtered if it is focused
+    pub fn table_rows(&self, table: StatisticsTable) -> Vec<Vec<String>> {
+        // Only "hidden" would be listed
+        if table.hidden_by(self.privacy_level()) {
+            return Vec::new();
+        }
+        let rows = match table {
+            StatisticsTable::Clients if self.top_clients_blocked => self
+                .displayed_data()
+                .top_sources_blocked
+                .as_ref()
+                .map(|top_sources| order_convert_string_num_map(&top_sources.top_sources))
+                .unwrap_or_default(),
+            table => table.rows(self.displayed_data()),
+        };
+        if table != self.focused_table || self.statistics_filter.is_empty() {
+            return rows;
+        }
+        let filter = self.statistics_filter.to_lowercase();
+        rows.into_iter()
+            .filter(|row| {
+                row.first()
+                    .is_some_and(|name| name.to_lowercase().contains(&filter))
+            })
+            .collect()
+    }
+
+    /// Asks whether to blacklist the domain selected in Top Queries
+    pub fn on_b(&mut self) {
+        self.confirm_add_selected_domain(StatisticsTable::Queries, DomainList::Blacklist);
+    }
+
+    /// Asks whether to whitelist the domain selected in Top Ads
+    pub fn on_w(&mut self) {
+        self.confirm_add_selected_domain(StatisticsTable::Ads, DomainList::Whitelist);
+    }
+
+    /// Opens the prompt adding to the regex blacklist with a regex blocking the registered
+    /// domain of the domain of the domain popup, or else the one selected in Top Queries or
+    /// Top Ads, and all its subdomains
+    pub fn on_regex_block(&mut self) {
+        let domain = match &self.selected_domain {
+            Some(domain) => domain.clone(),
+            None => {
+                if self.focused_table == StatisticsTable::Clients
+                    || !matches!(self.focused_panel, Panel::Statistics(_))
+                {
+                    return;
+                }
+                let selected = match self.statistics_table_state.selected() {
+                    Some(selected) => selected,
+                    None => return,
+                };
+                match self
+                    .statistics_rows()
+                    .into_iter()
+                    .nth(selected)
+                    .and_then(|row| row.into_iter().next())
+                {
+                    Some(domain) => domain,
+                    None => return,
+                }
+            }
+        };
+        if !self.require_single_server() {
+            return;
+        }
+        self.selected_domain = None;
+        self.update_filtered_queries_fetching();
+        self.open_input_prompt(
+            PromptKind::AddDomain(DomainList::RegexBlacklist),
+            domain_regex(&domain),
+        );
+    }
+
+    /// Asks whether to add the domain selected in `table` to `list`, if `table` is focused
+    fn confirm_add_selected_domain(&mut self, table: StatisticsTable, list: DomainList) {
+        if self.focused_table != table || !self.require_single_server() {
+            return;
+        }
+        let selected = match self.statistics_table_state.selected() {
+            Some(selected) => selected,
+            None => return,
+        };
+        if let Some(domain) = self
+            .statistics_rows()
+            .into_iter()
+            .nth(selected)
+            .and_then(|row| row.into_iter().next())
+        {
+            self.confirmation = Some(Confirmation {
+                message: format!(
+                    "Add {} to the {}?",
+                    self.shown_domain(&domain),
+                    list.title().to_lowercase()
+                ),
+                action: PendingAction::AddDomain { domain, list },
+            });
+        }
+    }
+
+    pub fn cancel_confirmation(&mut self) {
+        self.confirmation = None;
+    }
+
+    pub fn accept_confirmation(&mut self) {
+        if let Some(confirmation) = self.confirmation.take() {
+            match confirmation.action {
+                PendingAction::AddDomain { domain, list } => {
+                    self.add_domain(self.selected_server_index, &domain, list)
+                }
+                PendingAction::RemoveDomain { domain, list } => {
+                    self.remove_domain(self.selected_server_index, &domain, list)
+                }
+                PendingAction::AddServers(servers) => self.add_discovered_servers(servers),
+                PendingAction::Disable(seconds) => self.disable(seconds),
+                PendingAction::UpdateGravity => self.update_gravity(),
+                PendingAction::RemoveLocalRecord {
+                    record_type,
+                    record,
+                } => self.remove_local_record(record_type, &record),
+                PendingAction::Run(action) => self.run_action(action),
+            }
+        }
+    }
+
+    /// Removes the domain last added to a list if that was recent enough
+    pub fn on_u(&mut self) {
+        let undo = match self.undo.take() {
+            Some(undo) if Instant::now() < undo.available_until => undo,
+            _ => return,
+        };
+        let server_index = self
+            .servers
+            .iter()
+            .position(|server| Arc::ptr_eq(&server.api_config, &undo.api_config));
+        if let (Some(server_index), PendingAction::RemoveDomain { domain, list }) =
+            (server_index, undo.action)
+        {
+            self.remove_domain(server_index, &domain, list);
+        }
+    }
+
+    fn add_domain(&mut self, server_index: usize, domain: &str, list: DomainList) {
+        self.servers[server_index].make_change(ServerChange::AddDomain {
+            domain: domain.to_string(),
+            list,
+        });
+        self.set_status_message(Ok(format!(
+            "Adding {} to the {}\u{2026}",
+            self.shown_domain(domain),
+            list.title().to_lowercase()
+        )));
+    }
+
+    fn remove_domain(&mut self, server_index: usize, domain: &str, list: DomainList) {
+        self.servers[server_index].make_change(ServerChange::RemoveDomain {
+            domain: domain.to_string(),
+            list,
+        });
+        self.set_status_message(Ok(format!(
+            "Removing {} from the {}\u{2026}",
+            self.shown_domain(domain),
+            list.title().to_lowercase()
+        )));
+    }
+
+    /// Shows the outcome of a change answered by the server of `api_config`
+    fn finish_change(
+        &mut self,
+        api_config: Arc<PiHoleConfigImplementation>,
+        change: ServerChange,
+        result: ChangeResult,
+    ) {
+        match change {
+            ServerChange::AddDomain { domain, list } => {
+                let message = list_modification_message(result, || {
+                    format!(
+                        "Added {} to the {}, press u within {} to undo",
+                        self.shown_domain(&domain),
+                        list.title().to_lowercase(),
+                        format_duration(UNDO_DURATION)
+                    )
+                });
+                self.undo = match message {
+                    Ok(_) => Some(Undo {
+                        api_config,
+                        action: PendingAction::RemoveDomain { domain, list },
+                        available_until: Instant::now() + UNDO_DURATION,
+                    }),
+                    Err(_) => None,
+                };
+                self.set_status_message(message);
+            }
+            ServerChange::RemoveDomain { domain, list } => {
+                self.set_status_message(list_modification_message(result, || {
+                    format!(
+                        "Removed {} from the {}",
+                        self.shown_domain(&domain),
+                        list.title().to_lowercase()
+                    )
+                }))
+            }
+            ServerChange::AddAdlist { address } => {
+                self.set_status_message(list_modification_message(result, || {
+                    format!(
+                        "Added {}, it is downloaded by the next gravity update",
+                        address
+                    )
+                }))
+            }
+            ServerChange::SetAdlistEnabled { adlist, enabled } => {
+                self.set_status_message(list_modification_message(result, || {
+                    let change = if enabled { "Enabled" } else { "Disabled" };
+                    format!("{} {}", change, adlist.address)
+                }))
+            }
+            ServerChange::AddLocalRecord {
+                record_type,
+                record,
+            } => self.set_status_message(list_modification_message(result, || {
+                format!(
+                    "Added {} pointing to {}",
+                    self.shown_domain(&record.domain),
+                    self.shown_record_target(record_type, &record.target)
+                )
+            })),
+            ServerChange::RemoveLocalRecord { record, .. } => {
+                self.set_status_message(list_modification_message(result, || {
+                    format!(
+                        "Removed the record of {}",
+                        self.shown_domain(&record.domain)
+                    )
+                }))
+            }
+        }
+    }
+
+    /// Creates a server with the defaults of the config
+    fn new_server(&self, config: PiHoleServerConfig) -> Result<PiHoleServer, String> {
+        let mut server = PiHoleServer::new(config, self.update_delay, self.request_timeout_ms)?;
+        server.data_arrived = self.data_arrived.clone();
+        Ok(server)
+    }
+
+    /// Sends `CustomEvent::DataArrived` to `sender` whenever a request of a server or the
+    /// search for Pi-holes finishes
+    pub fn set_data_arrived(&mut self, sender: mpsc::Sender<CustomEvent>) {
+        for server in &mut self.servers {
+            server.data_arrived = Some(sender.clone());
+        }
+        self.data_arrived = Some(sender);
+    }
+
+    /// Opens the server editor with an empty form
+    pub fn add_server(&mut self) {
+        self.server_editor = Some(ServerEditor::new(None, None));
+    }
+
+    /// Starts searching the local network for Pi-holes, those found can then be added
+    pub fn discover_servers(&mut self) {
+        if self.discovery.is_some() {
+            return;
+        }
+        let network = match discover::local_network() {
+            Some(network) => network,
+            None => {
+                self.set_status_message(Err("Could not find the local network".to_string()));
+                return;
+            }
+        };
+        let (tx, rx) = mpsc::channel();
+        let data_arrived = self.data_arrived.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(discover::discover(network, &discover::DEFAULT_PORTS).await);
+            if let Some(data_arrived) = data_arrived {
+                let _ = data_arrived.send(CustomEvent::DataArrived);
+            }
+        });
+        self.discovery = Some(rx);
+        self.set_status_message(Ok(format!("Searching {}/24 for Pi-holes\u{2026}", network)));
+    }
+
+    /// Asks whether to run gravity on the selected server, or shows the popup of the update
+    /// already running
+    pub fn on_gravity(&mut self) {
+        if let Some(gravity) = &mut self.gravity {
+            if gravity.result.is_none() {
+                gravity.popup_open = true;
+                return;
+            }
+        }
+        if !self.require_single_server() {
+            return;
+        }
+        let server = &self.servers[self.selected_server_index];
+        if !server.api_config.manages_adlists() {
+            let message = "Gravity can only be updated through the v6 API".to_string();
+            self.set_status_message(Err(message));
+            return;
+        }
+        self.confirmation = Some(Confirmation {
+            message: format!(
+                "Update gravity on {}? Every adlist is downloaded again.",
+                server.name
+            ),
+            action: PendingAction::UpdateGravity,
+        });
+    }
+
+    /// Runs gravity on the selected server, its output arrives in `check_gravity`
+    fn update_gravity(&mut self) {
+        let server = &self.servers[self.selected_server_index];
+        let api_config = Arc::clone(&server.api_config);
+        let (tx, rx) = mpsc::channel();
+        let data_arrived = self.data_arrived.clone();
+        let notify = move || {
+            if let Some(data_arrived) = &data_arrived {
+                let _ = data_arrived.send(CustomEvent::DataArrived);
+            }
+        };
+        let task_api_config = Arc::clone(&api_config);
+        tokio::spawn(async move {
+            let line_tx = tx.clone();
+            let result = task_api_config
+                .update_gravity(|line| {
+                    let _ = line_tx.send(GravityProgress::Line(line));
+                    notify();
+                })
+                .await
+                .map_err(|error| describe_api_error(&error));
+            let _ = tx.send(GravityProgress::Finished(result));
+            notify();
+        });
+        self.gravity = Some(GravityUpdate {
+            server: server.name.clone(),
+            output: Vec::new(),
+            result: None,
+            popup_open: true,
+            api_config,
+            progress: rx,
+        });
+    }
+
+    /// Takes the output of the running gravity update, refreshing its server once it
+    /// finishes so the number of blocked domains follows
+    fn check_gravity(&mut self) {
+        let gravity = match &mut self.gravity {
+            Some(gravity) if gravity.result.is_none() => gravity,
+            _ => return,
+        };
+        loop {
+            match gravity.progress.try_recv() {
+                Ok(GravityProgress::Line(line)) => gravity.output.push(line),
+                Ok(GravityProgress::Finished(result)) => {
+                    gravity.result = Some(result);
+                    break;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    gravity.result = Some(Err("The gravity update stopped".to_string()));
+                    break;
+                }
+            }
+        }
+        if let Some(server) = self
+            .servers
+            .iter_mut()
+            .find(|server| Arc::ptr_eq(&server.api_config, &gravity.api_config))
+        {
+            server.refresh();
+        }
+        if !gravity.popup_open {
+            let message = match &gravity.result {
+                Some(Ok(())) => Ok(format!("Gravity updated on {}", gravity.server)),
+                Some(Err(error)) => Err(format!(
+                    "Gravity update on {} failed: {}",
+                    gravity.server, error
+                )),
+                None => return,
+            };
+            self.gravity = None;
+            self.set_status_message(message);
+        }
+    }
+
+    /// Blocklist size of the server of the last gravity update, as last fetched
+    pub fn gravity_blocklist_size(&self) -> Option<&str> {
+        let gravity = self.gravity.as_ref()?;
+        self.servers
+            .iter()
+            .find(|server| Arc::ptr_eq(&server.api_config, &gravity.api_config))?
+            .last_data
+            .summary
+            .as_ref()
+            .map(|summary| summary.domains_being_blocked.as_str())
+    }
+
+    /// Opens the actions menu of the selected server
+    pub fn open_actions_menu(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        let server = &self.servers[self.selected_server_index];
+        if !server.api_config.runs_actions() {
+            let message = "Actions can only be run through the v6 API".to_string();
+            self.set_status_message(Err(message));
+            return;
+        }
+        self.actions_menu_open = true;
+    }
+
+    pub fn close_actions_menu(&mut self) {
+        self.actions_menu_open = false;
+    }
+
+    /// Asks whether to run the action of `key` in the actions menu, other keys are ignored
+    pub fn on_action_key(&mut self, key: char) {
+        let action = match ServerAction::from_key(key.to_ascii_lowercase()) {
+            Some(action) => action,
+            None => return,
+        };
+        self.actions_menu_open = false;
+        let server = &self.servers[self.selected_server_index];
+        self.confirmation = Some(Confirmation {
+            message: format!("{} on {}?", action.title(), server.name),
+            action: PendingAction::Run(action),
+        });
+    }
+
+    /// Runs `action` on the selected server in the background, its outcome is taken by
+    /// `check_actions`
+    fn run_action(&mut self, action: ServerAction) {
+        let server = &self.servers[self.selected_server_index];
+        let api_config = Arc::clone(&server.api_config);
+        let name = server.name.clone();
+        let (tx, rx) = mpsc::channel();
+        let data_arrived = self.data_arrived.clone();
+        tokio::spawn(async move {
+            let message = match api_config.run_action(action).await {
+                Ok(()) => Ok(match action {
+                    ServerAction::FlushLogs => format!("Flushed the query log of {}", name),
+                    ServerAction::FlushNetworkTable => {
+                        format!("Flushed the network table of {}", name)
+                    }
+                    ServerAction::RestartDns => {
+                        format!("Restarted the DNS resolver of {}", name)
+                    }
+                }),
+                Err(error) => Err(format!(
+                    "{} on {} failed: {}",
+                    action.title(),
+                    name,
+                    describe_api_error(&error)
+                )),
+            };
+            let _ = tx.send((api_config, message));
+            if let Some(data_arrived) = data_arrived {
+                let _ = data_arrived.send(CustomEvent::DataArrived);
+            }
+        });
+        self.running_actions.push(rx);
+        self.set_status_message(Ok(format!("{} on {}\u{2026}", action.title(), server.name)));
+    }
+
+    /// Shows the outcome of the actions that finished, refreshing their server so it follows
+    fn check_actions(&mut self) {
+        let mut finished = Vec::new();
+        self.running_actions
+            .retain(|running| match running.try_recv() {
+                Ok(outcome) => {
+                    finished.push(outcome);
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => false,
+            });
+        for (api_config, message) in finished {
+            if let Some(server) = self
+                .servers
+                .iter_mut()
+                .find(|server| Arc::ptr_eq(&server.api_config, &api_config))
+            {
+                server.refresh();
+            }
+            self.set_status_message(message);
+        }
+    }
+
+    /// Closes the gravity popup, an update still running reports in the status bar when done
+    pub fn close_gravity_popup(&mut self) {
+        match &mut self.gravity {
+            Some(gravity) if gravity.result.is_none() => gravity.popup_open = false,
+            _ => self.gravity = None,
+        }
+    }
+
+    /// Asks whether to add the Pi-holes found once the search finishes
+    fn check_discovery(&mut self) {
+        let found = match self.discovery.as_ref().map(mpsc::Receiver::try_recv) {
+            Some(Ok(found)) => found,
+            Some(Err(TryRecvError::Empty)) | None => return,
+            Some(Err(TryRecvError::Disconnected)) => Vec::new(),
+        };
+        self.discovery = None;
+        let configured: Vec<PiHoleServerConfig> = self
+            .servers
+            .iter()
+            .map(|server| server.config.clone())
+            .collect();
+        let found = discover::not_configured(found, &configured);
+        if found.is_empty() {
+            self.set_status_message(Ok("No new Pi-holes found".to_string()));
+            return;
+        }
+        let mut hosts: Vec<&str> = found
+            .iter()
+            .take(3)
+            .map(|server| server.host.as_str())
+            .collect();
+        let more = format!("{} more", found.len().saturating_sub(3));
+        if found.len() > 3 {
+            hosts.push(&more);
+        }
+        self.confirmation = Some(Confirmation {
+            message: format!("Add the Pi-holes found at {}?", hosts.join(", ")),
+            action: PendingAction::AddServers(found),
+        });
+    }
+
+    /// Adds servers without an API key, they can be given one with the server editor
+    fn add_discovered_servers(&mut self, servers: Vec<DiscoveredServer>) {
+        let first = self.servers.len();
+        let mut added = 0;
+        for discovered in servers {
+            let config = PiHoleServerConfig::new(
+                discovered.default_name(),
+                discovered.host,
+                None,
+                discovered.api_version,
+            );
+            match self.new_server(config) {
+                Ok(mut server) => {
+                    server.run_background_update();
+                    self.servers.push(server);
+                    added += 1;
+                }
+                Err(error) => {
+                    if added > 0 {
+                        self.show_server(first);
+                    }
+                    self.set_status_message(Err(error));
+                    return;
+                }
+            }
+        }
+        if added > 0 {
+            self.show_server(first);
+        }
+        self.set_status_message(Ok(format!(
+            "Added {} servers, press M to give them an API key and Ctrl+S to save",
+            added
+        )));
+    }
 
-impl PiHoleConfigImplementation {
-    pub fn new(host: String, api_key: Option<String>) -> Self {
-        match api_key {
-            Some(key) => {
-                PiHoleConfigImplementation::WithKey(PiHoleAPIConfigWithKey::new(host, key))
+    /// Opens the server editor for the selected server
+    pub fn edit_server(&mut self) {
+        if !self.require_single_server() {
+            return;
+        }
+        let server = &self.servers[self.selected_server_index];
+        self.server_editor = Some(ServerEditor::new(
+            Some(self.selected_server_index),
+            Some(&server.config),
+        ));
+    }
+
+    pub fn close_server_editor(&mut self) {
+        self.server_editor = None;
+    }
+
+    pub fn server_editor_next_field(&mut self) {
+        if let Some(editor) = &mut self.server_editor {
+            editor.selected_field = (editor.selected_field + 1) % SERVER_EDITOR_FIELDS.len();
+        }
+    }
+
+    pub fn server_editor_previous_field(&mut self) {
+        if let Some(editor) = &mut self.server_editor {
+            editor.selected_field = (editor.selected_field + SERVER_EDITOR_FIELDS.len() - 1)
+                % SERVER_EDITOR_FIELDS.len();
+        }
+    }
+
+    pub fn server_editor_input(&mut self, c: char) {
+        if let Some(editor) = &mut self.server_editor {
+            editor.values[editor.selected_field].push(c);
+            editor.error = None;
+        }
+    }
+
+    pub fn server_editor_backspace(&mut self) {
+        if let Some(editor) = &mut self.server_editor {
+            editor.values[editor.selected_field].pop();
+            editor.error = None;
+        }
+    }
+
+    /// Adds or replaces the server described by the editor, leaving the editor open with
+    /// an error if the form is invalid
+    pub fn save_server_editor(&mut self) {
+        let editor = match &self.server_editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        let current_config = editor
+            .server_index
+            .map(|server_index| &self.servers[server_index].config);
+        let server = editor
+            .to_config(current_config)
+            .and_then(|config| self.new_server(config));
+
+        match server {
+            Ok(mut server) => {
+                server.run_background_update();
+                match editor.server_index {
+                    Some(server_index) => {
+                        let old_server = std::mem::replace(&mut self.servers[server_index], server);
+                        old_server.logout();
+                        self.show_server(server_index);
+                    }
+                    None => {
+                        self.servers.push(server);
+                        self.show_server(self.servers.len() - 1);
+                    }
+                }
+                self.server_editor = None;
+            }
+            Err(error) => {
+                if let Some(editor) = &mut self.server_editor {
+                    editor.error = Some(error);
+                }
             }
-            None => PiHoleConfigImplementation::Default(PiHoleAPIConfig::new(host)),
         }
     }
 
-    pub fn get_unauthenticated_api(&self) -> Option<&dyn UnauthenticatedPiHoleAPI> {
-        Some(match self {
-            Self::Default(config) => config,
-            Self::WithKey(config) => config,
+    /// End any API sessions before exiting, waiting for the workers up to the request timeout.
+    /// The servers are removed.
+    pub fn logout(&mut self) {
+        let logouts: Vec<mpsc::Receiver<()>> = self
+            .servers
+            .drain(..)
+            .filter_map(PiHoleServer::logout)
+            .collect();
+        let deadline = Instant::now() + Duration::from_millis(self.request_timeout_ms);
+        for logged_out in logouts {
+            let _ = logged_out.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
+impl TryFrom<PimonConfig> for App {
+    type Error = String;
+
+    fn try_from(config: PimonConfig) -> Result<Self, Self::Error> {
+        let update_delay = config.update_delay;
+        let request_timeout_ms = config.request_timeout_ms;
+        config.layout.validate()?;
+        Ok(App {
+            selected_server_index: 0,
+            all_selected: true,
+            selected_group: None,
+            aggregate: PiHoleData::default(),
+            view: View::Overview,
+            update_delay,
+            request_timeout_ms,
+            graph_squash_factor: config.graph_squash_factor,
+            terminal_width: 0,
+            chart_mode: config.chart_mode,
+            over_time_chart: OverTimeChart::Queries,
+            top_clients_blocked: false,
+            selected_client: None,
+            selected_domain: None,
+            top_items_count: config.top_items_count,
+            outage_threshold: config.outage_threshold,
+            paused: false,
+            follow_query_log: false,
+            chart_window: ChartWindow::Day,
+            chart_pan: 0,
+            long_term_period: LongTermPeriod::Month,
+            status_message: None,
+            input_prompt: None,
+            confirmation: None,
+            gravity: None,
+            actions_menu_open: false,
+            running_actions: Vec::new(),
+            selected_list: DomainList::Whitelist,
+            list_table_state: TableState::default(),
+            adlist_table_state: TableState::default(),
+            selected_record_type: LocalRecordType::Dns,
+            record_table_state: TableState::default(),
+            focused_panel: Panel::Overview,
+            panel_maximized: false,
+            focused_table: StatisticsTable::Queries,
+            statistics_table_state: TableState::default(),
+            statistics_filter: String::new(),
+            undo: None,
+            click_areas: ClickAreas::default(),
+            disable_duration: config.disable_duration,
+            keymap: config.keymap,
+            history_directory: config.history.then(history::default_directory),
+            timezone: config.timezone,
+            time_format: config.time_format,
+            theme: Theme::new(&config.theme)?,
+            theme_config: config.theme,
+            layout: config.layout,
+            alerts: Alerts::new(config.alerts),
+            thresholds: config.thresholds,
+            notifications: config.notifications,
+            redact: config.redact,
+            client_names: config.client_names,
+            numbers: Numbers::new(config.number_format),
+            webhooks: Webhooks::new(config.webhooks)?,
+            server_editor: None,
+            discovery: None,
+            data_arrived: None,
+            servers: config
+                .servers
+                .into_iter()
+                .map(|server| PiHoleServer::new(server, update_delay, request_timeout_ms))
+                .collect::<Result<_, _>>()?,
         })
     }
+}
 
-    pub fn get_authenticated_api(&self) -> Option<&dyn AuthenticatedPiHoleAPI> {
-        match self {
-            Self::Default(_) => None,
-            Self::WithKey(config) => Some(config),
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PimonConfig {
+    /// Default delay between updates in milliseconds
+    pub update_delay: u64,
+    /// Default time in milliseconds after which a request to a server is abandoned
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout_ms: u64,
+    /// Number of entries fetched for Top Queries, Top Ads and Top Clients
+    #[serde(default = "default_top_items_count")]
+    pub top_items_count: u32,
+    /// Updates in a row without reaching a server before it is marked down
+    #[serde(default = "default_outage_threshold")]
+    pub outage_threshold: u32,
+    /// Number of 10 minute periods combined into each bar of the queries chart
+    #[serde(default = "default_graph_squash_factor")]
+    pub graph_squash_factor: usize,
+    /// Whether the queries chart is drawn as bars or a line
+    #[serde(default)]
+    pub chart_mode: ChartMode,
+    /// Duration suggested when disabling blocking e.g. `30s`, `5m`, `1h` or `0` for permanently
+    #[serde(default = "default_disable_duration")]
+    pub disable_duration: String,
+    #[serde(default)]
+    pub keymap: Keymap,
+    /// Whether the summaries of the servers are recorded to disk
+    #[serde(default)]
+    pub history: bool,
+    /// Timezone of the times shown, `local`, `UTC` or an offset such as `+02:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Timezone>,
+    /// Format of the times shown, `24h`, `12h` or a strftime format such as `%H:%M`
+    #[serde(default, skip_serializing_if = "TimeFormat::is_default")]
+    pub time_format: TimeFormat,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    #[serde(default, skip_serializing_if = "LayoutConfig::is_default")]
+    pub layout: LayoutConfig,
+    #[serde(default, skip_serializing_if = "AlertsConfig::is_empty")]
+    pub alerts: AlertsConfig,
+    #[serde(default, skip_serializing_if = "ThresholdsConfig::is_empty")]
+    pub thresholds: ThresholdsConfig,
+    #[serde(default)]
+    pub notifications: bool,
+    /// Whether pimon starts with domains, clients and addresses partly masked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redact: bool,
+    /// Whether clients are shown by address, host name or both
+    #[serde(default)]
+    pub client_names: ClientNames,
+    /// Whether counts are written in full, compact or compact in narrow terminals
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+    pub servers: Vec<PiHoleServerConfig>,
+}
+
+impl PimonConfig {
+    /// Configuration of `servers` with the remaining settings at their defaults
+    pub fn new(update_delay: u64, servers: Vec<PiHoleServerConfig>) -> Self {
+        PimonConfig {
+            update_delay,
+            request_timeout_ms: default_request_timeout(),
+            top_items_count: default_top_items_count(),
+            outage_threshold: default_outage_threshold(),
+            graph_squash_factor: default_graph_squash_factor(),
+            chart_mode: ChartMode::default(),
+            disable_duration: default_disable_duration(),
+            keymap: Keymap::default(),
+            history: false,
+            timezone: None,
+            time_format: TimeFormat::default(),
+            theme: ThemeConfig::default(),
+            layout: LayoutConfig::default(),
+            alerts: AlertsConfig::default(),
+            thresholds: ThresholdsConfig::default(),
+            notifications: false,
+            redact: false,
+            client_names: ClientNames::default(),
+            number_format: NumberFormat::default(),
+            webhooks: Vec::new(),
+            servers,
         }
     }
 }
 
-struct BackgroundUpdater {
-    handle: thread::JoinHandle<()>,
-    receiver: mpsc::Receiver<Option<PiHoleData>>,
+fn default_request_timeout() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_MS
 }
 
-pub struct PiHoleServer {
+fn default_top_items_count() -> u32 {
+    DEFAULT_TOP_ITEMS_COUNT
+}
+
+fn default_outage_threshold() -> u32 {
+    DEFAULT_OUTAGE_THRESHOLD
+}
+
+fn default_graph_squash_factor() -> usize {
+    1
+}
+
+fn default_disable_duration() -> String {
+    "1m".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiHoleServerConfig {
     pub name: String,
     pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
-    pub api_config: PiHoleConfigImplementation,
-    pub last_update: Instant,
-    pub last_data: PiHoleData,
-    background_updater: Option<BackgroundUpdater>,
+    /// Name of an environment variable holding the API key,
+    /// takes precedence over `api_key_keyring` and `api_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// Name of a system keyring entry holding the API key, takes precedence over `api_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_keyring: Option<String>,
+    #[serde(default)]
+    pub api_version: ApiVersion,
+    /// Overrides the global delay between updates in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_delay: Option<u64>,
+    /// Overrides the global request timeout in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout_ms: Option<u64>,
+    /// Credentials of an authenticating reverse proxy in front of the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basic_auth: Option<BasicAuth>,
+    /// Path the Pi-hole is served under, e.g. `/pi-hole` for `/pi-hole/admin/api.php` (v5)
+    /// or `/pi-hole/api` (v6)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// URL of an HTTP proxy to reach the server through, otherwise `HTTP_PROXY`, `HTTPS_PROXY`
+    /// and `ALL_PROXY` are followed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Accepts any certificate of an https:// host, for a Pi-hole with a self-signed
+    /// certificate
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub insecure_tls: bool,
+    /// SSH server to forward the requests through, `host` is then resolved by the SSH server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    /// Domains to resolve on the server's DNS port with each update, checking it answers and
+    /// blocks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_probe: Option<DnsProbeConfig>,
+    /// Site the server is listed under, such as "Home", with the totals of each site on a
+    /// tab of its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Leaves out the DHCP leases panel of the Network page, for servers not running DHCP
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hide_dhcp_leases: bool,
 }
 
-impl PiHoleServer {
+/// SSH local forward opened by running `ssh` when the server is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshTunnelConfig {
+    /// Host name or `~/.ssh/config` alias of the SSH server
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Port of the Pi-hole on the far side, the port of `host` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_port: Option<u16>,
+}
+
+/// HTTP Basic authentication sent with every request of a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasicAuth {
+    pub username: String,
+    /// May contain `${VAR}` references to environment variables, as in `api_key`
+    pub password: String,
+}
+
+impl PiHoleServerConfig {
+    /// Server with the optional settings left at their defaults
     pub fn new(
         name: String,
         host: String,
         api_key: Option<String>,
-        update_delay: Duration,
+        api_version: ApiVersion,
     ) -> Self {
-        let api_config = PiHoleConfigImplementation::new(host.clone(), api_key.clone());
-        PiHoleServer {
-            name: name,
-            host: host,
-            api_key: api_key,
-            api_config: api_config,
-            last_update: Instant::now()
-                .checked_sub(update_delay)
-                .expect("Failed to set last update"),
-            last_data: PiHoleData {
-                summary: None,
-                top_sources: None,
-                top_items: None,
-                over_time_data: None,
-            },
-            background_updater: None,
+        PiHoleServerConfig {
+            name,
+            host,
+            api_key,
+            api_key_env: None,
+            api_key_keyring: None,
+            api_version,
+            update_delay: None,
+            request_timeout_ms: None,
+            basic_auth: None,
+            path_prefix: None,
+            proxy: None,
+            insecure_tls: false,
+            ssh_tunnel: None,
+            dns_probe: None,
+            group: None,
+            hide_dhcp_leases: false,
         }
     }
-    pub fn run_background_update(&mut self) {
-        if self.background_updater.is_none() {
-            let (tx, rx) = mpsc::channel();
-            let host = self.host.clone();
-            let api_key = self.api_key.clone();
-            let handle = thread::spawn(move || background_update(tx, host, api_key));
-
-            self.background_updater = Some(BackgroundUpdater {
-                handle,
-                receiver: rx,
-            });
+
+    /// Reads the API key from the environment or keyring if `api_key_env` or `api_key_keyring`
+    /// is set, otherwise expands `${VAR}` references within `api_key`
+    fn resolve_api_key(&self) -> Result<Option<String>, String> {
+        if let Some(variable) = &self.api_key_env {
+            let api_key = env::var(variable).map_err(|_| {
+                format!(
+                    "Environment variable {} (api_key_env of server {}) is not set",
+                    variable, self.name
+                )
+            })?;
+            Ok(Some(api_key))
+        } else if let Some(entry) = &self.api_key_keyring {
+            let api_key = read_keyring_secret(entry).map_err(|error| {
+                format!(
+                    "Could not read keyring entry {} (api_key_keyring of server {}): {}",
+                    entry, self.name, error
+                )
+            })?;
+            Ok(Some(api_key))
+        } else if let Some(api_key) = &self.api_key {
+            let api_key = expand_env_vars(api_key)
+                .map_err(|error| format!("{} in the api_key of server {}", error, self.name))?;
+            Ok(Some(api_key))
+        } else {
+            Ok(None)
         }
     }
 
-    pub fn check_background_update(&mut self) {
-        let mut join = false;
-        match &self.background_updater {
-            Some(background_updater) => match background_updater
-                .receiver
-                .recv_timeout(Duration::from_millis(10))
-            {
-                Ok(option_pi_hole_data) => {
-                    match option_pi_hole_data {
-                        Some(pi_hole_data) => self.last_data = pi_hole_data,
-                        None => {}
-                    }
-                    join = true;
-                    self.last_update = Instant::now();
-                }
-                Err(_) => {}
-            },
-            None => {}
-        }
-        if join {
-            if let Some(background_updater) = self.background_updater.take() {
-                background_updater
-                    .handle
-                    .join()
-                    .expect("Unable to join background updater thread");
-            }
+    /// `host`, the configured one or that of an SSH tunnel, followed by the `path_prefix`.
+    /// The base of the API URLs.
+    fn base_url(&self, host: &str) -> String {
+        let host = host.trim_end_matches('/');
+        match self
+            .path_prefix
+            .as_deref()
+            .map(|prefix| prefix.trim_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+        {
+            Some(prefix) => format!("{}/{}", host, prefix),
+            None => host.to_string(),
         }
     }
+
+    /// Expands `${VAR}` references within the `basic_auth` password
+    fn resolve_basic_auth(&self) -> Result<Option<BasicAuth>, String> {
+        self.basic_auth
+            .as_ref()
+            .map(|basic_auth| {
+                let password = expand_env_vars(&basic_auth.password).map_err(|error| {
+                    format!(
+                        "{} in the basic_auth password of server {}",
+                        error, self.name
+                    )
+                })?;
+                Ok(BasicAuth {
+                    username: basic_auth.username.clone(),
+                    password,
+                })
+            })
+            .transpose()
+    }
 }
 
-pub struct App {
-    pub selected_server_index: usize,
-    pub servers: Vec<PiHoleServer>,
-    pub update_delay: u64,
-    pub graph_squash_factor: usize,
+/// Replaces each `${VAR}` in `value` with the contents of the environment variable `VAR`
+fn expand_env_vars(value: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut remaining = value;
+    while let Some(start) = remaining.find("${") {
+        let end = match remaining[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let variable = &remaining[start + 2..end];
+        let contents = env::var(variable)
+            .map_err(|_| format!("Environment variable {} is not set", variable))?;
+        expanded.push_str(&remaining[..start]);
+        expanded.push_str(&contents);
+        remaining = &remaining[end + 1..];
+    }
+    expanded.push_str(remaining);
+    Ok(expanded)
 }
 
-impl App {
-    pub fn next_server(&mut self) {
-        self.selected_server_index = (self.selected_server_index + 1) % self.servers.len();
+fn read_keyring_secret(entry: &str) -> keyring::Result<String> {
+    keyring::Entry::new(KEYRING_SERVICE, entry)?.get_password()
+}
+
+/// Stores `secret` in the system keyring under `entry`
+pub fn store_keyring_secret(entry: &str, secret: &str) -> keyring::Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, entry)?.set_password(secret)
+}
+
+/// Keyring entry holding the API key of `server_name`.
+/// This is the server's `api_key_keyring` if configured, otherwise the server name.
+pub fn keyring_entry_name(
+    config_path: Option<&Path>,
+    server_name: &str,
+) -> Result<String, Box<dyn Error>> {
+    if let Some(path) = config_path {
+        let pimon_config = read_config(path)?;
+        let entry = pimon_config
+            .servers
+            .into_iter()
+            .find(|server| server.name == server_name)
+            .and_then(|server| server.api_key_keyring);
+        if let Some(entry) = entry {
+            return Ok(entry);
+        }
     }
+    Ok(server_name.to_string())
+}
 
-    pub fn previous_server(&mut self) {
-        if self.selected_server_index > 0 {
-            self.selected_server_index -= 1;
-        } else {
-            self.selected_server_index = self.servers.len() - 1;
+/// Human readable description of an API error
+pub fn describe_api_error(error: &APIError) -> String {
+    match error {
+        // The URL is left out as the v5 API key is part of it
+        APIError::RequestError(error) if error.is_timeout() => "Request timed out".to_string(),
+        APIError::RequestError(error) if error.is_connect() => {
+            "Could not connect to the server".to_string()
         }
+        APIError::RequestError(error) => match error.status() {
+            Some(status) => format!("The server responded with {}", status),
+            None => "Request failed".to_string(),
+        },
+        APIError::SerdeJSONError(error) => format!("Unexpected response: {}", error),
+        APIError::MissingAPIKey => "API key or password missing or rejected".to_string(),
+        APIError::InvalidList => "Invalid list".to_string(),
+        APIError::FTLNotRunning => "FTL is not running".to_string(),
     }
+}
 
-    pub fn on_tick(&mut self) {
-        let server = &mut self.servers[self.selected_server_index];
-        server.check_background_update();
-        if Instant::now().duration_since(server.last_update)
-            > Duration::from_millis(self.update_delay)
-        {
-            server.run_background_update();
+/// Locations searched when no configuration file is given, in order of preference
+pub fn config_file_candidates() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    if let Some(config_home) = env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+        directories.push(PathBuf::from(config_home).join("pimon"));
+    }
+    if let Some(home) = env::var_os("HOME").filter(|path| !path.is_empty()) {
+        let default_config_home = PathBuf::from(home).join(".config").join("pimon");
+        if !directories.contains(&default_config_home) {
+            directories.push(default_config_home);
         }
     }
+    directories.push(PathBuf::new());
 
-    pub fn on_space(&mut self) {
-        let server = &mut self.servers[self.selected_server_index];
-        server.run_background_update();
+    directories
+        .iter()
+        .flat_map(|directory| {
+            CONFIG_FILE_EXTENSIONS
+                .iter()
+                .map(move |extension| directory.join("pimon").with_extension(extension))
+        })
+        .collect()
+}
+
+/// Where a new configuration file is created, the first candidate location
+pub fn default_config_file_path() -> PathBuf {
+    config_file_candidates()
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| PathBuf::from("pimon.json"))
+}
+
+/// Finds the first existing configuration file from `config_file_candidates`
+pub fn find_config_file() -> Option<PathBuf> {
+    config_file_candidates()
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Parses the configuration, the format is chosen by the file extension
+/// (TOML for `.toml`, YAML for `.yaml`/`.yml`, otherwise JSON)
+pub fn read_config(path: &Path) -> Result<PimonConfig, Box<dyn Error>> {
+    let contents = fs::read_to_string(path).map_err(|error| {
+        format!(
+            "Could not read configuration file {}: {}",
+            path.display(),
+            error
+        )
+    })?;
+    let pimon_config: Result<PimonConfig, Box<dyn Error>> =
+        match path.extension().and_then(OsStr::to_str) {
+            Some("toml") => toml::from_str(&contents).map_err(Box::from),
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents).map_err(Box::from),
+            _ => serde_json::from_str(&contents).map_err(Box::from),
+        };
+    Ok(pimon_config
+        .map_err(|error| format!("Invalid configuration file {}: {}", path.display(), error))?)
+}
+
+/// Writes the configuration, the format is chosen by the file extension as in `read_config`
+pub fn write_config(path: &Path, pimon_config: &PimonConfig) -> Result<(), Box<dyn Error>> {
+    let contents = match path.extension().and_then(OsStr::to_str) {
+        Some("toml") => toml::to_string_pretty(pimon_config)?,
+        Some("yaml") | Some("yml") => serde_yaml::to_string(pimon_config)?,
+        _ => serde_json::to_string_pretty(pimon_config)?,
+    };
+    if let Some(directory) = path.parent().filter(|path| !path.as_os_str().is_empty()) {
+        fs::create_dir_all(directory)?;
     }
+    fs::write(path, contents)?;
+    Ok(())
+}
 
-    pub fn on_z(&mut self) {
-        if self.graph_squash_factor > 1 {
-            self.graph_squash_factor /= 2;
+/// Writes the servers and settings of `app` back to the configuration file at `path`.
+/// API keys are saved as configured so keys read from the environment or keyring stay there.
+pub fn save_config(app: &App, path: &Path) -> Result<(), Box<dyn Error>> {
+    write_config(
+        path,
+        &PimonConfig {
+            update_delay: app.update_delay,
+            request_timeout_ms: app.request_timeout_ms,
+            top_items_count: app.top_items_count,
+            outage_threshold: app.outage_threshold,
+            graph_squash_factor: app.graph_squash_factor,
+            chart_mode: app.chart_mode,
+            disable_duration: app.disable_duration.clone(),
+            keymap: app.keymap,
+            history: app.history_directory.is_some(),
+            timezone: app.timezone.clone(),
+            time_format: app.time_format.clone(),
+            theme: app.theme_config.clone(),
+            layout: app.layout.clone(),
+            alerts: app.alerts.config.clone(),
+            thresholds: app.thresholds.clone(),
+            notifications: app.notifications,
+            redact: app.redact,
+            client_names: app.client_names,
+            number_format: app.numbers.format,
+            webhooks: app.webhooks.config.clone(),
+            servers: app
+                .servers
+                .iter()
+                .map(|server| server.config.clone())
+                .collect(),
+        },
+    )
+}
+
+/// Loads the configuration and resolves the API keys of each server
+pub fn load_config(path: &Path) -> Result<App, Box<dyn Error>> {
+    Ok(App::try_from(read_config(path)?)?)
+}
+
+/// Whether any component of the Pi-Hole can be updated
+pub fn update_available(versions: &Versions) -> bool {
+    versions.core_update || versions.web_update || versions.ftl_update
+}
+
+/// Status message for the outcome of changing a list, `success` describes the change
+fn list_modification_message(
+    result: Result<ListModificationResponse, APIError>,
+    success: impl FnOnce() -> String,
+) -> Result<String, String> {
+    match result {
+        Ok(response) if response.success => Ok(success()),
+        Ok(response) => Err(response
+            .message
+            .unwrap_or_else(|| "The Pi-Hole rejected the change".to_string())),
+        Err(error) => Err(describe_api_error(&error)),
+    }
+}
+
+/// Second level labels under which country code domains are registered, as in `example.co.uk`
+const SECOND_LEVEL_LABELS: [&str; 8] = ["ac", "co", "com", "edu", "gov", "net", "or", "org"];
+
+/// Regex matching the registered domain of `domain` and its subdomains, e.g.
+/// `(\.|^)doubleclick\.net$` for `ads.g.doubleclick.net`. Registered domains are taken as the
+/// last two labels, or three below a second level such as `co.uk`.
+fn domain_regex(domain: &str) -> String {
+    let labels: Vec<&str> = domain.trim_end_matches('.').split('.').collect();
+    let second_level = labels.len() >= 3
+        && labels[labels.len() - 1].len() == 2
+        && SECOND_LEVEL_LABELS.contains(&labels[labels.len() - 2]);
+    let kept = if second_level { 3 } else { 2 };
+    let registered = labels[labels.len().saturating_sub(kept)..].join(".");
+    format!("(\\.|^){}$", registered.replace('.', "\\."))
+}
+
+/// Reads a Local DNS record typed as the domain followed by its IP address or target,
+/// e.g. `nas.lan 192.168.1.10`
+fn parse_local_record(record_type: LocalRecordType, input: &str) -> Result<LocalRecord, String> {
+    let mut words = input.split_whitespace();
+    let (domain, target) = match (words.next(), words.next(), words.next()) {
+        (Some(domain), Some(target), None) => (domain, target),
+        _ => {
+            return Err(format!(
+                "Type the domain and the {}",
+                record_type.target_title().to_lowercase()
+            ))
         }
+    };
+    if record_type == LocalRecordType::Dns && target.parse::<IpAddr>().is_err() {
+        return Err(format!("{} is not an IP address", target));
     }
+    Ok(LocalRecord {
+        domain: domain.to_string(),
+        target: target.to_string(),
+    })
+}
 
-    pub fn on_x(&mut self) {
-        if self.graph_squash_factor < usize::MAX {
-            self.graph_squash_factor *= 2;
+/// Answer to adlist changes on servers whose API cannot make them
+fn adlists_unsupported() -> ListModificationResponse {
+    ListModificationResponse {
+        success: false,
+        message: Some("Adlists can only be managed through the v6 API".to_string()),
+    }
+}
+
+/// Moves the selected row of `state` by `rows` within a table of `length` rows.
+/// Without a selection it moves from just before the first row.
+fn move_selection(state: &mut TableState, length: usize, rows: isize) {
+    if length == 0 {
+        return;
+    }
+    let selected = match state.selected() {
+        Some(selected) if rows < 0 => selected.saturating_sub(rows.unsigned_abs()),
+        Some(selected) => selected.saturating_add(rows as usize).min(length - 1),
+        None if rows > 0 => (rows as usize - 1).min(length - 1),
+        None => 0,
+    };
+    state.select(Some(selected));
+}
+
+/// Rows visible in a bordered table with a header drawn in `area`, at least one
+fn page_size(area: Option<Rect>) -> isize {
+    area.map_or(1, |area| area.height.saturating_sub(3).max(1) as isize)
+}
+
+/// Formats a count in the same style as the v5 API e.g. 12,345
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            formatted.push(',');
         }
+        formatted.push(digit);
     }
+    formatted
+}
 
-    pub fn on_e(&mut self) {
-        let server = &mut self.servers[self.selected_server_index];
-        match server.api_config.get_authenticated_api() {
-            None => {}
-            Some(api) => {
-                api.enable().expect("Failed to enable pi-hole");
-            }
-        };
-        server.run_background_update();
+/// `name` usable as part of a file name, anything other than letters, digits, `-` and `_` is
+/// replaced with `_`
+pub fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Parses a count formatted by the v5 API e.g. 12,345, treating anything else as zero
+pub fn parse_count(count: &str) -> u64 {
+    count.replace(',', "").trim().parse().unwrap_or_default()
+}
+
+/// Adds the counts of `map` to `total`
+fn merge_counts(total: &mut HashMap<String, u64>, map: &HashMap<String, u64>) {
+    for (key, count) in map {
+        *total.entry(key.clone()).or_default() += count;
     }
+}
 
-    pub fn on_d(&mut self) {
-        let server = &mut self.servers[self.selected_server_index];
-        match server.api_config.get_authenticated_api() {
-            None => {}
-            Some(api) => {
-                api.disable(60).expect("Failed to disable pi-hole");
+/// Combines the data of all servers for the All tab.
+/// Counts are summed, the blocklist size and unique domains are the largest of any server
+/// as the same domains are usually seen by every server.
+fn aggregate_data(servers: &[&PiHoleServer]) -> PiHoleData {
+    let summaries: Vec<&Summary> = servers
+        .iter()
+        .filter_map(|server| server.last_data.summary.as_ref())
+        .collect();
+    let sum = |field: fn(&Summary) -> &String| -> u64 {
+        summaries
+            .iter()
+            .map(|summary| parse_count(field(summary)))
+            .sum()
+    };
+    let max = |field: fn(&Summary) -> &String| -> u64 {
+        summaries
+            .iter()
+            .map(|summary| parse_count(field(summary)))
+            .max()
+            .unwrap_or_default()
+    };
+    let summary = if summaries.is_empty() {
+        None
+    } else {
+        let dns_queries_today = sum(|summary| &summary.dns_queries_today);
+        let ads_blocked_today = sum(|summary| &summary.ads_blocked_today);
+        let enabled = summaries
+            .iter()
+            .filter(|summary| summary.status == "enabled")
+            .count();
+        Some(Summary {
+            domains_being_blocked: format_count(max(|summary| &summary.domains_being_blocked)),
+            dns_queries_today: format_count(dns_queries_today),
+            ads_blocked_today: format_count(ads_blocked_today),
+            ads_percentage_today: match dns_queries_today {
+                0 => "0.0".to_string(),
+                _ => format!(
+                    "{:.1}",
+                    ads_blocked_today as f64 * 100.0 / dns_queries_today as f64
+                ),
+            },
+            unique_domains: format_count(max(|summary| &summary.unique_domains)),
+            queries_forwarded: format_count(sum(|summary| &summary.queries_forwarded)),
+            queries_cached: format_count(sum(|summary| &summary.queries_cached)),
+            clients_ever_seen: format_count(sum(|summary| &summary.clients_ever_seen)),
+            unique_clients: format_count(sum(|summary| &summary.unique_clients)),
+            dns_queries_all_types: format_count(sum(|summary| &summary.dns_queries_all_types)),
+            reply_nodata: format_count(sum(|summary| &summary.reply_nodata)),
+            reply_nxdomain: format_count(sum(|summary| &summary.reply_nxdomain)),
+            reply_cname: format_count(sum(|summary| &summary.reply_cname)),
+            reply_ip: format_count(sum(|summary| &summary.reply_ip)),
+            privacy_level: max(|summary| &summary.privacy_level).to_string(),
+            status: if enabled == summaries.len() {
+                "enabled".to_string()
+            } else if enabled == 0 {
+                "disabled".to_string()
+            } else {
+                format!("{}/{} enabled", enabled, summaries.len())
+            },
+        })
+    };
+
+    let data: Vec<&PiHoleData> = servers.iter().map(|server| &server.last_data).collect();
+    let top_items = data.iter().filter_map(|data| data.top_items.as_ref()).fold(
+        None,
+        |total: Option<TopItems>, top_items| {
+            let mut total = total.unwrap_or(TopItems {
+                top_queries: HashMap::new(),
+                top_ads: HashMap::new(),
+            });
+            merge_counts(&mut total.top_queries, &top_items.top_queries);
+            merge_counts(&mut total.top_ads, &top_items.top_ads);
+            Some(total)
+        },
+    );
+    let merge_top_sources = |field: fn(&PiHoleData) -> &Option<TopClients>| {
+        data.iter().filter_map(|data| field(data).as_ref()).fold(
+            None,
+            |total: Option<TopClients>, top_sources| {
+                let mut total = total.unwrap_or(TopClients {
+                    top_sources: HashMap::new(),
+                });
+                merge_counts(&mut total.top_sources, &top_sources.top_sources);
+                Some(total)
+            },
+        )
+    };
+    let top_sources = merge_top_sources(|data| &data.top_sources);
+    let top_sources_blocked = merge_top_sources(|data| &data.top_sources_blocked);
+    // Every server reports the same 10 minute periods, so they can be summed by timestamp
+    let over_time_data = data
+        .iter()
+        .filter_map(|data| data.over_time_data.as_ref())
+        .fold(None, |total: Option<OverTimeData>, over_time_data| {
+            let mut total = total.unwrap_or(OverTimeData {
+                domains_over_time: HashMap::new(),
+                ads_over_time: HashMap::new(),
+            });
+            merge_counts(
+                &mut total.domains_over_time,
+                &over_time_data.domains_over_time,
+            );
+            merge_counts(&mut total.ads_over_time, &over_time_data.ads_over_time);
+            Some(total)
+        });
+    // Percentages are weighted by the number of queries of each server
+    let weighted_query_types: Vec<(&QueryTypes, f64)> = servers
+        .iter()
+        .filter_map(|server| {
+            let query_types = server.last_data.query_types.as_ref()?;
+            let queries = server
+                .last_data
+                .summary
+                .as_ref()
+                .map_or(0, |summary| parse_count(&summary.dns_queries_today));
+            Some((query_types, queries as f64))
+        })
+        .collect();
+    let total_queries: f64 = weighted_query_types
+        .iter()
+        .map(|(_, queries)| queries)
+        .sum();
+    let query_types = if weighted_query_types.is_empty() {
+        None
+    } else {
+        let mut querytypes: HashMap<String, f64> = HashMap::new();
+        for (query_types, queries) in &weighted_query_types {
+            // Without any queries every server counts equally
+            let weight = if total_queries > 0.0 {
+                queries / total_queries
+            } else {
+                1.0 / weighted_query_types.len() as f64
+            };
+            for (name, percentage) in &query_types.querytypes {
+                // v5 names types e.g. "A (IPv4)" and v6 only "A", combine them by record type
+                let name = name.split_whitespace().next().unwrap_or_default();
+                *querytypes.entry(name.to_string()).or_default() += percentage * weight;
             }
-        };
-        server.run_background_update();
+        }
+        Some(QueryTypes { querytypes })
+    };
+    let cache_info = data
+        .iter()
+        .filter_map(|data| data.cache_info.as_ref())
+        .fold(None, |total: Option<CacheInfo>, cache_info| {
+            let total = total.unwrap_or(CacheInfo {
+                cache_size: 0,
+                cache_live_freed: 0,
+                cache_inserted: 0,
+            });
+            Some(CacheInfo {
+                cache_size: total.cache_size + cache_info.cache_size,
+                cache_live_freed: total.cache_live_freed + cache_info.cache_live_freed,
+                cache_inserted: total.cache_inserted + cache_info.cache_inserted,
+            })
+        });
+
+    PiHoleData {
+        summary,
+        top_sources,
+        top_sources_blocked,
+        top_items,
+        over_time_data,
+        // The query log of the All tab is merged from the servers when drawn
+        queries: None,
+        query_types,
+        disable_timer: None,
+        cache_info,
+        status_counts: data
+            .iter()
+            .filter_map(|data| data.status_counts.as_ref())
+            .fold(None, |total: Option<HashMap<StatusGroup, u64>>, counts| {
+                let mut total = total.unwrap_or_default();
+                for (group, count) in counts {
+                    *total.entry(*group).or_default() += count;
+                }
+                Some(total)
+            }),
+        versions: None,
+        domain_lists: None,
+        adlists: None,
+        local_records: None,
+        clients_over_time: aggregate_clients_over_time(&data),
+        long_term: aggregate_long_term(&data),
+        long_term_recorded: data.iter().any(|data| data.long_term_recorded),
+        // Merged from the servers when drawn, as the query log
+        filtered_queries: None,
+        dns_health: None,
+        // The busiest server
+        load: data
+            .iter()
+            .filter_map(|data| data.load)
+            .reduce(|busiest, load| if load[0] > busiest[0] { load } else { busiest }),
+        network_devices: None,
+        dhcp_leases: None,
     }
 }
 
-impl From<PimonConfig> for App {
-    fn from(config: PimonConfig) -> Self {
-        App {
-            selected_server_index: 0,
-            update_delay: config.update_delay,
-            graph_squash_factor: 1,
-            servers: config
-                .servers
-                .iter()
-                .map(|server| {
-                    PiHoleServer::new(
-                        server.name.clone(),
-                        server.host.clone(),
-                        server.api_key.clone(),
-                        Duration::from_millis(config.update_delay),
-                    )
-                })
-                .collect(),
+/// Sums the daily totals of the servers, their days start at the same local midnight
+fn aggregate_long_term(data: &[&PiHoleData]) -> Option<Vec<DayTotals>> {
+    let mut days: BTreeMap<i64, DayTotals> = BTreeMap::new();
+    let mut any = false;
+    for long_term in data.iter().filter_map(|data| data.long_term.as_ref()) {
+        any = true;
+        for day in long_term {
+            let total = days.entry(day.start).or_insert(DayTotals {
+                start: day.start,
+                queries: 0,
+                blocked: 0,
+            });
+            total.queries += day.queries;
+            total.blocked += day.blocked;
+        }
+    }
+    any.then(|| days.into_values().collect())
+}
+
+/// Sums the activity of clients with the same name on different servers by period
+fn aggregate_clients_over_time(data: &[&PiHoleData]) -> Option<ClientsOverTime> {
+    let mut clients: Vec<String> = Vec::new();
+    let mut over_time: BTreeMap<i64, Vec<u64>> = BTreeMap::new();
+    let mut any = false;
+    for clients_over_time in data
+        .iter()
+        .filter_map(|data| data.clients_over_time.as_ref())
+    {
+        any = true;
+        let indices: Vec<usize> = clients_over_time
+            .clients
+            .iter()
+            .map(
+                |client| match clients.iter().position(|name| name == client) {
+                    Some(index) => index,
+                    None => {
+                        clients.push(client.clone());
+                        clients.len() - 1
+                    }
+                },
+            )
+            .collect();
+        for (timestamp, counts) in &clients_over_time.over_time {
+            let total = over_time.entry(*timestamp).or_default();
+            for (index, count) in indices.iter().zip(counts) {
+                if total.len() <= *index {
+                    total.resize(index + 1, 0);
+                }
+                total[*index] += count;
+            }
         }
     }
+    any.then(|| ClientsOverTime {
+        over_time: over_time
+            .into_iter()
+            .map(|(timestamp, mut counts)| {
+                counts.resize(clients.len(), 0);
+                (timestamp, counts)
+            })
+            .collect(),
+        clients,
+    })
 }
 
-#[derive(Debug, Deserialize)]
-struct PimonConfig {
-    servers: Vec<PiHoleServerConfig>,
-    update_delay: u64,
+/// Formats a duration for display e.g. `1h 05m`, `4m 12s` or `12s`
+/// Time since `since`, zero if it is in the future
+fn elapsed_since(since: DateTime<Local>) -> Duration {
+    (Local::now() - since).to_std().unwrap_or_default()
 }
 
-#[derive(Debug, Deserialize)]
-struct PiHoleServerConfig {
-    name: String,
-    host: String,
-    api_key: Option<String>,
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, (seconds % 3600) / 60, seconds % 60) {
+        (0, 0, seconds) => format!("{}s", seconds),
+        (0, minutes, seconds) => format!("{}m {:02}s", minutes, seconds),
+        (hours, minutes, _) => format!("{}h {:02}m", hours, minutes),
+    }
 }
 
-pub fn load_server_from_json(path: &PathBuf) -> Result<App, Box<dyn Error>> {
-    let f = File::open(path).expect("Configuration file not found");
-    let pimon_config: PimonConfig = serde_json::from_reader(&f)?;
-    Ok(App::from(pimon_config))
+/// Parses a duration such as `90`, `30s`, `5m`, `1h30m` or `2d` into seconds, `0` means permanently
+pub fn parse_duration(duration: &str) -> Result<u64, String> {
+    let duration = duration.trim();
+    let invalid = || {
+        format!(
+            "Invalid duration \"{}\", use e.g. 30s, 5m, 1h or 0 for permanently",
+            duration
+        )
+    };
+    if duration.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    for c in duration.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = value
+            .checked_mul(unit)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = seconds.checked_add(value).ok_or_else(invalid)?;
+    }
+    Ok(seconds)
 }
 
 pub fn order_convert_string_num_map(map: &HashMap<String, u64>) -> Vec<Vec<String>> {
@@ -256,30 +6066,367 @@ pub fn order_convert_string_num_map(map: &HashMap<String, u64>) -> Vec<Vec<Strin
         .collect()
 }
 
-fn background_update(tx: mpsc::Sender<Option<PiHoleData>>, host: String, api_key: Option<String>) {
-    let api_config = PiHoleConfigImplementation::new(host, api_key);
-
-    tx.send(Some(PiHoleData {
-        summary: api_config
-            .get_unauthenticated_api()
-            .and_then(|api| api.get_summary().ok()),
-        top_sources: api_config
-            .get_authenticated_api()
-            .and_then(|api| api.get_top_clients(Some(25)).ok()),
-        top_items: api_config
-            .get_authenticated_api()
-            .and_then(|api| api.get_top_items(Some(25)).ok()),
-        over_time_data: api_config
-            .get_unauthenticated_api()
-            .and_then(|api| api.get_over_time_data_10_mins().ok()),
-    }))
-    .unwrap();
-}
-
-pub fn squash_queries_over_time(
-    queries: &Vec<(i64, u64)>,
-    squash_factor: usize,
-) -> Vec<(i64, u64)> {
+pub fn order_string_percentage_map(map: &HashMap<String, f64>) -> Vec<(String, f64)> {
+    let mut selected_items: Vec<(String, f64)> = map
+        .iter()
+        .map(|(name, percentage)| (name.clone(), *percentage))
+        .collect();
+    selected_items.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    selected_items
+}
+
+/// Makes every request to the server `name` for as long as it is shown, sharing the HTTP
+/// client and its connections of `api_config` between them
+async fn run_worker(
+    api_config: Arc<PiHoleConfigImplementation>,
+    name: String,
+    mut commands: UnboundedReceiver<WorkerCommand>,
+    responses: WorkerResponses,
+    data_arrived: Option<mpsc::Sender<CustomEvent>>,
+) {
+    let notify = || {
+        if let Some(data_arrived) = &data_arrived {
+            let _ = data_arrived.send(CustomEvent::DataArrived);
+        }
+    };
+    let mut update = None;
+    loop {
+        let next = match &mut update {
+            Some((id, in_flight)) => tokio::select! {
+                finished = in_flight => Err((*id, finished)),
+                command = commands.recv() => Ok(command),
+            },
+            None => Ok(commands.recv().await),
+        };
+        match next {
+            Ok(Some(WorkerCommand::Update {
+                id,
+                optional,
+                top_items_count,
+                dns_probe,
+            })) => {
+                update = Some((
+                    id,
+                    Box::pin(background_update(
+                        &api_config,
+                        optional,
+                        top_items_count,
+                        dns_probe,
+                        &name,
+                    )),
+                ));
+            }
+            // The update in flight waits meanwhile, it is replaced once the change is made
+            Ok(Some(WorkerCommand::ChangeBlocking(change))) => {
+                let result = match change {
+                    BlockingChange::Enable => api_config.enable().await,
+                    BlockingChange::Disable(seconds) => api_config.disable(seconds).await,
+                };
+                let _ = responses.blocking_changes.send(result);
+                notify();
+            }
+            Ok(Some(WorkerCommand::Change(change))) => {
+                let result = match &change {
+                    ServerChange::AddDomain { domain, list } => {
+                        api_config.list_add(domain, *list).await
+                    }
+                    ServerChange::RemoveDomain { domain, list } => {
+                        api_config.list_remove(domain, *list).await
+                    }
+                    ServerChange::AddAdlist { address } => api_config.adlist_add(address).await,
+                    ServerChange::SetAdlistEnabled { adlist, enabled } => {
+                        api_config.adlist_set_enabled(adlist, *enabled).await
+                    }
+                    ServerChange::AddLocalRecord {
+                        record_type,
+                        record,
+                    } => api_config.local_record_add(*record_type, record).await,
+                    ServerChange::RemoveLocalRecord {
+                        record_type,
+                        record,
+                    } => api_config.local_record_remove(*record_type, record).await,
+                };
+                let _ = responses.changes.send((change, result));
+                notify();
+            }
+            Ok(Some(WorkerCommand::Logout {
+                logged_out,
+                ssh_tunnel,
+            })) => {
+                api_config.logout().await;
+                drop(ssh_tunnel);
+                let _ = logged_out.send(());
+                return;
+            }
+            Ok(None) => return,
+            Err(finished) => {
+                update = None;
+                if responses.updates.send(finished).is_err() {
+                    return;
+                }
+                notify();
+            }
+        }
+    }
+}
+
+async fn background_update(
+    api_config: &PiHoleConfigImplementation,
+    optional: OptionalData,
+    top_items_count: u32,
+    dns_probe: Option<(String, DnsProbeConfig)>,
+    name: &str,
+) -> FinishedUpdate {
+    let started = Instant::now();
+    let requests = UpdateRequests {
+        name,
+        timings: Mutex::new(Vec::new()),
+    };
+    let dns_health = async {
+        match &dns_probe {
+            Some((host, config)) => {
+                let dns_health = dns_probe::probe(host, config).await;
+                match &dns_health {
+                    DnsHealth::Ok => log::trace!("{}: DNS probe succeeded", name),
+                    DnsHealth::NotBlocking => {
+                        log::warn!("{}: {} was not blocked", name, config.blocked_domain)
+                    }
+                    DnsHealth::Failed(error) => log::warn!("{}: DNS probe failed: {}", name, error),
+                }
+                Some(dns_health)
+            }
+            None => None,
+        }
+    };
+    // All endpoints are requested concurrently, along with the DNS probe
+    let (
+        summary,
+        top_sources,
+        top_items,
+        over_time_data,
+        queries,
+        query_types,
+        disable_timer,
+        cache_info,
+        status_counts,
+        versions,
+        load,
+        dns_health,
+    ) = tokio::join!(
+        requests.time("summary", api_config.get_summary()),
+        requests.time("top clients", api_config.get_top_clients(top_items_count)),
+        requests.time("top items", api_config.get_top_items(top_items_count)),
+        requests.time("over time data", api_config.get_over_time_data_10_mins()),
+        requests.time("queries", api_config.get_all_queries(QUERY_LOG_LENGTH)),
+        requests.time("query types", api_config.get_query_types()),
+        requests.time("disable timer", api_config.get_disable_timer()),
+        requests.time("cache info", api_config.get_cache_info()),
+        requests.time("query statuses", api_config.get_status_counts()),
+        requests.time("versions", api_config.get_versions()),
+        requests.time("system load", api_config.get_system_load()),
+        dns_health,
+    );
+    let domain_lists = if optional.domain_lists {
+        tokio::try_join!(
+            requests.time("whitelist", api_config.get_list(DomainList::ALL[0])),
+            requests.time("regex whitelist", api_config.get_list(DomainList::ALL[1])),
+            requests.time("blacklist", api_config.get_list(DomainList::ALL[2])),
+            requests.time("regex blacklist", api_config.get_list(DomainList::ALL[3])),
+        )
+        .ok()
+        .map(|(first, second, third, fourth)| vec![first, second, third, fourth])
+    } else {
+        None
+    };
+    let local_records = if optional.local_records {
+        tokio::try_join!(
+            requests.time(
+                "local DNS records",
+                api_config.get_local_records(LocalRecordType::Dns)
+            ),
+            requests.time(
+                "local CNAME records",
+                api_config.get_local_records(LocalRecordType::Cname)
+            ),
+        )
+        .ok()
+        .map(|(dns, cname)| vec![dns, cname])
+    } else {
+        None
+    };
+    let clients_over_time = if optional.clients_over_time {
+        requests
+            .time("clients over time", api_config.get_clients_over_time())
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let adlists = if optional.adlists {
+        requests
+            .time("adlists", api_config.get_adlists())
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    let network_devices = if optional.network_devices {
+        requests
+            .time("network devices", api_config.get_network_devices())
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let dhcp_leases = if optional.dhcp_leases {
+        requests
+            .time("DHCP leases", api_config.get_dhcp_leases())
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    let top_sources_blocked = if optional.top_clients_blocked {
+        requests
+            .time(
+                "top clients blocked",
+                api_config.get_top_clients_blocked(top_items_count),
+            )
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let filtered_queries = match &optional.filtered_queries {
+        Some(filter) => requests
+            .time(
+                "filtered queries",
+                api_config.get_filtered_queries(filter, FILTERED_QUERIES_LENGTH),
+            )
+            .await
+            .ok(),
+        None => None,
+    };
+    let mut long_term_recorded = false;
+    let long_term = match optional.long_term_days {
+        Some(days) => match (
+            requests
+                .time("long term data", api_config.get_long_term(days))
+                .await,
+            optional.long_term_history,
+        ) {
+            (Ok(long_term), _) => Some(long_term),
+            (Err(_), Some(history)) => {
+                let from = long_term_from(days);
+                let recorded =
+                    tokio::task::spawn_blocking(move || history.day_totals(from, days)).await;
+                match recorded {
+                    Ok(Ok(long_term)) => {
+                        long_term_recorded = true;
+                        Some(long_term)
+                    }
+                    Ok(Err(error)) => {
+                        log::warn!("Could not read the history of {}: {}", name, error);
+                        None
+                    }
+                    Err(_) => None,
+                }
+            }
+            (Err(_), None) => None,
+        },
+        None => None,
+    };
+    let data = PiHoleData {
+        summary: summary.ok(),
+        top_sources: top_sources.ok(),
+        top_sources_blocked,
+        top_items: top_items.ok(),
+        over_time_data: over_time_data.ok(),
+        queries: queries.ok(),
+        query_types: query_types.ok(),
+        disable_timer: disable_timer.ok().flatten(),
+        cache_info: cache_info.ok(),
+        status_counts: status_counts.ok().flatten(),
+        versions: versions.ok(),
+        domain_lists,
+        adlists,
+        local_records,
+        clients_over_time,
+        long_term,
+        long_term_recorded,
+        filtered_queries,
+        dns_health,
+        load: load.ok().flatten(),
+        network_devices,
+        dhcp_leases,
+    };
+    let timings = requests.timings.into_inner().unwrap_or_default();
+    FinishedUpdate {
+        data,
+        timings,
+        duration: started.elapsed(),
+    }
+}
+
+/// Times and logs the requests of one update of the server `name`
+struct UpdateRequests<'a> {
+    name: &'a str,
+    timings: Mutex<Vec<RequestTiming>>,
+}
+
+impl UpdateRequests<'_> {
+    /// Awaits `request` to `endpoint`, recording how long it took and logging failures and
+    /// slow responses
+    async fn time<T>(
+        &self,
+        endpoint: &'static str,
+        request: impl Future<Output = Result<T, APIError>>,
+    ) -> Result<T, APIError> {
+        let started = Instant::now();
+        let result = request.await;
+        let duration = started.elapsed();
+        let name = self.name;
+        let error = match &result {
+            // Expected of every authenticated endpoint of a server without a key, nothing
+            // was requested
+            Err(APIError::MissingAPIKey) => {
+                log::debug!("{}: {} requires an API key", name, endpoint);
+                return result;
+            }
+            Err(error) => {
+                let error = describe_api_error(error);
+                log::warn!(
+                    "{}: {} failed after {:?}: {}",
+                    name,
+                    endpoint,
+                    duration,
+                    error
+                );
+                Some(error)
+            }
+            Ok(_) if duration > SLOW_REQUEST => {
+                log::warn!("{}: {} was slow, {:?}", name, endpoint, duration);
+                None
+            }
+            Ok(_) => {
+                log::trace!("{}: {} took {:?}", name, endpoint, duration);
+                None
+            }
+        };
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.push(RequestTiming {
+                endpoint,
+                duration,
+                error,
+            });
+        }
+        result
+    }
+}
+
+pub fn squash_queries_over_time(queries: &[(i64, u64)], squash_factor: usize) -> Vec<(i64, u64)> {
     let mut squashed = Vec::new();
     let mut count = 0;
     let mut sum = 0;
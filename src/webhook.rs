@@ -0,0 +1,98 @@
+use crate::alerts::Alert;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// How long a webhook is given to accept an alert
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Entry of the `webhooks` list of the config, each raised alert is POSTed to every entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+/// Shape of the JSON body, matching what each service expects of an incoming webhook
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// The fields of the alert
+    #[default]
+    Json,
+    /// `{"text": ...}`, also accepted by Mattermost and Rocket.Chat
+    Slack,
+    /// `{"content": ...}`
+    Discord,
+    /// `{"title": ..., "message": ..., "priority": ...}`
+    Gotify,
+}
+
+/// The configured webhooks with the client they are called with
+pub struct Webhooks {
+    pub config: Vec<WebhookConfig>,
+    client: Client,
+}
+
+impl Webhooks {
+    /// Checks the URL of every webhook, which like the servers must be http:// or https://
+    pub fn new(config: Vec<WebhookConfig>) -> Result<Self, String> {
+        for webhook in &config {
+            let url = Url::parse(&webhook.url)
+                .map_err(|error| format!("Invalid webhook {}: {}", webhook.url, error))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(format!(
+                    "Invalid webhook {}, it must begin with http:// or https://",
+                    webhook.url
+                ));
+            }
+        }
+        let client = Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|error| format!("Could not create the HTTP client: {}", error))?;
+        Ok(Webhooks { config, client })
+    }
+
+    /// POSTs `alert` to every webhook in the background, failures are only logged
+    pub fn send(&self, alert: &Alert) {
+        for webhook in &self.config {
+            let request = self
+                .client
+                .post(&webhook.url)
+                .json(&payload(webhook.format, alert));
+            let url = webhook.url.clone();
+            tokio::spawn(async move {
+                match request
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                {
+                    Ok(_) => log::debug!("Sent alert to webhook {}", url),
+                    Err(error) => log::warn!("Could not send alert to webhook {}: {}", url, error),
+                }
+            });
+        }
+    }
+}
+
+fn payload(format: WebhookFormat, alert: &Alert) -> Value {
+    let text = format!("pimon: {}: {}", alert.server, alert.message);
+    match format {
+        WebhookFormat::Json => json!({
+            "server": alert.server,
+            "alert": format!("{:?}", alert.kind),
+            "message": alert.message,
+            "raised_at": alert.raised_at.to_rfc3339(),
+        }),
+        WebhookFormat::Slack => json!({ "text": text }),
+        WebhookFormat::Discord => json!({ "content": text }),
+        WebhookFormat::Gotify => json!({
+            "title": format!("pimon: {}", alert.server),
+            "message": alert.message,
+            "priority": 5,
+        }),
+    }
+}